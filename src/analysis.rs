@@ -0,0 +1,168 @@
+/* analysis.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::{glib, glib::Sender};
+use gst::prelude::*;
+use gtk_macros::send;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashSet, VecDeque},
+    error::Error,
+    thread,
+};
+use log::{debug, error};
+
+use super::database::DatabaseAction;
+
+//Loudness & tempo measured in a track, None when the value could not be measured
+#[derive(Clone, Debug, Default)]
+pub struct TrackAnalysis {
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub bpm: Option<f64>,
+}
+
+//Queue of the tracks missing replaygain or bpm tags, each one analyzed by gstreamer in its own thread
+#[derive(Debug)]
+pub struct LibraryAnalyzer {
+    queue: RefCell<VecDeque<(i64, String)>>,
+    running: RefCell<HashSet<i64>>,
+    max_jobs: Cell<usize>,
+    paused: Cell<bool>,
+    sender: Sender<DatabaseAction>,
+}
+
+impl LibraryAnalyzer {
+    pub fn new(sender: Sender<DatabaseAction>, max_jobs: usize, paused: bool) -> Self {
+        Self {
+            queue: RefCell::new(VecDeque::new()),
+            running: RefCell::new(HashSet::new()),
+            max_jobs: Cell::new(max_jobs.max(1)),
+            paused: Cell::new(paused),
+            sender,
+        }
+    }
+
+    //Replaces the waiting tracks, the tracks being analyzed are not queued again
+    pub fn set_queue(&self, tracks: Vec<(i64, String)>) {
+        let queue = {
+            let running = self.running.borrow();
+            tracks.into_iter()
+                .filter(|(track_id, _)| !running.contains(track_id))
+                .collect::<VecDeque<(i64, String)>>()
+        };
+        debug!("{} tracks queued for analysis", queue.len());
+        self.queue.replace(queue);
+        self.start_jobs();
+    }
+
+    pub fn set_max_jobs(&self, max_jobs: usize) {
+        self.max_jobs.set(max_jobs.max(1));
+        self.start_jobs();
+    }
+
+    //Running jobs are left to finish, no new ones start until resumed
+    pub fn pause(&self) {
+        self.paused.set(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.set(false);
+        self.start_jobs();
+    }
+
+    pub fn job_done(&self, track_id: i64) {
+        self.running.borrow_mut().remove(&track_id);
+        self.start_jobs();
+    }
+
+    //No job running & none about to start
+    pub fn is_idle(&self) -> bool {
+        self.running.borrow().is_empty() && (self.paused.get() || self.queue.borrow().is_empty())
+    }
+
+    fn start_jobs(&self) {
+        while !self.paused.get() && self.running.borrow().len() < self.max_jobs.get() {
+            let (track_id, path) = match self.queue.borrow_mut().pop_front() {
+                Some(track) => track,
+                None => break,
+            };
+
+            self.running.borrow_mut().insert(track_id);
+            let sender = self.sender.clone();
+
+            thread::spawn(move || {
+                let analysis = match analyze_file(&path) {
+                    Ok(analysis) => analysis,
+                    Err(e) => {
+                        error!("Unable to analyze {}: {}", path, e);
+                        TrackAnalysis::default()
+                    },
+                };
+                send!(sender, DatabaseAction::AddTrackAnalysis((track_id, analysis)));
+            });
+        }
+    }
+}
+
+//Decodes the whole file as fast as possible, rganalysis & bpmdetect post their results as tags
+fn analyze_file(path: &str) -> Result<TrackAnalysis, Box<dyn Error>> {
+    let uri = glib::filename_to_uri(path, None)?;
+
+    //bpmdetect comes from the soundtouch plugin, which is often not installed
+    let bpmdetect = if gst::ElementFactory::find("bpmdetect").is_some() { " ! bpmdetect" } else { "" };
+    let description = format!(
+        "uridecodebin name=decode caps=audio/x-raw ! audioconvert ! audioresample ! rganalysis name=rganalysis{} ! fakesink sync=false",
+        bpmdetect
+    );
+
+    let pipeline = gst::parse_launch(&description)?
+        .downcast::<gst::Pipeline>()
+        .map_err(|_| "analysis pipeline is not a pipeline")?;
+    let decode = pipeline.by_name("decode").ok_or("analysis pipeline has no decoder")?;
+    decode.set_property("uri", uri.as_str());
+
+    let bus = pipeline.bus().ok_or("analysis pipeline has no bus")?;
+    pipeline.set_state(gst::State::Playing)?;
+
+    let mut analysis = TrackAnalysis::default();
+    let mut result: Result<(), String> = Ok(());
+
+    for message in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match message.view() {
+            MessageView::Tag(tag) => {
+                let tags = tag.tags();
+                //tags of the file pass through the sink too, only the measured gain is kept
+                let from_rganalysis = message.src().map(|src| src.name() == "rganalysis").unwrap_or(false);
+                if from_rganalysis {
+                    if let Some(gain) = tags.get::<gst::tags::TrackGain>() {
+                        analysis.track_gain = Some(gain.get());
+                    }
+                    if let Some(peak) = tags.get::<gst::tags::TrackPeak>() {
+                        analysis.track_peak = Some(peak.get());
+                    }
+                }
+                if let Some(bpm) = tags.get::<gst::tags::BeatsPerMinute>() {
+                    analysis.bpm = Some(bpm.get());
+                }
+            },
+            MessageView::Eos(_) => break,
+            MessageView::Error(err) => {
+                result = Err(format!("{} ({:?})", err.error(), err.debug()));
+                break;
+            },
+            _ => (),
+        }
+    }
+
+    pipeline.set_state(gst::State::Null)?;
+    result?;
+
+    Ok(analysis)
+}
@@ -16,6 +16,7 @@ use crate::database::Database;
 use crate::model::model::Model;
 use crate::player::player::Player;
 use crate::views::preferences_window::PreferencesWindow;
+use crate::views::statistics_window::StatisticsWindow;
 use crate::i18n::i18n;
 
 mod imp {
@@ -113,7 +114,11 @@ impl App {
             .activate(move |app: &Self, _, _| app.show_preferences())
             .build();
 
-        self.add_action_entries([quit_action, about_action, preferences_action]);
+        let statistics_action = gio::ActionEntry::builder("statistics")
+            .activate(move |app: &Self, _, _| app.show_statistics())
+            .build();
+
+        self.add_action_entries([quit_action, about_action, preferences_action, statistics_action]);
     }
 
     fn show_about(&self) {
@@ -145,6 +150,13 @@ impl App {
         preferences.show();
     }
 
+    fn show_statistics(&self) {
+        let statistics = StatisticsWindow::new();
+        let window = self.active_window().unwrap();
+        statistics.set_transient_for(Some(&window));
+        statistics.show();
+    }
+
     pub fn model(&self) -> Rc<Model> {
         self.imp().model.clone()
     }
@@ -0,0 +1,128 @@
+/* backup.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use serde::{Deserialize, Serialize};
+
+use std::{error::Error, fs, path::{Path, PathBuf}};
+use directories_next::BaseDirs;
+use log::{debug, error};
+
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+//Number of automatic backups kept, older ones are deleted
+const AUTO_BACKUPS_KEPT: usize = 5;
+
+//A track is identified by its path first, then by its tags if the file moved
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrackRef {
+    pub path: String,
+    pub title: String,
+    pub album: String,
+    pub album_artist: String,
+    pub disc_number: i64,
+    pub track_number: i64,
+    pub duration: f64,
+}
+
+impl TrackRef {
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.title, &self.album, &self.album_artist, self.disc_number, self.track_number)
+    }
+}
+
+pub fn fingerprint(title: &str, album: &str, album_artist: &str, disc_number: i64, track_number: i64) -> String {
+    format!("{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}", title.trim().to_lowercase(), album.trim().to_lowercase(), album_artist.trim().to_lowercase(), disc_number, track_number)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlaylistBackup {
+    pub title: String,
+    pub description: String,
+    pub creation_time: i64,
+    pub modify_time: i64,
+    //indices into LibraryBackup.tracks, in playlist order
+    pub tracks: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayBackup {
+    pub playtime: i64,
+    pub track: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LibraryBackup {
+    pub version: u32,
+    pub created: i64,
+    pub tracks: Vec<TrackRef>,
+    pub playlists: Vec<PlaylistBackup>,
+    pub plays: Vec<PlayBackup>,
+}
+
+impl LibraryBackup {
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        debug!("wrote backup to {:?}", path);
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<LibraryBackup, Box<dyn Error>> {
+        let json = fs::read_to_string(path)?;
+        let backup: LibraryBackup = serde_json::from_str(&json)?;
+        if backup.version > BACKUP_FORMAT_VERSION {
+            return Err(format!("backup format {} is newer than this version of Resonance", backup.version).into());
+        }
+        Ok(backup)
+    }
+}
+
+pub fn backup_dir() -> PathBuf {
+    if let Some(base_dirs) = BaseDirs::new() {
+        base_dirs.data_dir().join("io.github.nate_xyz.Resonance").join("backups")
+    } else {
+        PathBuf::from("backups")
+    }
+}
+
+//Location of a new automatic backup, older automatic backups past the limit are removed
+pub fn auto_backup_path() -> PathBuf {
+    let dir = backup_dir();
+    prune_auto_backups(&dir);
+    let stamp = chrono::offset::Utc::now().format("%Y%m%d-%H%M%S");
+    dir.join(format!("auto-{}.json", stamp))
+}
+
+fn prune_auto_backups(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut backups: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with("auto-") && name.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    //the timestamp in the name sorts chronologically
+    backups.sort();
+
+    while backups.len() >= AUTO_BACKUPS_KEPT {
+        let oldest = backups.remove(0);
+        if let Err(e) = fs::remove_file(&oldest) {
+            error!("Unable to remove old backup {:?}: {}", oldest, e);
+        }
+    }
+}
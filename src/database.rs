@@ -14,21 +14,34 @@ use std::{
     collections::{HashMap, HashSet}, 
     time::Duration, 
     error::Error, 
-    path::PathBuf, 
+    path::{Path, PathBuf}, 
     rc::Rc, 
     env, 
     fs, 
     fmt, 
     thread,
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
 };
 use rusqlite::{Connection, Result, Transaction, OptionalExtension, params};
 use chrono::{DateTime, Utc};
 use directories_next::BaseDirs; 
 use log::{debug, error};
 
-use crate::model::{track::Track, model::ModelAction};
-
-use super::importer::{Importer, MapVal};
+use crate::model::{track::Track, model::ModelAction, cover_art_cache};
+use crate::views::dialog::database_error_dialog::DatabaseErrorDialog;
+use crate::views::dialog::playlist_import_report_dialog::PlaylistImportReportDialog;
+
+use super::importer::{self, Importer, MapVal};
+use super::watcher::LibraryWatcher;
+use super::analysis::{LibraryAnalyzer, TrackAnalysis};
+use super::duplicates::FormatPolicy;
+use super::statistics::{ListeningStats, PlayStats, StatsPeriod, StatsRange, TOP_LIMIT, PERIOD_LIMIT};
+use super::rating::Rating;
+use super::smart_playlist::SmartRules;
+use super::playlist_file::{LibraryTrack, PlaylistFile, TrackMatcher};
+use super::import_progress::{ImportProblem, ImportProgress, ImportState, ImportStatus};
+use super::migrations::{self, MigrationError};
+use super::backup::{self, LibraryBackup, PlaylistBackup, PlayBackup, TrackRef};
 use super::toasts::{add_error_toast, add_success_toast};
 use super::i18n::{i18n, i18n_k};
 use super::util;
@@ -38,6 +51,14 @@ pub enum DatabaseAction {
     TryLoadingDataBase,
     TryAddMusicFolder(PathBuf),
     ConstructFromTags((String, HashMap<String, HashMap<String, HashMap<String, MapVal>>>, HashMap<String, Vec<u8>>)),
+    RescanLibrary,
+    ApplyRescan((String, Vec<String>, HashMap<String, HashMap<String, HashMap<String, MapVal>>>, HashMap<String, Vec<u8>>)),
+    QueueFileChange((String, String)),
+    BackupLibrary(PathBuf),
+    RestoreLibrary(PathBuf),
+    ImportPlaylistFile(PathBuf),
+    ImportFolderPlaylists((String, Vec<PlaylistFile>)),
+    ApplyFileChanges,
     AddArtistImages(Vec<(i64, Option<(String, Vec<u8>)>)>),
     AddArtistImage((i64, Option<(String, Vec<u8>)>)),
     AddArtistImagesDone(u32),
@@ -45,11 +66,19 @@ pub enum DatabaseAction {
     DuplicatePlaylist((String, String, Vec<i64>)),
     RenamePlaylist((i64, String, String)),
     RemoveDirectory(String),
+    RelocateFolder((String, PathBuf)),
     DeletePlaylist(i64),
     ChangePlaylistTitleAndOrDescription((i64, Option<String>, Option<String>)),
     AddTracksToPlaylist((i64, String, Vec<i64>)),
     RemoveTrackFromPlaylist(i64),
-    ReorderPlaylist((i64, usize, usize))
+    ReorderPlaylist((i64, usize, usize)),
+    CreateSmartPlaylist((String, String, SmartRules)),
+    UpdateSmartPlaylist((i64, String, String, SmartRules)),
+    DeleteSmartPlaylist(i64),
+    AddTrackAnalysis((i64, TrackAnalysis)),
+    ImportProgress((String, ImportProgress)),
+    CancelImport(String),
+    SetAlbumFormatPolicy((String, Option<FormatPolicy>)),
 }
 
 #[derive(Debug)]
@@ -64,7 +93,7 @@ impl Error for DatabaseError {}
 mod imp {
     use super::*;
     use glib::{
-        Value, ParamSpec, ParamSpecBoolean
+        Value, ParamSpec, ParamSpecBoolean, subclass::Signal
     };
     use once_cell::sync::Lazy;
 
@@ -75,11 +104,19 @@ mod imp {
         pub loaded: Cell<bool>,
         pub conn: RefCell<Option<Connection>>,
         pub importer: Importer,
+        pub watcher: LibraryWatcher,
+        pub analyzer: LibraryAnalyzer,
+        pub analyzed_tracks: RefCell<Vec<u64>>,
         pub model_sender: Sender<ModelAction>,
         pub model_receiver: RefCell<Option<Receiver<ModelAction>>>,
         pub db_sender: Sender<DatabaseAction>,
         pub db_receiver: RefCell<Option<Receiver<DatabaseAction>>>,
         pub import_start_time: RefCell<Option<DateTime<Utc>>>,
+        pub imports: RefCell<Vec<ImportStatus>>,
+        pub import_cancel: RefCell<Option<Arc<AtomicBool>>>,
+        pub rescan_folders: RefCell<HashSet<String>>,
+        pub rescan_changed: Cell<usize>,
+        pub rescan_failed: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -95,17 +132,32 @@ mod imp {
             let (db_sender, r) = glib::MainContext::channel(glib::PRIORITY_LOW);
             let db_receiver = RefCell::new(Some(r));
 
+            let settings = util::settings_manager();
+            let analyzer = LibraryAnalyzer::new(
+                db_sender.clone(),
+                settings.int("analysis-jobs") as usize,
+                !settings.boolean("analyze-library"),
+            );
+
             Self {
-                settings: util::settings_manager(),
+                settings,
                 folders: RefCell::new(HashSet::new()),
                 loaded: Cell::new(false),
                 conn: RefCell::new(None),
                 importer: Importer::new(),
+                watcher: LibraryWatcher::new(db_sender.clone()),
+                analyzer,
+                analyzed_tracks: RefCell::new(Vec::new()),
                 model_sender,
                 model_receiver,
                 db_sender,
                 db_receiver,
                 import_start_time: RefCell::new(None),
+                imports: RefCell::new(Vec::new()),
+                import_cancel: RefCell::new(None),
+                rescan_folders: RefCell::new(HashSet::new()),
+                rescan_changed: Cell::new(0),
+                rescan_failed: Cell::new(false),
             }
         }
     }
@@ -115,6 +167,17 @@ mod imp {
             self.parent_constructed();
         }
 
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![
+                    Signal::builder("import-progress").param_types([String::static_type()]).build(),
+                    Signal::builder("import-report-changed").build(),
+                ]
+            });
+
+            SIGNALS.as_ref()
+        }
+
         fn properties() -> &'static [ParamSpec] {
             static PROPERTIES: Lazy<Vec<ParamSpec>> =
                 Lazy::new(|| vec![
@@ -184,9 +247,104 @@ impl Database {
     fn process_action(&self, action: DatabaseAction) -> glib::Continue {
         match action {
             DatabaseAction::ConstructFromTags((folder, tags, cover_art_bytes)) => {
-                match self.construct_from_tags(folder, tags, cover_art_bytes) {
+                match self.construct_from_tags(folder.clone(), tags, cover_art_bytes) {
+                    Ok(_) => (),
+                    Err(e) => {
+                        error!("Unable to contrust database from tags: {}", e);
+                        add_error_toast(i18n("Unable to import music folder."));
+                        self.stop_import(&folder);
+                        self.set_import_state(&folder, ImportState::Failed);
+                    },
+                }
+            },
+            DatabaseAction::ImportProgress((folder, progress)) => {
+                self.import_progress(folder, progress);
+            },
+            DatabaseAction::CancelImport(folder) => {
+                self.cancel_import(&folder);
+            },
+            DatabaseAction::SetAlbumFormatPolicy((album_key, policy)) => {
+                match self.set_album_format_policy(&album_key, policy) {
+                    Ok(_) => send!(self.imp().model_sender, ModelAction::PopulateAll),
+                    Err(e) => {
+                        error!("Unable to set format policy of {}: {}", album_key, e);
+                        add_error_toast(i18n("Unable to change the preferred format."));
+                    },
+                }
+            },
+            DatabaseAction::RescanLibrary => {
+                match self.rescan_library() {
+                    Ok(_) => (),
+                    Err(e) => {
+                        error!("{}", e);
+                        add_error_toast(i18n("Unable to rescan library."));
+                    },
+                }
+            },
+            DatabaseAction::ApplyRescan((folder, removed, tags, cover_art_bytes)) => {
+                let result = self.apply_rescan(folder.clone(), removed, tags, cover_art_bytes);
+                if let Err(e) = result.as_ref() {
+                    error!("Unable to apply rescan of {}: {}", folder, e);
+                }
+                self.finish_rescan(&folder, result.ok());
+            },
+            DatabaseAction::BackupLibrary(path) => {
+                match self.backup_library(path.clone()) {
+                    Ok(_) => {
+                        // Translators: do not replace {backup_file}
+                        add_success_toast(&i18n("Saved:"), &i18n_k("Playlists & plays backed up to {backup_file}.", &[("backup_file", &format!("{}", path.display()))]))
+                    },
+                    Err(e) => {
+                        error!("Unable to back up library: {}", e);
+                        add_error_toast(i18n("Unable to back up playlists & plays."));
+                    },
+                }
+            },
+            DatabaseAction::RestoreLibrary(path) => {
+                match self.restore_library(path) {
+                    Ok((n_playlists, n_plays, n_unmatched)) => {
+                        // Translators: do not replace {number_of_playlists}, {number_of_plays} or {number_of_unmatched}
+                        add_success_toast(&i18n("Restored:"), &i18n_k("{number_of_playlists} playlists & {number_of_plays} plays, {number_of_unmatched} tracks not found.", &[
+                            ("number_of_playlists", &format!("{}", n_playlists)),
+                            ("number_of_plays", &format!("{}", n_plays)),
+                            ("number_of_unmatched", &format!("{}", n_unmatched)),
+                        ]))
+                    },
+                    Err(e) => {
+                        error!("Unable to restore backup: {}", e);
+                        add_error_toast(i18n("Unable to restore backup."));
+                    },
+                }
+            },
+            DatabaseAction::ImportPlaylistFile(path) => {
+                match self.import_playlist_file(path.clone()) {
+                    Ok((title, n_tracks, unmatched)) => self.show_playlist_import_report(title, n_tracks, unmatched),
+                    Err(e) => {
+                        error!("Unable to import playlist {:?}: {}", path, e);
+                        add_error_toast(i18n("Unable to import playlist."));
+                    },
+                }
+            },
+            DatabaseAction::ImportFolderPlaylists((folder, playlists)) => {
+                match self.import_folder_playlists(&folder, playlists) {
+                    Ok(0) => (),
+                    Ok(n_playlists) => {
+                        // Translators: do not replace {number_of_playlists} or {folder}
+                        add_success_toast(&i18n("Imported:"), &i18n_k("{number_of_playlists} playlists from {folder}.", &[("number_of_playlists", &format!("{}", n_playlists)), ("folder", &folder)]))
+                    },
+                    Err(e) => {
+                        error!("Unable to import the playlists of {}: {}", folder, e);
+                        add_error_toast(i18n("Unable to import playlist files."));
+                    },
+                }
+            },
+            DatabaseAction::QueueFileChange((folder, path)) => {
+                self.imp().watcher.queue_change(folder, path);
+            },
+            DatabaseAction::ApplyFileChanges => {
+                match self.apply_file_changes() {
                     Ok(_) => (),
-                    Err(e) => error!("Unable to contrust database from tags: {}", e),
+                    Err(e) => error!("Unable to apply file changes: {}", e),
                 }
             },
             DatabaseAction::TryLoadingDataBase => {
@@ -261,6 +419,18 @@ impl Database {
                     },
                 }
             },
+            DatabaseAction::RelocateFolder((old_path, new_path)) => {
+                match self.relocate_folder(old_path.clone(), new_path) {
+                    Ok(new_path) => {
+                        // Translators: do not replace {old_folder} or {new_folder}
+                        add_success_toast(&i18n("Relocated:"), &i18n_k("{old_folder} has moved to {new_folder}.", &[("old_folder", &old_path), ("new_folder", &new_path)]))
+                    },
+                    Err(e) => {
+                        error!("Unable to relocate {}: {}", old_path, e);
+                        add_error_toast(i18n("Unable to relocate folder."));
+                    },
+                }
+            },
             DatabaseAction::DeletePlaylist(playlist_id) => {
                 match self.delete_playlist(playlist_id) {
                     Ok(_) => {
@@ -308,6 +478,16 @@ impl Database {
                     },
                 }
             },
+            DatabaseAction::AddTrackAnalysis((track_id, analysis)) => {
+                let saved = match self.add_track_analysis(track_id, &analysis) {
+                    Ok(saved) => saved,
+                    Err(e) => {
+                        error!("Unable to save analysis of track {}: {}", track_id, e);
+                        false
+                    },
+                };
+                self.analysis_done(track_id, saved);
+            },
             DatabaseAction::ReorderPlaylist((playlist_id, old_position, new_position)) => {
                 match self.reorder_playlist(playlist_id, old_position, new_position) {
                     Ok(_) => {
@@ -319,6 +499,36 @@ impl Database {
                     },
                 }
             },
+            DatabaseAction::CreateSmartPlaylist((title, description, rules)) => {
+                match self.create_smart_playlist(title.clone(), description, rules) {
+                    Ok(_) => {
+                        // Translators: do not replace {playlist_title}
+                        add_success_toast(&i18n("Added Smart Playlist!"), &i18n_k("Smart playlist «{playlist_title}» has been created!", &[("playlist_title", &title)]))
+                    },
+                    Err(e) => {
+                        error!("Unable to add smart playlist: {}", e);
+                        add_error_toast(i18n("Unable to add smart playlist."));
+                    },
+                }
+            },
+            DatabaseAction::UpdateSmartPlaylist((smart_id, title, description, rules)) => {
+                match self.update_smart_playlist(smart_id, title, description, rules) {
+                    Ok(_) => add_success_toast(&i18n("Modified."), &i18n("Changed smart playlist successfully!")),
+                    Err(e) => {
+                        error!("Unable to modify smart playlist {}: {}", smart_id, e);
+                        add_error_toast(i18n("Unable to modify smart playlist."));
+                    },
+                }
+            },
+            DatabaseAction::DeleteSmartPlaylist(smart_id) => {
+                match self.delete_smart_playlist(smart_id) {
+                    Ok(_) => add_success_toast(&i18n("Deleted."), &i18n("Removed smart playlist successfully!")),
+                    Err(e) => {
+                        error!("Unable to remove smart playlist {}: {}", smart_id, e);
+                        add_error_toast(i18n("Unable to remove smart playlist."));
+                    },
+                }
+            },
             _ => debug!("Received action {:?}", action),
         }
 
@@ -356,6 +566,37 @@ impl Database {
                 send!(imp.model_sender, ModelAction::PopulateAll);
             }),
         );
+
+        imp.settings.connect_changed(
+            Some("analyze-library"),
+            glib::clone!(@strong self as this => move |settings, _name| {
+                let imp = this.imp();
+                if settings.boolean("analyze-library") {
+                    imp.analyzer.resume();
+                    this.queue_analysis();
+                } else {
+                    imp.analyzer.pause();
+                }
+            }),
+        );
+
+        imp.settings.connect_changed(
+            Some("analysis-jobs"),
+            glib::clone!(@strong self as this => move |settings, _name| {
+                this.imp().analyzer.set_max_jobs(settings.int("analysis-jobs") as usize);
+            }),
+        );
+
+        imp.settings.connect_changed(
+            Some("watch-music-folders"),
+            glib::clone!(@strong self as this => move |settings, _name| {
+                if settings.boolean("watch-music-folders") {
+                    this.watch_music_folders();
+                } else {
+                    this.imp().watcher.unwatch_all();
+                }
+            }),
+        );
     }
 
     fn get_dirs(&self) {
@@ -376,28 +617,42 @@ impl Database {
     pub fn try_loading_database(&self) {
         let imp = self.imp();
 
-        if let Ok(_) = self.open_connection_to_db() {
-            let is_folders = imp.folders.borrow().is_empty().clone();
-            if !is_folders { // there are folders loaded
-                match self.verify_music_folders() {
-                    Ok(_) => {
-                        //self.emit_by_name::<()>("populate-model", &[]);
-                        send!(imp.model_sender, ModelAction::PopulateAll);
-                        debug!("Folder verified & loaded");
-                        self.set_property("loaded", true.to_value());
-                        return
-                    },
-                    Err(e) => {
-                        error!("Unable to load music folders ... {}", e);
-                        add_error_toast(i18n("Unable to load music folders."));
-                    },
+        match self.open_connection_to_db() {
+            Ok(_) => {
+                if let Err(e) = self.resume_imports() {
+                    error!("Unable to resume imports: {}", e);
                 }
+            },
+            Err(e) => {
+                error!("Unable to open connection to database: {}", e);
+                if let Some(migration_error) = e.downcast_ref::<MigrationError>() {
+                    self.show_migration_error(migration_error);
+                }
+                self.set_property("loaded", false.to_value());
+                return;
+            },
+        }
 
-            } else {
-                debug!("No folders, but opened connection to db.")
+        let is_folders = imp.folders.borrow().is_empty().clone();
+        if !is_folders { // there are folders loaded
+            match self.verify_music_folders() {
+                Ok(_) => {
+                    //self.emit_by_name::<()>("populate-model", &[]);
+                    send!(imp.model_sender, ModelAction::PopulateAll);
+                    self.watch_music_folders();
+                    self.queue_analysis();
+                    debug!("Folder verified & loaded");
+                    self.set_property("loaded", true.to_value());
+                    return
+                },
+                Err(e) => {
+                    error!("Unable to load music folders ... {}", e);
+                    add_error_toast(i18n("Unable to load music folders."));
+                },
             }
+
         } else {
-            error!("Unable to open connection to database.");
+            debug!("No folders, but opened connection to db.")
         }
         self.set_property("loaded", false.to_value());
 
@@ -417,6 +672,17 @@ impl Database {
 
     }
 
+    fn show_migration_error(&self, migration_error: &MigrationError) {
+        if let Some(window) = util::window() {
+            let dialog = DatabaseErrorDialog::new(migration_error.version, migration_error.message.clone());
+            dialog.set_transient_for(Some(&window));
+            dialog.show();
+        } else {
+            // Translators: do not replace {version}
+            add_error_toast(i18n_k("Unable to upgrade the library to version {version}.", &[("version", &format!("{}", migration_error.version))]));
+        }
+    }
+
     fn check_loaded(&self, tx: &Transaction) -> Result<(), Box<dyn Error>> {
         let mut stmt = tx.prepare("SELECT EXISTS(SELECT 1 FROM Music_Folders LIMIT 1);")?;
         let exists: i32 = stmt.query_row([], |row| row.get(0))?;
@@ -440,7 +706,7 @@ impl Database {
         for dir in folders.iter() {
     
             if !self.check_if_folder_exists(&tx,dir.to_string())? {
-                self.importer().extract_folder(dir.to_string(), imp.db_sender.clone());
+                self.queue_import(dir.to_string());
             }
         }
 
@@ -471,7 +737,12 @@ impl Database {
         *self.imp().conn.borrow_mut() = Some(conn);
         //self.imp().conn.replace(Some(conn));
 
-        self.setup_db_tables()?;
+        if let Err(e) = self.setup_db_tables() {
+            //do not touch a library that could not be upgraded
+            self.imp().conn.replace(None);
+            return Err(e);
+        }
+
         self.check_new_artists_and_import()?;
         Ok(())
     }
@@ -505,7 +776,7 @@ impl Database {
     //     new
     // }
 
-    fn database_location(&self) -> PathBuf {
+    pub fn database_location(&self) -> PathBuf {
         if let Some(base_dirs) = BaseDirs::new() {
             let folder = base_dirs.data_dir().join("io.github.nate_xyz.Resonance");
             fs::create_dir_all(folder.clone()).unwrap();
@@ -526,31 +797,283 @@ impl Database {
         }
     }
 
-    //Extract tags from music folder with mutagen
+    //Extract tags from music folder with the tag reader set in preferences
     pub fn try_add_music_folder(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
-        let imp = self.imp();
         let path_str = path.into_os_string().into_string().ok().unwrap();
+        if self.imp().imports.borrow().iter().any(|status| status.folder == path_str) {
+            return Err(format!("{} is already being imported", path_str).into());
+        }
+        self.queue_import(path_str);
+        Ok(())
+    }
+
+    //Folders are imported one at a time, in the order they were added
+    fn queue_import(&self, folder: String) {
+        self.imp().imports.borrow_mut().push(ImportStatus::new(folder.clone()));
+        self.emit_by_name::<()>("import-progress", &[&folder]);
+        self.start_next_import();
+    }
+
+    fn start_next_import(&self) {
+        let imp = self.imp();
+        let folder = {
+            let mut imports = imp.imports.borrow_mut();
+            if imports.iter().any(|status| matches!(status.state, ImportState::Extracting | ImportState::Cancelling)) {
+                return;
+            }
+            match imports.iter_mut().find(|status| status.state == ImportState::Queued) {
+                Some(status) => {
+                    status.state = ImportState::Extracting;
+                    status.folder.clone()
+                },
+                None => return,
+            }
+        };
+
+        //files committed by an earlier, interrupted import of the folder are skipped
+        let known_files = match self.query_import_checkpoint(&folder) {
+            Ok(known_files) => known_files,
+            Err(e) => {
+                error!("Unable to query imported files of {}: {}", folder, e);
+                HashMap::new()
+            },
+        };
+
+        if known_files.is_empty() {
+            if let Err(e) = self.clear_folder_report(&folder) {
+                error!("Unable to clear the import report of {}: {}", folder, e);
+            }
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        imp.import_cancel.replace(Some(cancel.clone()));
         imp.import_start_time.replace(Some(chrono::offset::Utc::now()));
-        self.importer().extract_folder(path_str, imp.db_sender.clone());
+        self.importer().extract_folder(folder.clone(), known_files, cancel, imp.db_sender.clone());
+        self.emit_by_name::<()>("import-progress", &[&folder]);
+    }
+
+    fn query_import_checkpoint(&self, folder: &str) -> Result<HashMap<String, i64>, Box<dyn Error>> {
+        let mut conn = self.imp().conn.borrow_mut();
+        let conn = conn.as_mut().ok_or("Connection not established")?;
+        let tx = conn.transaction()?;
+
+        let mut stmt = tx.prepare("SELECT id FROM Music_Folders WHERE uri = (?)")?;
+        let folder_id: Option<i64> = stmt.query_row([folder], |row| row.get(0)).optional()?;
+        drop(stmt);
+
+        let known_files = match folder_id {
+            Some(folder_id) => self.query_folder_files(&tx, folder_id)?,
+            None => HashMap::new(),
+        };
+        tx.commit()?;
+
+        Ok(known_files)
+    }
+
+    //Folders in the database but not in the settings were being imported when the app closed
+    fn resume_imports(&self) -> Result<(), Box<dyn Error>> {
+        let imp = self.imp();
+        let unfinished = {
+            let mut conn = imp.conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+            let folders = self.query_music_folders(&tx)?;
+            tx.commit()?;
+
+            let settings_folders = imp.folders.borrow();
+            folders.into_iter()
+                .map(|(_, uri)| uri)
+                .filter(|uri| !settings_folders.contains(uri))
+                .collect::<Vec<String>>()
+        };
+
+        for folder in unfinished {
+            if !imp.imports.borrow().iter().any(|status| status.folder == folder) {
+                debug!("resuming import of {}", folder);
+                self.queue_import(folder);
+            }
+        }
+
+        Ok(())
+    }
+
+    //Queued imports are dropped, the running import stops at the next file & its committed batches are removed
+    fn cancel_import(&self, folder: &str) {
+        let state = self.imp().imports.borrow().iter()
+            .find(|status| status.folder == folder)
+            .map(|status| status.state);
+
+        match state {
+            Some(ImportState::Queued) => {
+                if let Err(e) = self.rollback_import(folder) {
+                    error!("Unable to remove the imported files of {}: {}", folder, e);
+                }
+                self.set_import_state(folder, ImportState::Done);
+            },
+            Some(ImportState::Extracting) => {
+                self.stop_import(folder);
+                self.set_import_state(folder, ImportState::Cancelling);
+            },
+            _ => (),
+        }
+    }
+
+    fn stop_import(&self, folder: &str) {
+        debug!("stopping import of {}", folder);
+        if let Some(cancel) = self.imp().import_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    //Removes what was committed of an import that never finished
+    fn rollback_import(&self, folder: &str) -> Result<(), Box<dyn Error>> {
+        let imp = self.imp();
+        if imp.folders.borrow().contains(folder) {
+            return Ok(());
+        }
+
+        {
+            let mut conn = imp.conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+
+            if !self.check_if_folder_exists(&tx, folder.to_string())? {
+                return Ok(());
+            }
+
+            self.remove_music_folder(&tx, folder.to_string())?;
+            self.check_loaded(&tx)?;
+            tx.commit()?;
+        }
+        self.emit_by_name::<()>("import-report-changed", &[]);
+
+        if imp.loaded.get() {
+            send!(imp.model_sender, ModelAction::PopulateAll);
+        }
+
         Ok(())
     }
 
-    //Called after importer extracts tags with mutagen
+    fn import_progress(&self, folder: String, progress: ImportProgress) {
+        match &progress {
+            ImportProgress::FileFailed((file, reason)) => {
+                debug!("unable to import {}: {}", file, reason);
+                // Translators: do not replace {reason}
+                let reason = i18n_k("Skipped, unreadable: {reason}", &[("reason", reason.as_str())]);
+                if let Err(e) = self.record_failed_file(&folder, file, &reason) {
+                    error!("Unable to add {} to the import report: {}", file, e);
+                }
+            },
+            ImportProgress::Failed(reason) => {
+                error!("Import of {} failed: {}", folder, reason);
+                // Translators: do not replace {folder}
+                add_error_toast(i18n_k("Unable to import {folder}.", &[("folder", &folder)]));
+            },
+            ImportProgress::Cancelled => {
+                match self.rollback_import(&folder) {
+                    // Translators: do not replace {folder}
+                    Ok(_) => add_success_toast(&i18n("Cancelled:"), &i18n_k("Import of {folder} has been cancelled.", &[("folder", &folder)])),
+                    Err(e) => {
+                        error!("Unable to remove the imported files of {}: {}", folder, e);
+                        add_error_toast(i18n("Unable to cancel import."));
+                    },
+                }
+            },
+            ImportProgress::Finished => {
+                match self.finish_import(folder.clone()) {
+                    // Translators: do not replace {folder}
+                    Ok(_) => add_success_toast(&i18n("Imported:"), &i18n_k("{folder} has been added to the library.", &[("folder", &folder)])),
+                    Err(e) => {
+                        error!("Unable to finish import of {}: {}", folder, e);
+                        add_error_toast(i18n("Unable to import music folder."));
+                    },
+                }
+            },
+            _ => (),
+        }
+
+        let state = {
+            let mut imports = self.imp().imports.borrow_mut();
+            match imports.iter_mut().find(|status| status.folder == folder) {
+                Some(status) => {
+                    status.apply(progress);
+                    status.state
+                },
+                None => return,
+            }
+        };
+
+        if matches!(state, ImportState::Done | ImportState::Failed) {
+            self.imp().import_cancel.take();
+            self.set_import_state(&folder, state);
+        } else {
+            self.emit_by_name::<()>("import-progress", &[&folder]);
+        }
+    }
+
+    //Finished imports are dropped from the queue & the next one starts
+    fn set_import_state(&self, folder: &str, state: ImportState) {
+        {
+            let mut imports = self.imp().imports.borrow_mut();
+            if matches!(state, ImportState::Done | ImportState::Failed) {
+                imports.retain(|status| status.folder != folder);
+            } else if let Some(status) = imports.iter_mut().find(|status| status.folder == folder) {
+                status.state = state;
+            }
+        }
+
+        self.emit_by_name::<()>("import-progress", &[&folder]);
+        self.start_next_import();
+    }
+
+    //Queued & running folder imports
+    pub fn imports(&self) -> Vec<ImportStatus> {
+        self.imp().imports.borrow().clone()
+    }
+
+    //Called after importer extracts a batch of tags of a new folder, each batch is committed on its own
     fn construct_from_tags(&self, folder: String, tags: HashMap<String, HashMap<String, HashMap<String, MapVal>>>, bytes: HashMap<String, Vec<u8>>)  -> Result<(), Box<dyn Error>>{
+        let mut conn = self.imp().conn.borrow_mut();
+        let conn = conn.as_mut().ok_or("Connection not established")?;
+        let tx = conn.transaction()?;
+
+        let mut stmt = tx.prepare("SELECT id FROM Music_Folders WHERE uri = (?)")?;
+        let folder_id: Option<i64> = stmt.query_row([folder.clone()], |row| row.get(0)).optional()?;
+        drop(stmt);
+
+        match folder_id {
+            Some(folder_id) => {
+                self.importer().update_database_from_tags(&tx, folder_id, &folder, tags, bytes)?;
+            },
+            None => self.importer().build_database_from_tags(&tx, folder, tags, bytes)?,
+        }
+
+        tx.commit()?;
+        drop(conn);
+
+        self.emit_by_name::<()>("import-report-changed", &[]);
+        Ok(())
+    }
+
+    //Called once every batch of a new folder is committed
+    fn finish_import(&self, folder: String) -> Result<(), Box<dyn Error>> {
         let imp = self.imp();
         let new_artists = {
             let mut conn = imp.conn.borrow_mut();
             let conn = conn.as_mut().ok_or("Connection not established")?;
-    
             let tx = conn.transaction()?;
-            self.importer().build_database_from_tags(&tx, folder.clone(), tags, bytes)?;
             let new_artists = self.new_artists(&tx)?;
-            
             tx.commit()?;
-
             new_artists
         };
 
+        if imp.settings.boolean("watch-music-folders") {
+            imp.watcher.watch_folder(&folder);
+        }
+
+        self.queue_analysis();
+        self.read_folder_playlists(folder.clone());
+
         //UPDATE SETTINGS
         let mut folders = imp.folders.borrow_mut().clone();
         folders.insert(folder);
@@ -585,39 +1108,292 @@ impl Database {
         Ok(())
     }
 
-    //Checks if new artists exist that have not been fetched yet (for image scrape)
-    fn check_new_artists_and_import(&self) -> Result<(), Box<dyn Error>> {
+    //Rescan every imported folder, only new or modified files are extracted again
+    pub fn rescan_library(&self) -> Result<(), Box<dyn Error>> {
         let imp = self.imp();
-        let mut conn = imp.conn.borrow_mut();
-        let conn = conn.as_mut().ok_or("Connection not established")?;
-        let tx = conn.transaction()?;
-
-        let new_artists = self.new_artists(&tx)?;
-        tx.commit()?;
-
-        //UPDATE ARTISTS ART
-        if !new_artists.is_empty() {
-            debug!("Adding new artists ...");
+        let folders = {
+            let mut conn = imp.conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
 
-            let sender = imp.db_sender.clone();
+            let mut folders = Vec::new();
+            for (folder_id, uri) in self.query_music_folders(&tx)? {
+                let known_files = self.query_folder_files(&tx, folder_id)?;
+                folders.push((uri, known_files));
+            }
+            tx.commit()?;
+            folders
+        };
 
-            thread::spawn(move || {
-                match fetch_artist_images_bulk(new_artists, sender.clone()) {
-                    Ok(()) => debug!("Added all artist images!"),
-                    Err(e) => error!("Adding artist images error: {}", e),
-                }
-            });
-        } else {
-            debug!("No new artists");
+        for (uri, known_files) in folders {
+            //an unmounted drive would look like every file was removed
+            if !Path::new(&uri).is_dir() {
+                error!("Music folder {} not found, skipping rescan", uri);
+                // Translators: do not replace {folder}
+                add_error_toast(i18n_k("{folder} was not found, relocate it in the preferences if it moved.", &[("folder", &uri)]));
+                continue;
+            }
+            imp.rescan_folders.borrow_mut().insert(uri.clone());
+            self.importer().rescan_folder(uri, known_files, imp.db_sender.clone());
         }
 
         Ok(())
     }
 
-    fn new_artists(&self, tx: &Transaction) -> Result<Vec<(i64, String)>, Box<dyn Error>> {
-        let mut stmt = tx.prepare("SELECT * FROM Artists WHERE Artists.image_fetched=0 AND NOT EXISTS(SELECT 1 FROM Artist_Discog_Artist_Image_Junction WHERE Artists.id = Artist_Discog_Artist_Image_Junction.artist_id);")?;
-        let rows = stmt.query_map([], |row| {
-            let id: i64 = row.get(0)?;
+    //A library rescan shows a single toast once every folder is done, changes found by the watcher only show their errors
+    fn finish_rescan(&self, folder: &str, n_changed: Option<usize>) {
+        let imp = self.imp();
+        if !imp.rescan_folders.borrow_mut().remove(folder) {
+            if n_changed.is_none() {
+                // Translators: do not replace {folder}
+                add_error_toast(i18n_k("Unable to apply the changes in {folder}.", &[("folder", folder)]));
+            }
+            return;
+        }
+
+        match n_changed {
+            Some(n_changed) => imp.rescan_changed.set(imp.rescan_changed.get() + n_changed),
+            None => imp.rescan_failed.set(true),
+        }
+
+        if !imp.rescan_folders.borrow().is_empty() {
+            return;
+        }
+
+        let n_changed = imp.rescan_changed.replace(0);
+        if imp.rescan_failed.replace(false) {
+            add_error_toast(i18n("Unable to rescan library."));
+        } else if n_changed == 0 {
+            add_success_toast(&i18n("Up to date:"), &i18n("No changes found in the library."));
+        } else {
+            // Translators: do not replace {number_of_files}
+            add_success_toast(&i18n("Rescanned:"), &i18n_k("Updated {number_of_files} files.", &[("number_of_files", &format!("{}", n_changed))]));
+        }
+    }
+
+    //Called after importer compares a folder on disk & extracts the changed files, returns the number of files changed
+    fn apply_rescan(&self, folder: String, removed: Vec<String>, tags: HashMap<String, HashMap<String, HashMap<String, MapVal>>>, bytes: HashMap<String, Vec<u8>>) -> Result<usize, Box<dyn Error>> {
+        let imp = self.imp();
+        let (removed_tracks, updated_tracks, new_artists) = {
+            let mut conn = imp.conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+
+            let mut stmt = tx.prepare("SELECT id FROM Music_Folders WHERE uri = (?)")?;
+            let folder_id: Option<i64> = stmt.query_row([folder.clone()], |row| row.get(0)).optional()?;
+            let folder_id = folder_id.ok_or(DatabaseError(format!("{} is not in the database", folder)))?;
+
+            //files moved within the folder keep their track, so plays & playlist entries follow them
+            let moved = self.importer().match_moved_files(&tx, &removed, &tags)?;
+            for (old_uri, new_uri) in moved.iter() {
+                debug!("relinking moved file {} -> {}", old_uri, new_uri);
+                self.relink_file(&tx, old_uri, new_uri)?;
+            }
+
+            let mut removed_tracks = Vec::new();
+            for uri in removed.iter().filter(|uri| !moved.iter().any(|(old_uri, _)| old_uri == *uri)) {
+                self.clear_import_problems(&tx, uri)?;
+                if let Some(track_id) = self.query_track_id_by_uri(&tx, uri)? {
+                    self.remove_track_from_playlists(&tx, track_id)?;
+                    self.remove_track(&tx, track_id)?;
+                    removed_tracks.push(track_id as u64);
+                }
+            }
+
+            let updated_tracks = self.importer().update_database_from_tags(&tx, folder_id, &folder, tags, bytes)?;
+
+            if !removed_tracks.is_empty() || !updated_tracks.is_empty() {
+                self.prune_library(&tx)?;
+            }
+
+            let new_artists = self.new_artists(&tx)?;
+            tx.commit()?;
+
+            let updated_tracks = updated_tracks.into_iter().map(|id| id as u64).collect::<Vec<u64>>();
+            (removed_tracks, updated_tracks, new_artists)
+        };
+
+        self.emit_by_name::<()>("import-report-changed", &[]);
+
+        //only the changed tracks are reloaded, instead of repopulating the whole model
+        if !removed_tracks.is_empty() {
+            send!(imp.model_sender, ModelAction::RemoveTracks(removed_tracks.clone()));
+            send!(imp.model_sender, ModelAction::PopulatePlaylists);
+        }
+
+        if !updated_tracks.is_empty() {
+            send!(imp.model_sender, ModelAction::UpdateTracks(updated_tracks.clone()));
+            self.queue_analysis();
+        }
+
+        //UPDATE ARTISTS ART
+        if !new_artists.is_empty() {
+            debug!("Adding new artists ...");
+
+            let sender = imp.db_sender.clone();
+
+            thread::spawn(move || {
+                match fetch_artist_images_bulk(new_artists, sender.clone()) {
+                    Ok(()) => debug!("Added all artist images!"),
+                    Err(e) => error!("Adding artist images error: {}", e),
+                }
+            });
+        }
+
+        self.read_folder_playlists(folder);
+
+        Ok(removed_tracks.len() + updated_tracks.len())
+    }
+
+    //Playlist files inside music folders are only imported when enabled in the preferences
+    fn read_folder_playlists(&self, folder: String) {
+        let imp = self.imp();
+        if imp.settings.boolean("import-playlist-files") {
+            self.importer().read_folder_playlists(folder, imp.db_sender.clone());
+        }
+    }
+
+    //Queues the tracks missing replaygain or bpm tags that were never analyzed
+    fn queue_analysis(&self) {
+        let imp = self.imp();
+        if !imp.settings.boolean("analyze-library") {
+            return;
+        }
+
+        match self.query_tracks_to_analyze() {
+            Ok(tracks) => imp.analyzer.set_queue(tracks),
+            Err(e) => error!("Unable to query tracks to analyze: {}", e),
+        }
+    }
+
+    //Analyzed tracks are reloaded in batches, instead of one model update per track
+    fn analysis_done(&self, track_id: i64, saved: bool) {
+        let imp = self.imp();
+        imp.analyzer.job_done(track_id);
+        if saved {
+            imp.analyzed_tracks.borrow_mut().push(track_id as u64);
+        }
+
+        let n_analyzed = imp.analyzed_tracks.borrow().len();
+        if n_analyzed >= 25 || (n_analyzed > 0 && imp.analyzer.is_idle()) {
+            let analyzed_tracks = imp.analyzed_tracks.take();
+            send!(imp.model_sender, ModelAction::UpdateTracks(analyzed_tracks));
+        }
+    }
+
+    fn watch_music_folders(&self) {
+        let imp = self.imp();
+        if !imp.settings.boolean("watch-music-folders") {
+            return;
+        }
+
+        for folder in imp.folders.borrow().iter() {
+            imp.watcher.watch_folder(folder);
+        }
+    }
+
+    //Called once the folder watcher settles, sorts the queued paths into changed & removed files
+    fn apply_file_changes(&self) -> Result<(), Box<dyn Error>> {
+        let imp = self.imp();
+        let pending = imp.watcher.take_pending();
+
+        let mut changes = Vec::new();
+        {
+            let mut conn = imp.conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+
+            for (folder_id, folder) in self.query_music_folders(&tx)? {
+                let paths = match pending.get(&folder) {
+                    Some(paths) => paths,
+                    None => continue,
+                };
+
+                let known_files = self.query_folder_files(&tx, folder_id)?;
+                let mut changed = HashSet::new();
+                let mut removed = HashSet::new();
+
+                for path in paths {
+                    let path_buf = PathBuf::from(path);
+                    if path_buf.is_dir() {
+                        imp.watcher.watch_tree(&folder, &path_buf);
+                        for (uri, modified) in importer::scan_folder(path) {
+                            if known_files.get(&uri) != Some(&modified) {
+                                changed.insert(uri);
+                            }
+                        }
+                    } else if path_buf.is_file() {
+                        if !importer::is_song(&path_buf) {
+                            continue;
+                        }
+                        let modified = match fs::metadata(&path_buf).and_then(|metadata| metadata.modified()) {
+                            Ok(modified) => DateTime::<Utc>::from(modified).timestamp(),
+                            Err(_) => continue,
+                        };
+                        if known_files.get(path) != Some(&modified) {
+                            changed.insert(path.clone());
+                        }
+                    } else {
+                        //a deleted directory takes every file beneath it
+                        imp.watcher.unwatch(path);
+                        let prefix = format!("{}/", path);
+                        for uri in known_files.keys() {
+                            if uri == path || uri.starts_with(&prefix) {
+                                removed.insert(uri.clone());
+                            }
+                        }
+                    }
+                }
+
+                if !changed.is_empty() || !removed.is_empty() {
+                    changes.push((folder, changed.into_iter().collect::<Vec<String>>(), removed.into_iter().collect::<Vec<String>>()));
+                }
+            }
+
+            tx.commit()?;
+        }
+
+        for (folder, changed, removed) in changes {
+            debug!("{}: {} changed, {} removed", folder, changed.len(), removed.len());
+            self.importer().extract_changes(folder, changed, removed, imp.db_sender.clone());
+        }
+
+        Ok(())
+    }
+
+    //Checks if new artists exist that have not been fetched yet (for image scrape)
+    fn check_new_artists_and_import(&self) -> Result<(), Box<dyn Error>> {
+        let imp = self.imp();
+        let mut conn = imp.conn.borrow_mut();
+        let conn = conn.as_mut().ok_or("Connection not established")?;
+        let tx = conn.transaction()?;
+
+        let new_artists = self.new_artists(&tx)?;
+        tx.commit()?;
+
+        //UPDATE ARTISTS ART
+        if !new_artists.is_empty() {
+            debug!("Adding new artists ...");
+
+            let sender = imp.db_sender.clone();
+
+            thread::spawn(move || {
+                match fetch_artist_images_bulk(new_artists, sender.clone()) {
+                    Ok(()) => debug!("Added all artist images!"),
+                    Err(e) => error!("Adding artist images error: {}", e),
+                }
+            });
+        } else {
+            debug!("No new artists");
+        }
+
+        Ok(())
+    }
+
+    fn new_artists(&self, tx: &Transaction) -> Result<Vec<(i64, String)>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT * FROM Artists WHERE Artists.image_fetched=0 AND NOT EXISTS(SELECT 1 FROM Artist_Discog_Artist_Image_Junction WHERE Artists.id = Artist_Discog_Artist_Image_Junction.artist_id);")?;
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
             let name: String = row.get(1)?;
             Ok((id, name))
         })?;
@@ -662,35 +1438,37 @@ impl Database {
     //ADD ALBUM
     pub fn add_album_full(&self, tx: &Transaction, 
         title: String, artist: String, date: String, genre: String,
-        cover_art_id: Option<i64>, album_artist_id: i64, genre_id: Option<i64>) -> Result<i64, Box<dyn Error>> {
+        cover_art_id: Option<i64>, album_artist_ids: &[i64], genre_ids: &[i64], compilation: bool) -> Result<i64, Box<dyn Error>> {
         
-        let album_id = self.add_album(&tx, title, artist, date, genre)?;
-        self.add_album_artist_junction(&tx, album_id, album_artist_id)?;
+        let album_id = self.add_album(&tx, title, artist, date, genre, compilation)?;
+        for (position, artist_id) in album_artist_ids.iter().enumerate() {
+            self.add_album_artist_junction(&tx, album_id, *artist_id, position)?;
+        }
 
         if let Some(id) = cover_art_id {
             self.add_album_cover_art_junction(&tx, album_id, id)?;
         }
 
-        if let Some(id) = genre_id {
-            self.add_album_genre_junction(&tx, album_id, id)?;
+        for id in genre_ids {
+            self.add_album_genre_junction(&tx, album_id, *id)?;
         }
 
         Ok(album_id)
     }
 
     fn add_album(&self, tx: &Transaction, 
-        title: String, artist: String, date: String, genre: String) -> Result<i64, Box<dyn Error>> {
-        let mut stmt = tx.prepare("INSERT INTO Albums (title, artist, date, genre) VALUES ( ?, ?, ?, ? );")?;
+        title: String, artist: String, date: String, genre: String, compilation: bool) -> Result<i64, Box<dyn Error>> {
+        let mut stmt = tx.prepare("INSERT INTO Albums (title, artist, date, genre, compilation) VALUES ( ?, ?, ?, ?, ? );")?;
         stmt.execute(params![
-            title, artist, date, genre
+            title, artist, date, genre, compilation
         ])?;
         Ok(tx.last_insert_rowid())
     }
 
     fn add_album_artist_junction(&self, tx: &Transaction, 
-        album_id: i64, artist_id: i64) -> Result<i64, Box<dyn Error>> {
-        let mut stmt = tx.prepare("INSERT INTO Album_Artist_Junction (album_id, artist_id) VALUES ( ?, ? );")?;
-        stmt.execute(params![album_id, artist_id])?;
+        album_id: i64, artist_id: i64, position: usize) -> Result<i64, Box<dyn Error>> {
+        let mut stmt = tx.prepare("INSERT INTO Album_Artist_Junction (album_id, artist_id, position) VALUES ( ?, ?, ? );")?;
+        stmt.execute(params![album_id, artist_id, position])?;
         Ok(tx.last_insert_rowid())
     }
 
@@ -710,20 +1488,55 @@ impl Database {
         Ok(tx.last_insert_rowid())
     }
 
+    //An album has every genre of its tracks, its genre label lists them
+    pub fn sync_album_genres(&self, tx: &Transaction, album_id: i64) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("DELETE FROM Album_Genre_Junction WHERE album_id = (?);")?;
+        stmt.execute(params![album_id])?;
+
+        let mut stmt = tx.prepare("SELECT DISTINCT Track_Genre_Junction.genre_id FROM Track_Genre_Junction 
+            INNER JOIN Track_Album_Junction ON Track_Genre_Junction.track_id = Track_Album_Junction.track_id 
+            WHERE Track_Album_Junction.album_id = (?) ORDER BY Track_Genre_Junction.id;")?;
+        let rows = stmt.query_map([album_id], |row| row.get(0))?;
+
+        let mut genre_ids: Vec<i64> = Vec::new();
+        for r in rows {
+            genre_ids.push(r?);
+        }
+
+        let mut names = Vec::new();
+        for genre_id in genre_ids {
+            self.add_album_genre_junction(&tx, album_id, genre_id)?;
+
+            let mut stmt = tx.prepare("SELECT name FROM Genres WHERE id = (?);")?;
+            let name: String = stmt.query_row([genre_id], |row| row.get(0))?;
+            names.push(name);
+        }
+
+        let mut stmt = tx.prepare("UPDATE Albums SET genre = (?) WHERE id = (?);")?;
+        stmt.execute(params![names.join(", "), album_id])?;
+
+        Ok(())
+    }
+
     //ADD TRACK
 
     pub fn add_track_full(&self, tx: &Transaction, 
-        title: String, filetype: String, album_name: String, album_artist: String, date: String, 
+        title: String, filetype: String, album_name: String, album_artist: String, artist: String, date: String, genre: String,
         duration: f32, track_number: u32, disc_number: u32,  
-        album_id: i64, album_artist_id: i64, cover_art_id: Option<i64>,
+        album_id: i64, artist_ids: &[i64], genre_ids: &[i64], cover_art_id: Option<i64>,
         file_uri: String, last_modified: DateTime<Utc>, folder_id: i64, 
     ) -> Result<i64, Box<dyn Error>> {
         
         let file_id = self.add_file_uri(&tx,file_uri, last_modified, folder_id)?;
-        let track_id = self.add_track(&tx, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_id)?;
+        let track_id = self.add_track(&tx, title, filetype, album_name, date, genre, duration, track_number, disc_number, album_artist, artist, file_id)?;
         
         self.add_track_album_junction(&tx, track_id, album_id)?;
-        self.add_track_artist_junction(&tx, track_id, album_artist_id)?;
+        for (position, artist_id) in artist_ids.iter().enumerate() {
+            self.add_track_artist_junction(&tx, track_id, *artist_id, position)?;
+        }
+        for genre_id in genre_ids {
+            self.add_track_genre_junction(&tx, track_id, *genre_id)?;
+        }
         self.add_track_folder_junction(&tx, track_id, folder_id)?;
 
         if let Some(id) = cover_art_id {
@@ -734,8 +1547,9 @@ impl Database {
     }
 
     fn add_file_uri(&self, tx: &Transaction, file_uri: String, last_modified: DateTime<Utc>, folder_id: i64) -> Result<i64, Box<dyn Error>> {
-        let mut stmt = tx.prepare("INSERT INTO File_URIs (uri, last_modified) VALUES ( ?, ? );")?;
-        stmt.execute(params![file_uri, last_modified.timestamp()])?;
+        let size = file_size(&file_uri);
+        let mut stmt = tx.prepare("INSERT INTO File_URIs (uri, last_modified, size) VALUES ( ?, ?, ? );")?;
+        stmt.execute(params![file_uri, last_modified.timestamp(), size])?;
         let file_id = tx.last_insert_rowid();
 
         let mut stmt = tx.prepare("INSERT INTO Folder_File_Junction (folder_id, file_id) VALUES ( ?, ? );")?;
@@ -745,10 +1559,10 @@ impl Database {
     }
 
     fn add_track(&self, tx: &Transaction, 
-        title: String, filetype: String, album_name: String, date: String, duration: f32, 
-        track_number: u32, disc_number: u32,  album_artist: String,  file_uri_id: i64) -> Result<i64, Box<dyn Error>> {
-        let mut stmt = tx.prepare("INSERT INTO Tracks (title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id) VALUES ( ?, ?, ?, ?, ?, ?, ?, ?, ? );")?;
-        stmt.execute(params![title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id])?;
+        title: String, filetype: String, album_name: String, date: String, genre: String, duration: f32, 
+        track_number: u32, disc_number: u32,  album_artist: String, artist: String, file_uri_id: i64) -> Result<i64, Box<dyn Error>> {
+        let mut stmt = tx.prepare("INSERT INTO Tracks (title, filetype, album_name, date, genre, duration, track_number, disc_number, album_artist, artist, file_uri_id, added_time) VALUES ( ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ? );")?;
+        stmt.execute(params![title, filetype, album_name, date, genre, duration, track_number, disc_number, album_artist, artist, file_uri_id, Utc::now().timestamp()])?;
         Ok(tx.last_insert_rowid())
     }
 
@@ -767,12 +1581,53 @@ impl Database {
     }
 
     fn add_track_artist_junction(&self, tx: &Transaction, 
-        track_id: i64, artist_id: i64) -> Result<i64, Box<dyn Error>> {
-        let mut stmt = tx.prepare("INSERT INTO Track_Artist_Junction (track_id, artist_id) VALUES ( ?, ? );")?;
-        stmt.execute(params![track_id, artist_id])?;
+        track_id: i64, artist_id: i64, position: usize) -> Result<i64, Box<dyn Error>> {
+        let mut stmt = tx.prepare("INSERT INTO Track_Artist_Junction (track_id, artist_id, position) VALUES ( ?, ?, ? );")?;
+        stmt.execute(params![track_id, artist_id, position])?;
         Ok(tx.last_insert_rowid())
     }
 
+    fn add_track_genre_junction(&self, tx: &Transaction, 
+        track_id: i64, genre_id: i64) -> Result<i64, Box<dyn Error>> {
+        let mut stmt = tx.prepare("INSERT INTO Track_Genre_Junction (track_id, genre_id) VALUES ( ?, ? );")?;
+        stmt.execute(params![track_id, genre_id])?;
+        Ok(tx.last_insert_rowid())
+    }
+
+    //A new release id drops the cover art found for the previous one
+    pub fn set_album_musicbrainz_ids(&self, tx: &Transaction, album_id: i64, release_id: &str, release_group_id: &str) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT musicbrainz_id, musicbrainz_release_group_id FROM Albums WHERE id = (?);")?;
+        let current: (String, String) = stmt.query_row([album_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        if current.0 == release_id && current.1 == release_group_id {
+            return Ok(());
+        }
+
+        let mut stmt = tx.prepare("UPDATE Albums SET musicbrainz_id = (?), musicbrainz_release_group_id = (?) WHERE id = (?);")?;
+        stmt.execute(params![release_id, release_group_id, album_id])?;
+
+        let mut stmt = tx.prepare("DELETE FROM MusicBrainz_Cover_Art WHERE album_id = (?);")?;
+        stmt.execute(params![album_id])?;
+        Ok(())
+    }
+
+    pub fn set_artist_musicbrainz_id(&self, tx: &Transaction, artist_id: i64, musicbrainz_id: &str) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("UPDATE Artists SET musicbrainz_id = (?) WHERE id = (?);")?;
+        stmt.execute(params![musicbrainz_id, artist_id])?;
+        Ok(())
+    }
+
+    //Replaces the extended tags of a track
+    pub fn set_track_tags(&self, tx: &Transaction, track_id: i64, tags: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("DELETE FROM Track_Tags WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        let mut stmt = tx.prepare("INSERT INTO Track_Tags (track_id, key, value) VALUES ( ?, ?, ? );")?;
+        for (key, value) in tags {
+            stmt.execute(params![track_id, key, value])?;
+        }
+        Ok(())
+    }
+
     fn add_track_cover_art_junction(&self, tx: &Transaction, 
         track_id: i64, cover_art_id: i64) -> Result<i64, Box<dyn Error>> {
         let mut stmt = tx.prepare("INSERT INTO Track_Cover_Art_Junction (track_id, cover_art_id) VALUES ( ?, ? );")?;
@@ -940,6 +1795,191 @@ impl Database {
     //     Ok(image_id)
     // }
 
+    //Cover Art Archive path found for an album, None when the lookup found nothing
+    pub fn add_musicbrainz_cover_art(&self, album_id: i64, art_path: Option<String>) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("INSERT OR REPLACE INTO MusicBrainz_Cover_Art (album_id, art_path, lookup_time) VALUES ( ?, ?, ? );")?;
+        stmt.execute(params![album_id, art_path, Utc::now().timestamp()])?;
+        Ok(())
+    }
+
+    //Results of an analysis, a failed analysis is kept too so the track is not analyzed again.
+    //Returns false when the track was removed while it was analyzed.
+    pub fn add_track_analysis(&self, track_id: i64, analysis: &TrackAnalysis) -> Result<bool, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT EXISTS(SELECT 1 FROM Tracks WHERE id = (?));")?;
+        let exists: bool = stmt.query_row([track_id], |row| row.get(0))?;
+        if !exists {
+            return Ok(false);
+        }
+
+        let mut stmt = conn.prepare("INSERT OR REPLACE INTO Track_Analysis (track_id, track_gain, track_peak, bpm, analysis_time) VALUES ( ?, ?, ?, ?, ? );")?;
+        stmt.execute(params![track_id, analysis.track_gain, analysis.track_peak, analysis.bpm, Utc::now().timestamp()])?;
+        Ok(true)
+    }
+
+    //Problem file of the import report, a file may have several problems
+    pub fn add_import_problem(&self, tx: &Transaction, folder: &str, uri: &str, reason: &str) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("INSERT OR REPLACE INTO Import_Report (folder, uri, reason, report_time) VALUES ( ?, ?, ?, ? );")?;
+        stmt.execute(params![folder, uri, reason, Utc::now().timestamp()])?;
+        Ok(())
+    }
+
+    //Called before a file is read again, its problems are reported anew
+    pub fn clear_import_problems(&self, tx: &Transaction, uri: &str) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("DELETE FROM Import_Report WHERE uri = (?);")?;
+        stmt.execute(params![uri])?;
+        Ok(())
+    }
+
+    fn record_failed_file(&self, folder: &str, uri: &str, reason: &str) -> Result<(), Box<dyn Error>> {
+        {
+            let mut conn = self.imp().conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+            self.clear_import_problems(&tx, uri)?;
+            self.add_import_problem(&tx, folder, uri, reason)?;
+            tx.commit()?;
+        }
+
+        self.emit_by_name::<()>("import-report-changed", &[]);
+        Ok(())
+    }
+
+    fn clear_folder_report(&self, folder: &str) -> Result<(), Box<dyn Error>> {
+        {
+            let conn = self.imp().conn.borrow();
+            let conn = conn.as_ref().ok_or("Connection not established")?;
+            let mut stmt = conn.prepare("DELETE FROM Import_Report WHERE folder = (?);")?;
+            stmt.execute(params![folder])?;
+        }
+
+        self.emit_by_name::<()>("import-report-changed", &[]);
+        Ok(())
+    }
+
+    pub fn query_import_report(&self) -> Result<Vec<ImportProblem>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT folder, uri, reason FROM Import_Report ORDER BY folder, uri, reason;")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ImportProblem {
+                folder: row.get(0)?,
+                uri: row.get(1)?,
+                reason: row.get(2)?,
+            })
+        })?;
+
+        let mut problems = Vec::new();
+        for row in rows {
+            problems.push(row?);
+        }
+        Ok(problems)
+    }
+
+    //None falls back on the library policy
+    fn set_album_format_policy(&self, album_key: &str, policy: Option<FormatPolicy>) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        match policy {
+            Some(policy) => {
+                let mut stmt = conn.prepare("INSERT OR REPLACE INTO Album_Format_Policy (album_key, policy) VALUES ( ?, ? );")?;
+                stmt.execute(params![album_key, policy.value()])?;
+            },
+            None => {
+                let mut stmt = conn.prepare("DELETE FROM Album_Format_Policy WHERE album_key = (?);")?;
+                stmt.execute(params![album_key])?;
+            },
+        }
+        Ok(())
+    }
+
+    pub fn query_album_format_policies(&self) -> Result<HashMap<String, FormatPolicy>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT album_key, policy FROM Album_Format_Policy;")?;
+        let rows = stmt.query_map([], |row| {
+            let album_key: String = row.get(0)?;
+            let policy: i32 = row.get(1)?;
+            Ok((album_key, FormatPolicy::new_from_settings(policy)))
+        })?;
+
+        let mut policies = HashMap::new();
+        for row in rows {
+            let (album_key, policy) = row?;
+            policies.insert(album_key, policy);
+        }
+        Ok(policies)
+    }
+
+    //Empty ratings are deleted rather than stored
+    pub fn set_track_rating(&self, uri: &str, rating: Rating) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        if rating.is_empty() {
+            let mut stmt = conn.prepare("DELETE FROM Track_Ratings WHERE uri = (?);")?;
+            stmt.execute(params![uri])?;
+        } else {
+            let mut stmt = conn.prepare("INSERT OR REPLACE INTO Track_Ratings (uri, favorite, stars) VALUES ( ?, ?, ? );")?;
+            stmt.execute(params![uri, rating.favorite, rating.stars])?;
+        }
+        Ok(())
+    }
+
+    pub fn set_album_rating(&self, album_key: &str, rating: Rating) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        if rating.is_empty() {
+            let mut stmt = conn.prepare("DELETE FROM Album_Ratings WHERE album_key = (?);")?;
+            stmt.execute(params![album_key])?;
+        } else {
+            let mut stmt = conn.prepare("INSERT OR REPLACE INTO Album_Ratings (album_key, favorite, stars) VALUES ( ?, ?, ? );")?;
+            stmt.execute(params![album_key, rating.favorite, rating.stars])?;
+        }
+        Ok(())
+    }
+
+    //Ratings of the tracks keyed by file path, including files no longer in the library
+    pub fn query_track_ratings(&self) -> Result<HashMap<String, Rating>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT uri, favorite, stars FROM Track_Ratings;")?;
+        let rows = stmt.query_map([], |row| {
+            let uri: String = row.get(0)?;
+            let favorite: bool = row.get(1)?;
+            let stars: u8 = row.get(2)?;
+            Ok((uri, Rating::new(favorite, stars)))
+        })?;
+
+        let mut ratings = HashMap::new();
+        for row in rows {
+            let (uri, rating) = row?;
+            ratings.insert(uri, rating);
+        }
+        Ok(ratings)
+    }
+
+    pub fn query_album_ratings(&self) -> Result<HashMap<String, Rating>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT album_key, favorite, stars FROM Album_Ratings;")?;
+        let rows = stmt.query_map([], |row| {
+            let album_key: String = row.get(0)?;
+            let favorite: bool = row.get(1)?;
+            let stars: u8 = row.get(2)?;
+            Ok((album_key, Rating::new(favorite, stars)))
+        })?;
+
+        let mut ratings = HashMap::new();
+        for row in rows {
+            let (album_key, rating) = row?;
+            ratings.insert(album_key, rating);
+        }
+        Ok(ratings)
+    }
+
     //ADD PLAY
     pub fn add_play(&self, track: Rc<Track>, datetime_stamp: DateTime<Utc>) -> Result<(), Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
@@ -955,10 +1995,96 @@ impl Database {
 
     }
 
+    //A track changed before the play was committed
+    pub fn add_skip(&self, track: Rc<Track>, datetime_stamp: DateTime<Utc>) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established add_skip")?;
+        let mut stmt = conn.prepare("INSERT INTO Skips (skiptime, track_id) VALUES ( ?, ? );")?;
+        stmt.execute(params![datetime_stamp.timestamp(), track.id()])?;
+        Ok(())
+    }
+
     /*
     QUERIES
     */
 
+    //Play count, last play, skip count & added time of every track, or of a single one
+    pub fn query_play_stats(&self, track_id: Option<i64>) -> Result<HashMap<i64, PlayStats>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("
+            SELECT Tracks.id, Tracks.added_time, COALESCE(plays.play_count, 0), plays.last_played, COALESCE(skips.skip_count, 0)
+            FROM Tracks
+            LEFT JOIN (SELECT track_id, COUNT(*) AS play_count, MAX(playtime) AS last_played FROM Plays GROUP BY track_id) AS plays ON plays.track_id = Tracks.id
+            LEFT JOIN (SELECT track_id, COUNT(*) AS skip_count FROM Skips GROUP BY track_id) AS skips ON skips.track_id = Tracks.id
+            WHERE (?1) IS NULL OR Tracks.id = (?1);
+        ")?;
+        let rows = stmt.query_map(params![track_id], |row| {
+            let id: i64 = row.get(0)?;
+            let added_time: i64 = row.get(1)?;
+            let play_count: i64 = row.get(2)?;
+            let last_played: Option<i64> = row.get(3)?;
+            let skip_count: i64 = row.get(4)?;
+            Ok((id, PlayStats {
+                play_count: play_count as u64,
+                last_played,
+                skip_count: skip_count as u64,
+                added_time,
+            }))
+        })?;
+
+        let mut result = HashMap::new();
+        for row in rows {
+            let (id, stats) = row?;
+            result.insert(id, stats);
+        }
+
+        Ok(result)
+    }
+
+    //Totals, top lists & plays over time of the plays in the range, read by the statistics window
+    pub fn query_listening_stats(&self, range: StatsRange, period: StatsPeriod) -> Result<ListeningStats, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let since = range.since();
+
+        let mut stmt = conn.prepare("SELECT COUNT(*), COALESCE(SUM(Tracks.duration), 0) FROM Plays INNER JOIN Tracks ON Tracks.id = Plays.track_id WHERE Plays.playtime >= (?);")?;
+        let (total_plays, total_seconds): (i64, f64) = stmt.query_row([since], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let top = |sql: &str| -> Result<Vec<(i64, u64)>, rusqlite::Error> {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map(params![since, TOP_LIMIT], |row| {
+                let id: i64 = row.get(0)?;
+                let plays: i64 = row.get(1)?;
+                Ok((id, plays as u64))
+            })?;
+            rows.collect()
+        };
+
+        let top_tracks = top("SELECT track_id, COUNT(*) AS plays FROM Plays WHERE playtime >= (?) GROUP BY track_id ORDER BY plays DESC, MAX(playtime) DESC LIMIT (?);")?;
+        let top_albums = top("SELECT album_id, COUNT(*) AS plays FROM Plays WHERE playtime >= (?) GROUP BY album_id ORDER BY plays DESC, MAX(playtime) DESC LIMIT (?);")?;
+        let top_artists = top("SELECT Track_Artist_Junction.artist_id, COUNT(*) AS plays FROM Plays INNER JOIN Track_Artist_Junction ON Track_Artist_Junction.track_id = Plays.track_id WHERE Plays.playtime >= (?) GROUP BY Track_Artist_Junction.artist_id ORDER BY plays DESC, MAX(Plays.playtime) DESC LIMIT (?);")?;
+        let top_genres = top("SELECT Track_Genre_Junction.genre_id, COUNT(*) AS plays FROM Plays INNER JOIN Track_Genre_Junction ON Track_Genre_Junction.track_id = Plays.track_id WHERE Plays.playtime >= (?) GROUP BY Track_Genre_Junction.genre_id ORDER BY plays DESC, MAX(Plays.playtime) DESC LIMIT (?);")?;
+
+        let mut stmt = conn.prepare("SELECT strftime((?), playtime, 'unixepoch', 'localtime') AS period, COUNT(*) FROM Plays WHERE playtime >= (?) GROUP BY period ORDER BY period DESC LIMIT (?);")?;
+        let rows = stmt.query_map(params![period.format(), since, PERIOD_LIMIT], |row| {
+            let period: String = row.get(0)?;
+            let plays: i64 = row.get(1)?;
+            Ok((period, plays as u64))
+        })?;
+        let plays_per_period = rows.collect::<Result<Vec<(String, u64)>, _>>()?;
+
+        Ok(ListeningStats {
+            total_plays: total_plays as u64,
+            total_seconds,
+            top_tracks,
+            top_albums,
+            top_artists,
+            top_genres,
+            plays_per_period,
+        })
+    }
+
     pub fn query_artist_images(&self) -> Result<Vec<(i64, String, Vec<u8>)>, Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
@@ -1012,6 +2138,84 @@ impl Database {
 
 
 
+    // Used in rescan, to compare the files of a folder against the disk
+    fn query_music_folders(&self, tx: &Transaction) -> Result<Vec<(i64, String)>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT id, uri FROM Music_Folders;")?;
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            let uri: String = row.get(1)?;
+            Ok((id, uri))
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+
+        Ok(result)
+    }
+
+    fn query_folder_files(&self, tx: &Transaction, folder_id: i64) -> Result<HashMap<String, i64>, Box<dyn Error>> {
+        //files flagged for re-extraction by a migration never match their time on disk
+        let mut stmt = tx.prepare("SELECT File_URIs.uri, CASE WHEN File_URIs.reextract = 1 THEN -1 ELSE File_URIs.last_modified END FROM File_URIs INNER JOIN Folder_File_Junction ON File_URIs.id = Folder_File_Junction.file_id WHERE Folder_File_Junction.folder_id = (?);")?;
+        let rows = stmt.query_map([folder_id], |row| {
+            let uri: String = row.get(0)?;
+            let last_modified: i64 = row.get(1)?;
+            Ok((uri, last_modified))
+        })?;
+
+        let mut result = HashMap::new();
+        for row in rows {
+            let (uri, last_modified) = row?;
+            result.insert(uri, last_modified);
+        }
+
+        Ok(result)
+    }
+
+    pub fn query_track_id_by_uri(&self, tx: &Transaction, uri: &str) -> Result<Option<i64>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT Tracks.id FROM Tracks INNER JOIN File_URIs ON Tracks.file_uri_id = File_URIs.id WHERE File_URIs.uri = (?);")?;
+        let track_id = stmt.query_row([uri], |row| row.get(0)).optional()?;
+        Ok(track_id)
+    }
+
+    //Title, album, album artist, duration & size of the track of a file, compared against new files to find moved ones
+    pub fn query_file_identity(&self, tx: &Transaction, uri: &str) -> Result<Option<(String, String, String, f32, i64)>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT Tracks.title, Tracks.album_name, Tracks.album_artist, Tracks.duration, File_URIs.size FROM Tracks INNER JOIN File_URIs ON Tracks.file_uri_id = File_URIs.id WHERE File_URIs.uri = (?);")?;
+        let identity = stmt.query_row([uri], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))).optional()?;
+        Ok(identity)
+    }
+
+    pub fn query_genre_id_by_name(&self, tx: &Transaction, name: &str) -> Result<Option<i64>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT id FROM Genres WHERE name = (?);")?;
+        let genre_id = stmt.query_row([name], |row| row.get(0)).optional()?;
+        Ok(genre_id)
+    }
+
+    pub fn query_artist_id_by_name(&self, tx: &Transaction, name: &str) -> Result<Option<i64>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT id FROM Artists WHERE name = (?);")?;
+        let artist_id = stmt.query_row([name], |row| row.get(0)).optional()?;
+        Ok(artist_id)
+    }
+
+    pub fn query_album_id_by_title_and_artist(&self, tx: &Transaction, title: &str, artist_id: i64) -> Result<Option<i64>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT Albums.id FROM Albums INNER JOIN Album_Artist_Junction ON Albums.id = Album_Artist_Junction.album_id WHERE Albums.title = (?) AND Album_Artist_Junction.artist_id = (?) AND Album_Artist_Junction.position = 0;")?;
+        let album_id = stmt.query_row(params![title, artist_id], |row| row.get(0)).optional()?;
+        Ok(album_id)
+    }
+
+    pub fn query_cover_art_id_by_data(&self, tx: &Transaction, data: &[u8]) -> Result<Option<i64>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT id FROM Cover_Art WHERE data = (?);")?;
+        let cover_art_id = stmt.query_row([data], |row| row.get(0)).optional()?;
+        Ok(cover_art_id)
+    }
+
+    pub fn album_has_cover_art(&self, tx: &Transaction, album_id: i64) -> Result<bool, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT EXISTS(SELECT 1 FROM Album_Cover_Art_Junction WHERE album_id = (?) LIMIT 1);")?;
+        let exists: i32 = stmt.query_row([album_id], |row| row.get(0))?;
+        Ok(exists != 0)
+    }
+
     // MODEL POPULATION QUERIES
 
     // Used in model population
@@ -1114,15 +2318,15 @@ impl Database {
     }
 
      // Used in model population
-    pub fn query_art(&self) -> Result<Vec<(i64, Vec<u8>)>, Box<dyn Error>> {
+    //The art data is loaded on demand by the CoverArtCache
+    pub fn query_art_ids(&self) -> Result<Vec<i64>, Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
 
-        let mut stmt = conn.prepare("SELECT id, data FROM cover_art;")?;
+        let mut stmt = conn.prepare("SELECT id FROM cover_art;")?;
         let rows = stmt.query_map([], |row| {
             let id: i64 = row.get(0)?;
-            let data: Vec<u8> = row.get(1)?;
-            Ok((id, data))
+            Ok(id)
         })?;
 
         let mut result = Vec::new();
@@ -1133,14 +2337,14 @@ impl Database {
         Ok(result)
     }
 
-    pub fn query_art_by_id(&self, id: u64) -> Result<(i64, Vec<u8>), Box<dyn Error>> {
+    pub fn query_art_by_id(&self, id: u64) -> Result<i64, Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
 
-        let mut stmt = conn.prepare("SELECT data FROM cover_art WHERE id = (?);")?;
-        let data: Vec<u8> = stmt.query_row([id], |row| row.get(0))?;
+        let mut stmt = conn.prepare("SELECT id FROM cover_art WHERE id = (?);")?;
+        let id: i64 = stmt.query_row([id], |row| row.get(0))?;
 
-        Ok((id as i64, data))
+        Ok(id)
     }
 
      // Used in model population
@@ -1191,7 +2395,7 @@ impl Database {
     }
 
     // Used in model population
-    pub fn query_artists(&self) -> Result<Vec<(i64, String, Option<i64>, Option<Vec<i64>>)>, Box<dyn Error>> {
+    pub fn query_artists(&self) -> Result<Vec<(i64, String, Option<i64>, Option<Vec<i64>>, Vec<i64>)>, Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
         let mut stmt = conn.prepare("SELECT * FROM Artists;")?;
@@ -1218,17 +2422,34 @@ impl Database {
                 let album_id = r?;
                 albums.push(album_id);
             }
+
+            let appearances = self.query_artist_appearances(conn, artist_id)?;
             
             if albums.is_empty() {
-                result.push((artist_id, artist_name, image_id, None));
+                result.push((artist_id, artist_name, image_id, None, appearances));
             } else {
-                result.push((artist_id, artist_name, image_id, Some(albums)));
+                result.push((artist_id, artist_name, image_id, Some(albums), appearances));
             }
         }
 
         Ok(result)
     }
 
+    //Albums of other artists the artist is credited on
+    fn query_artist_appearances(&self, conn: &Connection, artist_id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+        let mut stmt = conn.prepare("SELECT DISTINCT Track_Album_Junction.album_id FROM Track_Album_Junction 
+            INNER JOIN Track_Artist_Junction ON Track_Album_Junction.track_id = Track_Artist_Junction.track_id 
+            WHERE Track_Artist_Junction.artist_id = (?1) 
+            AND Track_Album_Junction.album_id NOT IN (SELECT album_id FROM Album_Artist_Junction WHERE artist_id = (?1));")?;
+        let rows = stmt.query_map([artist_id], |row| row.get(0))?;
+
+        let mut albums = Vec::new();
+        for r in rows {
+            albums.push(r?);
+        }
+        Ok(albums)
+    }
+
     pub fn query_artist_by_id(&self, id: u64) -> Result<(i64, String), Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
@@ -1240,7 +2461,7 @@ impl Database {
     }
 
      // Used in model population
-    pub fn query_albums(&self) -> Result<Vec<(i64, String, String, String, String, Option<i64>, i64)>, Box<dyn Error>> {
+    pub fn query_albums(&self) -> Result<Vec<(i64, String, String, String, String, Option<i64>, i64, bool)>, Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
         let mut stmt = conn.prepare("SELECT * FROM albums;")?;
@@ -1251,28 +2472,29 @@ impl Database {
             let album_artist: String = row.get(2)?;
             let date: String = row.get(3)?;
             let genre: String = row.get(4)?;
+            let compilation: bool = row.get(5)?;
 
-            Ok((id, title, album_artist, date, genre))
+            Ok((id, title, album_artist, date, genre, compilation))
         })?;
 
 
         let mut result = Vec::new();
         for row in rows {
-            let (album_id, title, artist, date, genre) = row?;
+            let (album_id, title, artist, date, genre, compilation) = row?;
 
-            let mut stmt = conn.prepare("SELECT artist_id FROM Album_Artist_Junction WHERE album_id = ?;")?;
+            let mut stmt = conn.prepare("SELECT artist_id FROM Album_Artist_Junction WHERE album_id = ? ORDER BY position, id LIMIT 1;")?;
             let album_artist_id: i64 = stmt.query_row([album_id], |row| row.get(0))?;
 
             let mut stmt = conn.prepare("SELECT cover_art_id FROM Album_Cover_Art_Junction WHERE album_id = ?;")?;
             let cover_art_id: Option<i64> = stmt.query_row([album_id], |row| row.get(0)).optional()?;
                           
-            result.push((album_id, title, artist, date, genre, cover_art_id, album_artist_id));
+            result.push((album_id, title, artist, date, genre, cover_art_id, album_artist_id, compilation));
         }
 
         Ok(result)
     }
 
-    pub fn query_album_by_id(&self, id: u64) -> Result<(i64, String, String, String, String, Option<i64>, i64, Option<i64>), Box<dyn Error>> {
+    pub fn query_album_by_id(&self, id: u64) -> Result<(i64, String, String, String, String, Option<i64>, i64, Vec<i64>, bool), Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
         let mut stmt = conn.prepare("SELECT * FROM Albums WHERE id = (?);")?;
@@ -1282,23 +2504,28 @@ impl Database {
             let album_artist: String = row.get(2)?;
             let date: String = row.get(3)?;
             let genre: String = row.get(4)?;
-            Ok((id, title, album_artist, date, genre))
+            let compilation: bool = row.get(5)?;
+            Ok((id, title, album_artist, date, genre, compilation))
         })?;
 
         let mut result = Vec::new();
         for row in rows {
-            let (album_id, title, artist, date, genre) = row?;
+            let (album_id, title, artist, date, genre, compilation) = row?;
 
-            let mut stmt = conn.prepare("SELECT artist_id FROM Album_Artist_Junction WHERE album_id = ?;")?;
+            let mut stmt = conn.prepare("SELECT artist_id FROM Album_Artist_Junction WHERE album_id = ? ORDER BY position, id LIMIT 1;")?;
             let album_artist_id: i64 = stmt.query_row([album_id], |row| row.get(0))?;
 
             let mut stmt = conn.prepare("SELECT cover_art_id FROM Album_Cover_Art_Junction WHERE album_id = ?;")?;
             let cover_art_id: Option<i64> = stmt.query_row([album_id], |row| row.get(0)).optional()?;
             
-            let mut stmt = conn.prepare("SELECT genre_id FROM Album_Genre_Junction WHERE album_id = ?;")?;
-            let genre_id: Option<i64> = stmt.query_row([album_id], |row| row.get(0)).optional()?;
+            let mut stmt = conn.prepare("SELECT genre_id FROM Album_Genre_Junction WHERE album_id = ? ORDER BY id;")?;
+            let genre_rows = stmt.query_map([album_id], |row| row.get(0))?;
+            let mut genre_ids: Vec<i64> = Vec::new();
+            for r in genre_rows {
+                genre_ids.push(r?);
+            }
                 
-            result.push((album_id, title, artist, date, genre, cover_art_id, album_artist_id, genre_id));
+            result.push((album_id, title, artist, date, genre, cover_art_id, album_artist_id, genre_ids, compilation));
         }
 
         let result = result.remove(0);
@@ -1308,7 +2535,7 @@ impl Database {
 
 
     // Used in model population
-    pub fn query_tracks(&self) -> Result<Vec<(i64, String, String, String, String, f64, i64, i64, String, String, i64, i64, Option<i64>)>, Box<dyn Error>> {
+    pub fn query_tracks(&self) -> Result<Vec<(i64, String, String, String, String, f64, i64, i64, String, String, i64, i64, Option<i64>, String, Vec<i64>, String, Vec<i64>, HashMap<String, String>)>, Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
         let conn = conn.as_ref().ok_or("Connection not established")?;
         let mut stmt = conn.prepare("SELECT * FROM tracks;")?;
@@ -1323,18 +2550,22 @@ impl Database {
             let disc_number: i64 = row.get(7)?;
             let album_artist: String = row.get(8)?;
             let file_uri_id: i64 = row.get(9)?;
-            Ok((id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id))
+            let artist: String = row.get(10)?;
+            let genre: String = row.get(11)?;
+            Ok((id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id, artist, genre))
         })?;
 
         let mut result = Vec::new();
         for row in rows {
-            let (track_id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id) = row?;
+            let (track_id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id, artist, genre) = row?;
 
             let mut stmt = conn.prepare("SELECT album_id FROM Track_Album_Junction WHERE track_id = ?;")?;
             let album_id: i64 = stmt.query_row([track_id], |row| row.get(0))?;
             
-            let mut stmt = conn.prepare("SELECT artist_id FROM Track_Artist_Junction WHERE track_id = ?;")?;
-            let artist_id: i64 = stmt.query_row([track_id], |row| row.get(0))?;
+            let artist_id = self.query_album_artist_id(conn, album_id)?;
+            let artist_ids = self.query_track_artist_ids(conn, track_id)?;
+            let genre_ids = self.query_track_genre_ids(conn, track_id)?;
+            let tags = self.query_track_tags(conn, track_id)?;
 
             let mut stmt = conn.prepare("SELECT cover_art_id FROM Track_Cover_Art_Junction WHERE track_id = ?;")?;
             let cover_art_id: Option<i64> = stmt.query_row([track_id], |row| row.get(0)).optional()?;
@@ -1342,12 +2573,154 @@ impl Database {
             let mut stmt = conn.prepare("SELECT uri FROM File_URIs WHERE id = ?")?;
             let uri: String = stmt.query_row([file_uri_id], |row| row.get(0))?;
 
-            result.push((track_id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, uri, artist_id, album_id, cover_art_id));
+            result.push((track_id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, uri, artist_id, album_id, cover_art_id, artist, artist_ids, genre, genre_ids, tags));
         }
 
         Ok(result)
     }
 
+    // Used in targeted model updates, after a rescan or a folder change
+    pub fn query_track_by_id(&self, id: u64) -> Result<(i64, String, String, String, String, f64, i64, i64, String, String, i64, i64, Option<i64>, String, Vec<i64>, String, Vec<i64>, HashMap<String, String>), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT * FROM Tracks WHERE id = (?);")?;
+        let (track_id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id, artist, genre) = stmt.query_row([id], |row| {
+            let id: i64 = row.get(0)?;
+            let title: String = row.get(1)?;
+            let filetype: String = row.get(2)?;
+            let album_name: String = row.get(3)?;
+            let date: String = row.get(4)?;
+            let duration: f64 = row.get(5)?;
+            let track_number: i64 = row.get(6)?;
+            let disc_number: i64 = row.get(7)?;
+            let album_artist: String = row.get(8)?;
+            let file_uri_id: i64 = row.get(9)?;
+            let artist: String = row.get(10)?;
+            let genre: String = row.get(11)?;
+            Ok((id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id, artist, genre))
+        })?;
+
+        let mut stmt = conn.prepare("SELECT album_id FROM Track_Album_Junction WHERE track_id = ?;")?;
+        let album_id: i64 = stmt.query_row([track_id], |row| row.get(0))?;
+        
+        let artist_id = self.query_album_artist_id(conn, album_id)?;
+        let artist_ids = self.query_track_artist_ids(conn, track_id)?;
+        let genre_ids = self.query_track_genre_ids(conn, track_id)?;
+        let tags = self.query_track_tags(conn, track_id)?;
+
+        let mut stmt = conn.prepare("SELECT cover_art_id FROM Track_Cover_Art_Junction WHERE track_id = ?;")?;
+        let cover_art_id: Option<i64> = stmt.query_row([track_id], |row| row.get(0)).optional()?;
+
+        let mut stmt = conn.prepare("SELECT uri FROM File_URIs WHERE id = ?")?;
+        let uri: String = stmt.query_row([file_uri_id], |row| row.get(0))?;
+
+        Ok((track_id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, uri, artist_id, album_id, cover_art_id, artist, artist_ids, genre, genre_ids, tags))
+    }
+
+    //Release & release group ids of an album, with the result of a previous cover art lookup
+    pub fn query_album_musicbrainz(&self, album_id: i64) -> Result<(String, String, Option<Option<String>>), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+
+        let mut stmt = conn.prepare("SELECT musicbrainz_id, musicbrainz_release_group_id FROM Albums WHERE id = (?);")?;
+        let (release_id, release_group_id): (String, String) = stmt.query_row([album_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut stmt = conn.prepare("SELECT art_path FROM MusicBrainz_Cover_Art WHERE album_id = (?);")?;
+        let art_path: Option<Option<String>> = stmt.query_row([album_id], |row| row.get(0)).optional()?;
+
+        Ok((release_id, release_group_id, art_path))
+    }
+
+    //Paths of the tracks missing a replaygain or bpm tag, that were never analyzed
+    fn query_tracks_to_analyze(&self) -> Result<Vec<(i64, String)>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare(
+            "SELECT Tracks.id, File_URIs.uri FROM Tracks
+            INNER JOIN File_URIs ON File_URIs.id = Tracks.file_uri_id
+            WHERE Tracks.id NOT IN (SELECT track_id FROM Track_Analysis)
+            AND (
+                NOT EXISTS (SELECT 1 FROM Track_Tags WHERE Track_Tags.track_id = Tracks.id AND key = 'replaygain_track_gain')
+                OR NOT EXISTS (SELECT 1 FROM Track_Tags WHERE Track_Tags.track_id = Tracks.id AND key = 'bpm')
+            );"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            let uri: String = row.get(1)?;
+            Ok((id, uri))
+        })?;
+
+        let mut tracks = Vec::new();
+        for r in rows {
+            tracks.push(r?);
+        }
+        Ok(tracks)
+    }
+
+    //Primary artist of an album
+    fn query_album_artist_id(&self, conn: &Connection, album_id: i64) -> Result<i64, Box<dyn Error>> {
+        let mut stmt = conn.prepare("SELECT artist_id FROM Album_Artist_Junction WHERE album_id = ? ORDER BY position, id LIMIT 1;")?;
+        let artist_id: i64 = stmt.query_row([album_id], |row| row.get(0))?;
+        Ok(artist_id)
+    }
+
+    fn query_track_genre_ids(&self, conn: &Connection, track_id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+        let mut stmt = conn.prepare("SELECT genre_id FROM Track_Genre_Junction WHERE track_id = ? ORDER BY id;")?;
+        let rows = stmt.query_map([track_id], |row| row.get(0))?;
+
+        let mut genre_ids = Vec::new();
+        for r in rows {
+            genre_ids.push(r?);
+        }
+        Ok(genre_ids)
+    }
+
+    //Extended tags of a track, keyed by the tag name, analyzed values fill in the missing tags
+    fn query_track_tags(&self, conn: &Connection, track_id: i64) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let mut stmt = conn.prepare("SELECT key, value FROM Track_Tags WHERE track_id = ?;")?;
+        let rows = stmt.query_map([track_id], |row| {
+            let key: String = row.get(0)?;
+            let value: String = row.get(1)?;
+            Ok((key, value))
+        })?;
+
+        let mut tags = HashMap::new();
+        for r in rows {
+            let (key, value) = r?;
+            tags.insert(key, value);
+        }
+
+        let mut stmt = conn.prepare("SELECT track_gain, track_peak, bpm FROM Track_Analysis WHERE track_id = ?;")?;
+        let analysis: Option<(Option<f64>, Option<f64>, Option<f64>)> = stmt.query_row([track_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        }).optional()?;
+
+        if let Some((track_gain, track_peak, bpm)) = analysis {
+            if let Some(gain) = track_gain {
+                tags.entry("replaygain_track_gain".to_string()).or_insert(format!("{:.2} dB", gain));
+            }
+            if let Some(peak) = track_peak {
+                tags.entry("replaygain_track_peak".to_string()).or_insert(format!("{:.6}", peak));
+            }
+            if let Some(bpm) = bpm {
+                tags.entry("bpm".to_string()).or_insert(format!("{:.0}", bpm));
+            }
+        }
+        Ok(tags)
+    }
+
+    //Artists credited on a track, in credit order
+    fn query_track_artist_ids(&self, conn: &Connection, track_id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+        let mut stmt = conn.prepare("SELECT artist_id FROM Track_Artist_Junction WHERE track_id = ? ORDER BY position;")?;
+        let rows = stmt.query_map([track_id], |row| row.get(0))?;
+
+        let mut artist_ids = Vec::new();
+        for r in rows {
+            artist_ids.push(r?);
+        }
+        Ok(artist_ids)
+    }
+
     //Used in naming a new playlist
     pub fn query_n_playlists(&self, playlist_id: Option<i64>) -> Result<i64, Box<dyn Error>> {
         let conn = self.imp().conn.borrow();
@@ -1367,56 +2740,43 @@ impl Database {
         }
     }
 
-    //Used in the home page
-    // pub fn query_most_recent_tracks(&self) -> Result<Vec<i64>, Box<dyn Error>> {
-    //     let conn = self.imp().conn.borrow();
-    //     let conn = conn.as_ref().ok_or("Connection not established")?;
-    //     let mut stmt = conn.prepare("SELECT * FROM plays ORDER BY playtime;")?;
-    //     let rows = stmt.query_map([], |row| {
-    //         let track_id: i64 = row.get(2)?;
-    //         Ok(track_id)
-    //     })?;
+    //Used by the recently played collection, most recent first
+    pub fn query_most_recent_tracks(&self, limit: usize) -> Result<Vec<i64>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT track_id FROM Plays GROUP BY track_id ORDER BY MAX(playtime) DESC LIMIT (?);")?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            let track_id: i64 = row.get(0)?;
+            Ok(track_id)
+        })?;
 
-    //     let mut result = Vec::new();
-    //     for row in rows {
-    //         let id = row?;
-    //         if !result.contains(&id) {
-    //             result.push(id);
-    //         }
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
 
-    //         if result.len() >= 20 {
-    //             break;
-    //         }
-           
-    //     }
+        Ok(result)
+    }
 
-    //     Ok(result)
-    // }
+    //Used by the recently added collection, albums ordered by their newest imported track
+    pub fn query_most_recent_albums(&self, limit: usize) -> Result<Vec<i64>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT Track_Album_Junction.album_id FROM Tracks
+            INNER JOIN Track_Album_Junction ON Tracks.id = Track_Album_Junction.track_id
+            GROUP BY Track_Album_Junction.album_id ORDER BY MAX(Tracks.added_time) DESC LIMIT (?);")?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            let album_id: i64 = row.get(0)?;
+            Ok(album_id)
+        })?;
 
-    //Used in the home page
-    // fn query_most_recent_albums(&self) -> Result<Vec<i64>, Box<dyn Error>> {
-    //     let conn = self.imp().conn.borrow();
-    //     let conn = conn.as_ref().ok_or("Connection not established")?;
-    //     let mut stmt = conn.prepare("SELECT * FROM plays ORDER BY playtime;")?;
-    //     let rows = stmt.query_map([], |row| {
-    //         let album_id: i64 = row.get(3)?;
-    //         Ok(album_id)
-    //     })?;
-
-    //     let mut result = Vec::new();
-    //     for row in rows {
-    //         let id = row?;
-    //         if !result.contains(&id) {
-    //             result.push(id);
-    //         }
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
 
-    //         if result.len() >= 20 {
-    //             break;
-    //         }
-           
-    //     }
-    //     Ok(result)
-    // }
+        Ok(result)
+    }
 
     // ######
     // # MODIFY VALUES 
@@ -1438,6 +2798,56 @@ impl Database {
         Ok(())
     }
 
+    //Used in rescan, keeps the track id so plays & playlist entries survive a tag change
+    pub fn update_track_full(&self, tx: &Transaction, track_id: i64,
+        title: String, filetype: String, album_name: String, album_artist: String, artist: String, date: String, genre: String,
+        duration: f32, track_number: u32, disc_number: u32,  
+        album_id: i64, artist_ids: &[i64], genre_ids: &[i64], cover_art_id: Option<i64>,
+        last_modified: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("UPDATE Tracks SET title = (?), filetype = (?), album_name = (?), date = (?), genre = (?), duration = (?), track_number = (?), disc_number = (?), album_artist = (?), artist = (?) WHERE id = (?);")?;
+        stmt.execute(params![title, filetype, album_name, date, genre, duration, track_number, disc_number, album_artist, artist, track_id])?;
+
+        let mut stmt = tx.prepare("SELECT File_URIs.id, File_URIs.uri FROM File_URIs INNER JOIN Tracks ON Tracks.file_uri_id = File_URIs.id WHERE Tracks.id = (?);")?;
+        let (file_id, file_uri): (i64, String) = stmt.query_row([track_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut stmt = tx.prepare("UPDATE File_URIs SET last_modified = (?), size = (?), reextract = 0 WHERE id = (?);")?;
+        stmt.execute(params![last_modified.timestamp(), file_size(&file_uri), file_id])?;
+
+        //the audio may have changed with the file
+        let mut stmt = tx.prepare("DELETE FROM Track_Analysis WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        let mut stmt = tx.prepare("UPDATE Track_Album_Junction SET album_id = (?) WHERE track_id = (?);")?;
+        stmt.execute(params![album_id, track_id])?;
+
+        //the old album & artist are pruned once the track was their last one, the plays must not reference them
+        repoint_plays(tx, track_id, album_id)?;
+
+        let mut stmt = tx.prepare("DELETE FROM Track_Artist_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        for (position, artist_id) in artist_ids.iter().enumerate() {
+            self.add_track_artist_junction(&tx, track_id, *artist_id, position)?;
+        }
+
+        let mut stmt = tx.prepare("DELETE FROM Track_Genre_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        for genre_id in genre_ids {
+            self.add_track_genre_junction(&tx, track_id, *genre_id)?;
+        }
+
+        let mut stmt = tx.prepare("DELETE FROM Track_Cover_Art_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        if let Some(id) = cover_art_id {
+            self.add_track_cover_art_junction(&tx, track_id, id)?;
+        }
+
+        Ok(())
+    }
+
     pub fn change_playlist_title_and_or_description(&self, playlist_id: i64, new_title: Option<String>, new_description: Option<String>) -> Result<(), Box<dyn Error>> {
         let mut conn = self.imp().conn.borrow_mut();
         let conn = conn.as_mut().ok_or("Connection not established")?;
@@ -1459,15 +2869,65 @@ impl Database {
     }
 
 
-    pub fn rename_playlist(&self, playlist_id: i64, new_title: String) -> Result<(), Box<dyn Error>> {
-        let mut conn = self.imp().conn.borrow_mut();
-        let conn = conn.as_mut().ok_or("Connection not established")?;
-        let tx = conn.transaction()?;
-        self.modify_playlist_title(&tx, playlist_id, new_title)?;
-        self.update_playlist_modify_time(&tx, playlist_id)?;
-        tx.commit()?;
-        send!(self.imp().model_sender, ModelAction::PopulatePlaylist(playlist_id as u64));
-        Ok(())
+    pub fn rename_playlist(&self, playlist_id: i64, new_title: String) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.imp().conn.borrow_mut();
+        let conn = conn.as_mut().ok_or("Connection not established")?;
+        let tx = conn.transaction()?;
+        self.modify_playlist_title(&tx, playlist_id, new_title)?;
+        self.update_playlist_modify_time(&tx, playlist_id)?;
+        tx.commit()?;
+        send!(self.imp().model_sender, ModelAction::PopulatePlaylist(playlist_id as u64));
+        Ok(())
+    }
+
+    // SMART PLAYLISTS
+    //Only the rules are stored, the tracks are evaluated by the model
+    fn create_smart_playlist(&self, title: String, description: String, rules: SmartRules) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let now = chrono::offset::Utc::now();
+        let mut stmt = conn.prepare("INSERT INTO Smart_Playlists (title, description, rules, creation_time, modify_time) VALUES ( ?, ?, ?, ?, ? );")?;
+        stmt.execute(params![title, description, rules.to_json()?, now, now])?;
+        send!(self.imp().model_sender, ModelAction::PopulateSmartPlaylists);
+        Ok(())
+    }
+
+    fn update_smart_playlist(&self, smart_id: i64, title: String, description: String, rules: SmartRules) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("UPDATE Smart_Playlists SET title = (?), description = (?), rules = (?), modify_time = (?) WHERE id = (?);")?;
+        let updated = stmt.execute(params![title, description, rules.to_json()?, chrono::offset::Utc::now(), smart_id])?;
+        if updated == 0 {
+            return Err(Box::new(DatabaseError(format!("smart playlist {} does not exist", smart_id))));
+        }
+        send!(self.imp().model_sender, ModelAction::PopulateSmartPlaylists);
+        Ok(())
+    }
+
+    fn delete_smart_playlist(&self, smart_id: i64) -> Result<(), Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("DELETE FROM Smart_Playlists WHERE id = (?);")?;
+        stmt.execute(params![smart_id])?;
+        send!(self.imp().model_sender, ModelAction::PopulateSmartPlaylists);
+        Ok(())
+    }
+
+    //(id, title, description, rules json, creation time, modify time)
+    pub fn query_smart_playlists(&self) -> Result<Vec<(i64, String, String, String, DateTime<Utc>, DateTime<Utc>)>, Box<dyn Error>> {
+        let conn = self.imp().conn.borrow();
+        let conn = conn.as_ref().ok_or("Connection not established")?;
+        let mut stmt = conn.prepare("SELECT id, title, description, rules, creation_time, modify_time FROM Smart_Playlists;")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+
+        Ok(result)
     }
 
     fn modify_playlist_title(&self, tx: &Transaction, playlist_id: i64, new_title: String) -> Result<(), Box<dyn Error>> {
@@ -1562,6 +3022,15 @@ impl Database {
     //REMOVE MUSIC FOLDER
 
     pub fn try_remove_folder(&self, path: String) -> Result<(), Box<dyn Error>> {
+        //playlist entries & plays of the folder are deleted with it, keep a copy to restore from
+        match self.auto_backup() {
+            Ok(backup_path) => debug!("backed up library to {:?} before removing {}", backup_path, path),
+            Err(e) => {
+                error!("Unable to back up library before removing {}: {}", path, e);
+                add_error_toast(i18n("Unable to back up playlists & plays before removing the folder."));
+            },
+        }
+
         {
            
             let mut conn = self.imp().conn.borrow_mut();
@@ -1573,11 +3042,94 @@ impl Database {
             tx.commit()?;
         }
         let imp = self.imp();
+        imp.watcher.unwatch(&path);
         let mut folders = imp.folders.borrow_mut().clone();
         folders.remove(&path);
         let music_folders = folders.into_iter().collect::<Vec<String>>();
         imp.settings.set_strv("music-folders", music_folders.as_slice())?;
+        self.emit_by_name::<()>("import-report-changed", &[]);
+
+        Ok(())
+    }
+
+    //Rewrites the path of a folder & of its files, keeping the tracks, plays, ratings & playlist entries
+    pub fn relocate_folder(&self, old_path: String, new_path: PathBuf) -> Result<String, Box<dyn Error>> {
+        let imp = self.imp();
+        let new_path = new_path.into_os_string().into_string().map_err(|_| DatabaseError("Folder path is not valid unicode".to_string()))?;
+
+        if imp.folders.borrow().contains(&new_path) {
+            return Err(Box::new(DatabaseError(format!("{} is already a music folder", new_path))));
+        }
+
+        if imp.imports.borrow().iter().any(|status| status.folder == old_path) {
+            return Err(Box::new(DatabaseError(format!("{} is being imported", old_path))));
+        }
+
+        let (track_ids, known_files) = {
+            let mut conn = imp.conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+
+            let mut stmt = tx.prepare("SELECT id FROM Music_Folders WHERE uri = (?)")?;
+            let folder_id: i64 = stmt.query_row([&old_path], |row| row.get(0))?;
+
+            let mut stmt = tx.prepare("UPDATE Music_Folders SET uri = (?) WHERE id = (?);")?;
+            stmt.execute(params![new_path, folder_id])?;
+
+            let mut stmt = tx.prepare("UPDATE File_URIs SET uri = (?1) || substr(uri, length(?2) + 1) WHERE substr(uri, 1, length(?2)) = (?2) AND id IN (SELECT file_id FROM Folder_File_Junction WHERE folder_id = (?3));")?;
+            stmt.execute(params![new_path, old_path, folder_id])?;
+
+            let mut stmt = tx.prepare("UPDATE Import_Report SET folder = (?1), uri = (?1) || substr(uri, length(?2) + 1) WHERE folder = (?2);")?;
+            stmt.execute(params![new_path, old_path])?;
+
+            let mut stmt = tx.prepare("UPDATE OR REPLACE Track_Ratings SET uri = (?1) || substr(uri, length(?2) + 1) WHERE substr(uri, 1, length(?2) + 1) = (?2) || '/';")?;
+            stmt.execute(params![new_path, old_path])?;
+
+            //imported playlist files are recognized by their path, the rescan would import them again
+            let mut stmt = tx.prepare("UPDATE OR REPLACE Playlist_Files SET uri = (?1) || substr(uri, length(?2) + 1) WHERE substr(uri, 1, length(?2) + 1) = (?2) || '/';")?;
+            stmt.execute(params![new_path, old_path])?;
+
+            let mut stmt = tx.prepare("SELECT track_id FROM Track_Folder_Junction WHERE folder_id = (?);")?;
+            let track_ids = stmt.query_map([folder_id], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<i64>, _>>()?;
+
+            let known_files = self.query_folder_files(&tx, folder_id)?;
+            tx.commit()?;
+
+            (track_ids.into_iter().map(|id| id as u64).collect::<Vec<u64>>(), known_files)
+        };
+
+        //loaded tracks, including the queued ones, take the new paths before the model repopulates
+        send!(imp.model_sender, ModelAction::UpdateTracks(track_ids));
+
+        imp.watcher.unwatch(&old_path);
+        let mut folders = imp.folders.borrow().clone();
+        folders.remove(&old_path);
+        folders.insert(new_path.clone());
+        let music_folders = folders.into_iter().collect::<Vec<String>>();
+        imp.settings.set_strv("music-folders", music_folders.as_slice())?;
+
+        if imp.settings.boolean("watch-music-folders") {
+            imp.watcher.watch_folder(&new_path);
+        }
+
+        self.emit_by_name::<()>("import-report-changed", &[]);
+
+        //picks up whatever changed in the folder while it was away
+        self.importer().rescan_folder(new_path.clone(), known_files, imp.db_sender.clone());
+
+        Ok(new_path)
+    }
 
+    //Points the file of a track to its new path, the tags are updated by the rescan
+    fn relink_file(&self, tx: &Transaction, old_uri: &str, new_uri: &str) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("UPDATE File_URIs SET uri = (?) WHERE uri = (?);")?;
+        stmt.execute(params![new_uri, old_uri])?;
+        let mut stmt = tx.prepare("UPDATE OR REPLACE Track_Ratings SET uri = (?) WHERE uri = (?);")?;
+        stmt.execute(params![new_uri, old_uri])?;
+        let mut stmt = tx.prepare("UPDATE OR REPLACE Playlist_Files SET uri = (?) WHERE uri = (?);")?;
+        stmt.execute(params![new_uri, old_uri])?;
+        self.clear_import_problems(tx, old_uri)?;
         Ok(())
     }
 
@@ -1586,7 +3138,7 @@ impl Database {
         
         //GET ID OF FOLDER FROM FOLDER TABLE
         let mut stmt = tx.prepare("SELECT id FROM Music_Folders WHERE uri = (?)")?;
-        let folder_id: i64 = stmt.query_row([path], |row| row.get(0))?;
+        let folder_id: i64 = stmt.query_row([&path], |row| row.get(0))?;
 
         debug!("folder id: {}", folder_id);
 
@@ -1607,37 +3159,11 @@ impl Database {
         for row in rows {
             let track_id = row?;
 
-            debug!("removing Track_Folder_Junction track_id = {}", track_id);
-            let mut stmt = tx.prepare("DELETE FROM Track_Folder_Junction WHERE track_id = (?);")?;
-            stmt.execute(params![track_id])?;
-
-            let mut stmt = tx.prepare("DELETE FROM Track_Folder_Junction WHERE track_id = (?);")?;
-            stmt.execute(params![track_id])?;
-
-            debug!("removing Track_Cover_Art_Junction track_id = {}", track_id);
-            let mut stmt = tx.prepare("DELETE FROM Track_Cover_Art_Junction WHERE track_id = (?);")?;
-            stmt.execute(params![track_id])?;
-
-            debug!("removing Track_Artist_Junction track_id = {}", track_id);
-            let mut stmt = tx.prepare("DELETE FROM Track_Artist_Junction WHERE track_id = (?);")?;
-            stmt.execute(params![track_id])?;
-
-            debug!("removing Track_Album_Junction track_id = {}", track_id);
-            let mut stmt = tx.prepare("DELETE FROM Track_Album_Junction WHERE track_id = (?);")?;
-            stmt.execute(params![track_id])?;
-
             debug!("removing Playlist_Entry_Track_Junction track_id = {}", track_id);
             let mut stmt = tx.prepare("DELETE FROM Playlist_Entry_Track_Junction WHERE track_id = (?);")?;
             stmt.execute(params![track_id])?;
 
- 
-            debug!("removing Plays track_id = {}", track_id);
-            let mut stmt = tx.prepare("DELETE FROM Plays WHERE track_id = (?);")?;
-            stmt.execute(params![track_id])?;
-
-            debug!("removing Tracks track_id = {}", track_id);
-            let mut stmt = tx.prepare("DELETE FROM Tracks WHERE id = (?);")?;
-            stmt.execute(params![track_id])?;
+            self.remove_track(&tx, track_id)?;
         }
 
         //delete album, artist, playlist, play if none 
@@ -1647,6 +3173,78 @@ impl Database {
         stmt.execute(params![folder_id])?;
         debug!("removed folder");
 
+        let mut stmt = tx.prepare("DELETE FROM Import_Report WHERE folder = (?);")?;
+        stmt.execute(params![path])?;
+
+        //playlist files are imported again if the folder is added back
+        let mut stmt = tx.prepare("DELETE FROM Playlist_Files WHERE substr(uri, 1, length(?1) + 1) = ?1 || '/';")?;
+        stmt.execute(params![path])?;
+
+        Ok(())
+    }
+
+
+    fn remove_track(&self, tx: &Transaction, track_id: i64) -> Result<(), Box<dyn Error>> {
+        debug!("removing Track_Folder_Junction track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Track_Folder_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Track_Cover_Art_Junction track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Track_Cover_Art_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Track_Artist_Junction track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Track_Artist_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Track_Genre_Junction track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Track_Genre_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Track_Tags track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Track_Tags WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Track_Analysis track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Track_Analysis WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Track_Album_Junction track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Track_Album_Junction WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Plays track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Plays WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Skips track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Skips WHERE track_id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        debug!("removing Tracks track_id = {}", track_id);
+        let mut stmt = tx.prepare("DELETE FROM Tracks WHERE id = (?);")?;
+        stmt.execute(params![track_id])?;
+
+        Ok(())
+    }
+
+    //Removes every playlist entry of a track, keeping the remaining positions in order
+    fn remove_track_from_playlists(&self, tx: &Transaction, track_id: i64) -> Result<(), Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT playlist_entry_id FROM Playlist_Entry_Track_Junction WHERE track_id = (?);")?;
+        let rows = stmt.query_map([track_id], |row| {
+            let id: i64 = row.get(0)?;
+            Ok(id)
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+
+        for entry_id in entries {
+            self.delete_track_from_playlist(&tx, entry_id)?;
+        }
+
         Ok(())
     }
 
@@ -1665,6 +3263,18 @@ impl Database {
         Ok(())
     }
 
+    //Same as prune_all, but keeps playlists that lost all their tracks
+    fn prune_library(&self, tx: &Transaction) -> Result<(), Box<dyn Error>> {
+        debug!("pruning library");
+        self.prune_albums(&tx)?;
+        self.prune_genres(&tx)?;
+        self.prune_artists(&tx)?;
+        self.prune_cover_art(&tx)?;
+        self.prune_artist_image(&tx)?;
+        self.prune_file_uri(&tx)?;
+        Ok(())
+    }
+
     fn prune_albums(&self, tx: &Transaction) -> Result<(), Box<dyn Error>> {
         debug!("prune_albums");
 
@@ -1690,6 +3300,9 @@ impl Database {
                 let mut stmt = tx.prepare("DELETE FROM Album_Genre_Junction WHERE album_id = (?);")?;
                 stmt.execute(params![album_id])?;
 
+                let mut stmt = tx.prepare("DELETE FROM MusicBrainz_Cover_Art WHERE album_id = (?);")?;
+                stmt.execute(params![album_id])?;
+
                 let mut stmt = tx.prepare("DELETE FROM Albums WHERE id = (?);")?;
                 stmt.execute(params![album_id])?;
             }
@@ -1709,7 +3322,7 @@ impl Database {
         for row in rows {
             let genre_id = row?;
 
-            let mut stmt = tx.prepare("SELECT EXISTS(SELECT 1 FROM Album_Genre_Junction WHERE genre_id = (?) LIMIT 1);")?;
+            let mut stmt = tx.prepare("SELECT EXISTS(SELECT 1 FROM Album_Genre_Junction WHERE genre_id = (?1) LIMIT 1) OR EXISTS(SELECT 1 FROM Track_Genre_Junction WHERE genre_id = (?1) LIMIT 1);")?;
             let exists: i32 = stmt.query_row([genre_id], |row| row.get(0))?;
             
             if exists == 0 {
@@ -1791,6 +3404,7 @@ impl Database {
             if exists == 0 {
                 let mut stmt = tx.prepare("DELETE FROM Cover_Art WHERE id = (?);")?;
                 stmt.execute(params![cover_art_id])?;
+                cover_art_cache::remove_thumbnails(cover_art_id);
             }
         }
         Ok(())
@@ -1958,247 +3572,399 @@ impl Database {
     }
 
 
+    //BACKUP & RESTORE
+
+    pub fn backup_library(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        let backup = {
+            let mut conn = self.imp().conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+            let backup = self.build_backup(&tx)?;
+            tx.commit()?;
+            backup
+        };
+        backup.write(&path)
+    }
+
+    fn auto_backup(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let path = backup::auto_backup_path();
+        self.backup_library(path.clone())?;
+        Ok(path)
+    }
+
+    fn build_backup(&self, tx: &Transaction) -> Result<LibraryBackup, Box<dyn Error>> {
+        let library = self.query_track_refs(tx)?;
+
+        let mut tracks: Vec<TrackRef> = Vec::new();
+        let mut track_indices: HashMap<i64, usize> = HashMap::new();
+        let mut index_of = |track_id: i64| -> Option<usize> {
+            if let Some(index) = track_indices.get(&track_id) {
+                return Some(*index);
+            }
+            let track_ref = library.get(&track_id)?;
+            tracks.push(track_ref.clone());
+            track_indices.insert(track_id, tracks.len() - 1);
+            Some(tracks.len() - 1)
+        };
+
+        let mut playlists = Vec::new();
+        let mut stmt = tx.prepare("SELECT id, title, description, creation_time, modify_time FROM Playlists;")?;
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            let title: String = row.get(1)?;
+            let description: String = row.get(2)?;
+            let creation_time: DateTime<Utc> = row.get(3)?;
+            let modify_time: DateTime<Utc> = row.get(4)?;
+            Ok((id, title, description, creation_time, modify_time))
+        })?;
+
+        for row in rows {
+            let (playlist_id, title, description, creation_time, modify_time) = row?;
+
+            let mut stmt = tx.prepare("SELECT Playlist_Entry_Track_Junction.track_id FROM Playlist_Entry_Playlist_Junction 
+                INNER JOIN Playlist_Entries ON Playlist_Entry_Playlist_Junction.playlist_entry_id = Playlist_Entries.id 
+                INNER JOIN Playlist_Entry_Track_Junction ON Playlist_Entries.id = Playlist_Entry_Track_Junction.playlist_entry_id 
+                WHERE Playlist_Entry_Playlist_Junction.playlist_id = (?) ORDER BY Playlist_Entries.playlist_position;")?;
+            let entries = stmt.query_map([playlist_id], |row| row.get::<usize, i64>(0))?;
+
+            let mut playlist_tracks = Vec::new();
+            for entry in entries {
+                if let Some(index) = index_of(entry?) {
+                    playlist_tracks.push(index);
+                }
+            }
+
+            playlists.push(PlaylistBackup {
+                title,
+                description,
+                creation_time: creation_time.timestamp(),
+                modify_time: modify_time.timestamp(),
+                tracks: playlist_tracks,
+            });
+        }
+
+        let mut plays = Vec::new();
+        let mut stmt = tx.prepare("SELECT playtime, track_id FROM Plays ORDER BY playtime;")?;
+        let rows = stmt.query_map([], |row| {
+            let playtime: i64 = row.get(0)?;
+            let track_id: i64 = row.get(1)?;
+            Ok((playtime, track_id))
+        })?;
+
+        for row in rows {
+            let (playtime, track_id) = row?;
+            if let Some(index) = index_of(track_id) {
+                plays.push(PlayBackup { playtime, track: index });
+            }
+        }
+
+        Ok(LibraryBackup {
+            version: backup::BACKUP_FORMAT_VERSION,
+            created: chrono::offset::Utc::now().timestamp(),
+            tracks,
+            playlists,
+            plays,
+        })
+    }
+
+    fn query_track_refs(&self, tx: &Transaction) -> Result<HashMap<i64, TrackRef>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT Tracks.id, File_URIs.uri, Tracks.title, Tracks.album_name, Tracks.album_artist, Tracks.disc_number, Tracks.track_number, Tracks.duration 
+            FROM Tracks INNER JOIN File_URIs ON Tracks.file_uri_id = File_URIs.id;")?;
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            Ok((id, TrackRef {
+                path: row.get(1)?,
+                title: row.get(2)?,
+                album: row.get(3)?,
+                album_artist: row.get(4)?,
+                disc_number: row.get(5)?,
+                track_number: row.get(6)?,
+                duration: row.get(7)?,
+            }))
+        })?;
+
+        let mut result = HashMap::new();
+        for row in rows {
+            let (id, track_ref) = row?;
+            result.insert(id, track_ref);
+        }
+
+        Ok(result)
+    }
+
+    //Re-links the backup to the current library by path, then by tags, returns (playlists, plays, unmatched tracks) restored
+    pub fn restore_library(&self, path: PathBuf) -> Result<(usize, usize, usize), Box<dyn Error>> {
+        let backup = LibraryBackup::read(&path)?;
+
+        let (n_playlists, n_plays, n_unmatched) = {
+            let mut conn = self.imp().conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+
+            let library = self.query_track_refs(&tx)?;
+            let mut by_path: HashMap<&str, i64> = HashMap::new();
+            let mut by_fingerprint: HashMap<String, Vec<(i64, f64)>> = HashMap::new();
+            for (id, track_ref) in library.iter() {
+                by_path.insert(track_ref.path.as_str(), *id);
+                by_fingerprint.entry(track_ref.fingerprint()).or_default().push((*id, track_ref.duration));
+            }
+
+            let resolved: Vec<Option<i64>> = backup.tracks.iter().map(|track_ref| {
+                if let Some(id) = by_path.get(track_ref.path.as_str()) {
+                    return Some(*id);
+                }
+                by_fingerprint.get(&track_ref.fingerprint()).and_then(|candidates| {
+                    candidates.iter()
+                        .find(|(_, duration)| (duration - track_ref.duration).abs() < 2.0)
+                        .map(|(id, _)| *id)
+                })
+            }).collect();
+
+            let n_unmatched = resolved.iter().filter(|id| id.is_none()).count();
+
+            let mut n_playlists = 0;
+            for playlist in backup.playlists.iter() {
+                if self.check_if_backup_playlist_exists(&tx, &playlist.title, playlist.creation_time)? {
+                    debug!("playlist {} already exists, skipping", playlist.title);
+                    continue;
+                }
+
+                let track_ids: Vec<i64> = playlist.tracks.iter()
+                    .filter_map(|index| resolved.get(*index).cloned().flatten())
+                    .collect();
+
+                if track_ids.is_empty() {
+                    continue;
+                }
+
+                let playlist_id = self.add_playlist(&tx, playlist.title.clone(), playlist.description.clone())?;
+                for (position, track_id) in track_ids.iter().enumerate() {
+                    self.add_track_to_playlist(&tx, playlist_id, position as i64, *track_id)?;
+                }
+
+                let creation_time = chrono::NaiveDateTime::from_timestamp_opt(playlist.creation_time, 0).ok_or("Invalid playlist creation time")?;
+                let modify_time = chrono::NaiveDateTime::from_timestamp_opt(playlist.modify_time, 0).ok_or("Invalid playlist modify time")?;
+                let creation_time = DateTime::<Utc>::from_utc(creation_time, Utc);
+                let modify_time = DateTime::<Utc>::from_utc(modify_time, Utc);
+                let mut stmt = tx.prepare("UPDATE Playlists SET creation_time = (?), modify_time = (?) WHERE id = (?);")?;
+                stmt.execute(params![creation_time, modify_time, playlist_id])?;
+
+                n_playlists += 1;
+            }
+
+            let mut n_plays = 0;
+            for play in backup.plays.iter() {
+                let track_id = match resolved.get(play.track).cloned().flatten() {
+                    Some(id) => id,
+                    None => continue,
+                };
+
+                let mut stmt = tx.prepare("SELECT EXISTS(SELECT 1 FROM Plays WHERE track_id = (?) AND playtime = (?) LIMIT 1);")?;
+                let exists: i32 = stmt.query_row(params![track_id, play.playtime], |row| row.get(0))?;
+                if exists != 0 {
+                    continue;
+                }
+
+                //the first credited album artist, the same one a new play of the track is stored with
+                let mut stmt = tx.prepare("INSERT INTO Plays (playtime, track_id, album_id, album_artist_id)
+                    SELECT (?), Track_Album_Junction.track_id, Track_Album_Junction.album_id, Album_Artist_Junction.artist_id
+                    FROM Track_Album_Junction INNER JOIN Album_Artist_Junction ON Track_Album_Junction.album_id = Album_Artist_Junction.album_id
+                    WHERE Track_Album_Junction.track_id = (?) ORDER BY Album_Artist_Junction.position, Album_Artist_Junction.id LIMIT 1;")?;
+                n_plays += stmt.execute(params![play.playtime, track_id])?;
+            }
+
+            tx.commit()?;
+            (n_playlists, n_plays, n_unmatched)
+        };
+
+        send!(self.imp().model_sender, ModelAction::PopulatePlaylists);
+        Ok((n_playlists, n_plays, n_unmatched))
+    }
+
+    fn check_if_backup_playlist_exists(&self, tx: &Transaction, title: &str, creation_time: i64) -> Result<bool, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT creation_time FROM Playlists WHERE title = (?);")?;
+        let rows = stmt.query_map([title], |row| row.get::<usize, DateTime<Utc>>(0))?;
+        for row in rows {
+            if row?.timestamp() == creation_time {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+
+    //PLAYLIST FILES
+
+    fn query_library_tracks(&self, tx: &Transaction) -> Result<Vec<LibraryTrack>, Box<dyn Error>> {
+        let mut stmt = tx.prepare("SELECT Tracks.id, File_URIs.uri, Tracks.title, Tracks.artist, Tracks.album_artist, Tracks.album_name, Tracks.duration 
+            FROM Tracks INNER JOIN File_URIs ON Tracks.file_uri_id = File_URIs.id;")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(LibraryTrack {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                title: row.get(2)?,
+                artist: row.get(3)?,
+                album_artist: row.get(4)?,
+                album: row.get(5)?,
+                duration: row.get(6)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+
+        Ok(result)
+    }
+
+    //Removes every entry of a playlist, the playlist itself is kept
+    fn clear_playlist_entries(&self, tx: &Transaction, playlist_id: i64) -> Result<(), Box<dyn Error>> {
+        let entries = self.query_ordered_vec_of_playlist_entries(tx, playlist_id)?;
+        for entry_id in entries {
+            let mut stmt = tx.prepare("DELETE FROM Playlist_Entry_Playlist_Junction WHERE playlist_entry_id = (?);")?;
+            stmt.execute(params![entry_id])?;
+
+            let mut stmt = tx.prepare("DELETE FROM Playlist_Entry_Track_Junction WHERE playlist_entry_id = (?);")?;
+            stmt.execute(params![entry_id])?;
+
+            let mut stmt = tx.prepare("DELETE FROM Playlist_Entries WHERE id = (?);")?;
+            stmt.execute(params![entry_id])?;
+        }
+        Ok(())
+    }
+
+    fn playlist_file_description(&self, playlist_file: &PlaylistFile) -> String {
+        let file_name = playlist_file.path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        // Translators: do not replace {file}
+        i18n_k("Imported from {file}", &[("file", &file_name)])
+    }
+
+    //Creates a playlist from a playlist file, returns its title, the number of tracks found & the entries not found
+    pub fn import_playlist_file(&self, path: PathBuf) -> Result<(String, usize, Vec<String>), Box<dyn Error>> {
+        let playlist_file = PlaylistFile::read(&path)?;
+
+        let (n_tracks, unmatched) = {
+            let mut conn = self.imp().conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+
+            let matcher = TrackMatcher::new(self.query_library_tracks(&tx)?);
+            let (track_ids, unmatched) = matcher.match_entries(&playlist_file.entries);
+
+            if !track_ids.is_empty() {
+                let playlist_id = self.add_playlist(&tx, playlist_file.title.clone(), self.playlist_file_description(&playlist_file))?;
+                for (position, track_id) in track_ids.iter().enumerate() {
+                    self.add_track_to_playlist(&tx, playlist_id, position as i64, *track_id)?;
+                }
+            }
+
+            tx.commit()?;
+            (track_ids.len(), unmatched)
+        };
+
+        if n_tracks > 0 {
+            send!(self.imp().model_sender, ModelAction::PopulatePlaylists);
+        }
+        Ok((playlist_file.title, n_tracks, unmatched))
+    }
+
+    //Imports the playlist files found in a music folder, returns the number of playlists added or updated
+    //files imported before are skipped unless they changed, a changed file replaces the tracks of its playlist
+    fn import_folder_playlists(&self, folder: &str, playlists: Vec<PlaylistFile>) -> Result<usize, Box<dyn Error>> {
+        let n_imported = {
+            let mut conn = self.imp().conn.borrow_mut();
+            let conn = conn.as_mut().ok_or("Connection not established")?;
+            let tx = conn.transaction()?;
+
+            let matcher = TrackMatcher::new(self.query_library_tracks(&tx)?);
+            let mut n_imported = 0;
+
+            for playlist_file in playlists.iter() {
+                let uri = playlist_file.path.to_string_lossy().to_string();
+
+                let mut stmt = tx.prepare("SELECT last_modified, playlist_id FROM Playlist_Files WHERE uri = (?);")?;
+                let known: Option<(i64, Option<i64>)> = stmt.query_row([&uri], |row| Ok((row.get(0)?, row.get(1)?))).optional()?;
+                if matches!(known, Some((last_modified, _)) if last_modified == playlist_file.last_modified) {
+                    continue;
+                }
+
+                let (track_ids, unmatched) = matcher.match_entries(&playlist_file.entries);
+
+                self.clear_import_problems(&tx, &uri)?;
+                if !unmatched.is_empty() {
+                    // Translators: do not replace {entries}
+                    let reason = i18n_k("Playlist entries not found in the library: {entries}", &[("entries", &unmatched.join(", "))]);
+                    self.add_import_problem(&tx, folder, &uri, &reason)?;
+                }
+
+                let mut playlist_id = match known.and_then(|(_, playlist_id)| playlist_id) {
+                    Some(playlist_id) if self.check_if_playlist_exists(&tx, playlist_id)? => Some(playlist_id),
+                    _ => None,
+                };
+
+                if !track_ids.is_empty() {
+                    let id = match playlist_id {
+                        Some(id) => {
+                            self.clear_playlist_entries(&tx, id)?;
+                            self.update_playlist_modify_time(&tx, id)?;
+                            id
+                        },
+                        None => self.add_playlist(&tx, playlist_file.title.clone(), self.playlist_file_description(playlist_file))?,
+                    };
+
+                    for (position, track_id) in track_ids.iter().enumerate() {
+                        self.add_track_to_playlist(&tx, id, position as i64, *track_id)?;
+                    }
+
+                    playlist_id = Some(id);
+                    n_imported += 1;
+                }
+
+                let mut stmt = tx.prepare("INSERT OR REPLACE INTO Playlist_Files (uri, last_modified, playlist_id) VALUES ( ?, ?, ? );")?;
+                stmt.execute(params![uri, playlist_file.last_modified, playlist_id])?;
+            }
+
+            tx.commit()?;
+            n_imported
+        };
+
+        self.emit_by_name::<()>("import-report-changed", &[]);
+        if n_imported > 0 {
+            send!(self.imp().model_sender, ModelAction::PopulatePlaylists);
+        }
+        Ok(n_imported)
+    }
+
+    fn show_playlist_import_report(&self, title: String, n_tracks: usize, unmatched: Vec<String>) {
+        if unmatched.is_empty() {
+            // Translators: do not replace {playlist_title} or {number_of_tracks}
+            add_success_toast(&i18n("Imported:"), &i18n_k("«{playlist_title}» with {number_of_tracks} tracks.", &[("playlist_title", &title), ("number_of_tracks", &format!("{}", n_tracks))]));
+            return;
+        }
+
+        match util::window() {
+            Some(window) => {
+                let dialog = PlaylistImportReportDialog::new(title, n_tracks, unmatched);
+                dialog.set_transient_for(Some(&window));
+                dialog.show();
+            },
+            None => {
+                // Translators: do not replace {playlist_title} or {number_of_unmatched}
+                add_success_toast(&i18n("Imported:"), &i18n_k("«{playlist_title}», {number_of_unmatched} tracks not found.", &[("playlist_title", &title), ("number_of_unmatched", &format!("{}", unmatched.len()))]));
+            },
+        }
+    }
+
 // TABLE SETUP 
 
     fn setup_db_tables(&self) -> Result<(), Box<dyn Error>> {
-        let conn = self.imp().conn.borrow();
-        let connection = conn.as_ref().ok_or("Connection not established")?;
-
-        connection
-            .execute("PRAGMA foreign_keys = ON", params![])
-            .unwrap();
-
-        // make directories table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Music_Folders
-        (
-            id  INTEGER PRIMARY KEY,
-            uri TEXT NOT NULL
-        );", params![],).unwrap();
-
-        // make files table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        File_URIs
-        (
-            id  INTEGER PRIMARY KEY,
-            uri TEXT NOT NULL,
-            last_modified TIMESTAMP
-        );", params![],).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Folder_File_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            folder_id INTEGER NOT NULL,
-            file_id INTEGER NOT NULL,
-            FOREIGN KEY (folder_id) REFERENCES Music_Folders(id),
-            FOREIGN KEY (file_id) REFERENCES File_URIs(id)
-        );", params![],).unwrap();
-
-        // make genre table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Genres
-        (
-            id  INTEGER PRIMARY KEY,
-            name TEXT NOT NULL
-        );", params![],).unwrap();
-
-        // make artist table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Artists
-        (
-            id  INTEGER PRIMARY KEY,
-            name TEXT NOT NULL,
-            image_fetched INTEGER NOT NULL
-        );",params![],).unwrap();
-
-        // make album table
-        // foreign key -> albumartist_parent
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Albums
-        (
-            id  INTEGER PRIMARY KEY,
-            title TEXT NOT NULL,
-            artist TEXT NOT NULL,
-            date TEXT NOT NULL,
-            genre TEXT NOT NULL
-        );", params![],).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Cover_Art
-        (
-            id  INTEGER PRIMARY KEY,
-            data BLOB NOT NULL,
-            thing INTEGER
-        );",params![],).unwrap();
-
-        // make tracks table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Tracks
-        (
-            id  INTEGER PRIMARY KEY,
-            title TEXT NOT NULL,
-            filetype TEXT NOT NULL,
-            album_name TEXT NOT NULL,
-            date TEXT NOT NULL,
-            duration REAL NOT NULL,
-            track_number INTEGER NOT NULL,
-            disc_number INTEGER NOT NULL,
-            album_artist TEXT NOT NULL,
-            file_uri_id INTEGER NOT NULL,
-            FOREIGN KEY (file_uri_id) REFERENCES File_URIs(id)
-        );", params![]).unwrap();
-
-        // make plays table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Plays
-        (
-            id  INTEGER PRIMARY KEY,
-            playtime TIMESTAMP,
-            track_id INTEGER NOT NULL,
-            album_id INTEGER NOT NULL,
-            album_artist_id INTEGER NOT NULL,
-            FOREIGN KEY (track_id) REFERENCES tracks(id),
-            FOREIGN KEY (album_id) REFERENCES albums(id),
-            FOREIGN KEY (album_artist_id) REFERENCES artists(id)
-        );",  params![],).unwrap();
-
-        // make playlists table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Playlists
-        (
-            id  INTEGER PRIMARY KEY,
-            title TEXT NOT NULL,
-            description TEXT NOT NULL,
-            creation_time TIMESTAMP,
-            modify_time TIMESTAMP
-        );", params![],).unwrap();
-
-        // make playlist entries table
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Playlist_Entries
-        (
-            id  INTEGER PRIMARY KEY,
-            playlist_position INTEGER NOT NULL
-        );", params![],).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Playlist_Entry_Track_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            playlist_entry_id INTEGER NOT NULL,
-            track_id INTEGER NOT NULL,
-            FOREIGN KEY (playlist_entry_id) REFERENCES Playlist_Entries(id),
-            FOREIGN KEY (track_id) REFERENCES Tracks(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Playlist_Entry_Playlist_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            playlist_entry_id INTEGER NOT NULL,
-            playlist_id INTEGER NOT NULL,
-            FOREIGN KEY (playlist_entry_id) REFERENCES Playlist_Entries(id),
-            FOREIGN KEY (playlist_id) REFERENCES Playlists(id)
-        );", params![]).unwrap();
-
-
-        // let mut stmt = connection.prepare("SELECT name FROM sqlite_master WHERE type='table'")?;
-        // let mut rows = stmt.query(rusqlite::params![])?;
-
-        // while let Some(row) = rows.next()? {
-        //     let table_name: String = row.get(0)?;
-        //     debug!("Table name: {}", table_name);
-        // }
+        let mut conn = self.imp().conn.borrow_mut();
+        let connection = conn.as_mut().ok_or("Connection not established")?;
+
+        connection.execute("PRAGMA foreign_keys = ON", params![])?;
 
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Track_Folder_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            track_id INTEGER NOT NULL,
-            folder_id INTEGER NOT NULL,
-            FOREIGN KEY (track_id) REFERENCES Tracks(id),
-            FOREIGN KEY (folder_id) REFERENCES Music_Folders(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Track_Album_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            track_id INTEGER NOT NULL,
-            album_id INTEGER NOT NULL,
-            FOREIGN KEY (track_id) REFERENCES Tracks(id),
-            FOREIGN KEY (album_id) REFERENCES Albums(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Track_Artist_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            track_id INTEGER NOT NULL,
-            artist_id INTEGER NOT NULL,
-            FOREIGN KEY (track_id) REFERENCES Tracks(id),
-            FOREIGN KEY (artist_id) REFERENCES Artists(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Track_Cover_Art_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            track_id INTEGER NOT NULL,
-            cover_art_id INTEGER NOT NULL,
-            FOREIGN KEY (track_id) REFERENCES Tracks(id),
-            FOREIGN KEY (cover_art_id) REFERENCES Cover_Art(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Album_Cover_Art_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            album_id INTEGER NOT NULL,
-            cover_art_id INTEGER NOT NULL,
-            FOREIGN KEY (album_id) REFERENCES Albums(id),
-            FOREIGN KEY (cover_art_id) REFERENCES Cover_Art(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Album_Artist_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            album_id INTEGER NOT NULL,
-            artist_id INTEGER NOT NULL,
-            FOREIGN KEY (album_id) REFERENCES Albums(id),
-            FOREIGN KEY (artist_id) REFERENCES Artists(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Album_Genre_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            album_id INTEGER NOT NULL,
-            genre_id INTEGER NOT NULL,
-            FOREIGN KEY (album_id) REFERENCES Albums(id),
-            FOREIGN KEY (genre_id) REFERENCES Genres(id)
-        );", params![]).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Discog_Artist_Image
-        (
-            id  INTEGER PRIMARY KEY,
-            url TEXT NOT NULL,
-            data BLOB NOT NULL,
-            thing INTEGER
-        );",params![],).unwrap();
-
-        connection.execute("CREATE TABLE IF NOT EXISTS
-        Artist_Discog_Artist_Image_Junction
-        (
-            id  INTEGER PRIMARY KEY,
-            artist_id INTEGER NOT NULL,
-            image_id INTEGER NOT NULL,
-            FOREIGN KEY (artist_id) REFERENCES Artists(id),
-            FOREIGN KEY (image_id) REFERENCES Discog_Artist_Image(id)
-        );", params![]).unwrap();
+        migrations::migrate(connection)?;
 
         Ok(())
     }
@@ -2236,3 +4002,16 @@ fn fetch_artist_images_bulk(artists: Vec<(i64, String)>, sender: Sender<Database
 
     Ok(())
 }
+
+//Size of a file on disk, 0 when unreadable so it never rules out a match
+fn file_size(uri: &str) -> i64 {
+    fs::metadata(uri).map(|metadata| metadata.len() as i64).unwrap_or(0)
+}
+
+//Moves the plays of a track to its album & first credited album artist, the same ones a new play is stored with
+pub fn repoint_plays(conn: &Connection, track_id: i64, album_id: i64) -> Result<usize> {
+    let mut stmt = conn.prepare("UPDATE Plays SET album_id = ?1,
+        album_artist_id = COALESCE((SELECT artist_id FROM Album_Artist_Junction WHERE album_id = ?1 ORDER BY position, id LIMIT 1), album_artist_id)
+        WHERE track_id = ?2;")?;
+    stmt.execute(params![album_id, track_id])
+}
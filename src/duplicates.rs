@@ -0,0 +1,250 @@
+/* duplicates.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::{collections::{HashMap, HashSet}, rc::Rc};
+
+use crate::model::track::Track;
+
+//Copies whose durations differ by at most this are the same recording
+const DURATION_TOLERANCE: f64 = 2.0;
+
+const LOSSLESS_FORMATS: [&str; 6] = ["flac", "wav", "aif", "aiff", "ape", "wv"];
+
+//Normalized title, artist & album key, disc & track number
+type DuplicateKey = (String, String, String, i64, i64);
+
+//Which copies of a duplicated track are shown, set for the library & optionally per album
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FormatPolicy {
+    #[default]
+    ShowAll,
+    PreferLossless,
+    PreferLossy,
+}
+
+impl FormatPolicy {
+    pub fn new_from_settings(value: i32) -> Self {
+        match value {
+            1 => Self::PreferLossless,
+            2 => Self::PreferLossy,
+            _ => Self::ShowAll,
+        }
+    }
+
+    pub fn value(&self) -> i32 {
+        match self {
+            Self::ShowAll => 0,
+            Self::PreferLossless => 1,
+            Self::PreferLossy => 2,
+        }
+    }
+}
+
+//Copies of one track, found in the same or in different albums
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub album_key: String,
+    pub tracks: Vec<Rc<Track>>,
+}
+
+impl DuplicateGroup {
+    //Copy kept by the policy, None when every copy is shown
+    pub fn preferred(&self, policy: FormatPolicy) -> Option<i64> {
+        let rank = |track: &Rc<Track>| {
+            let lossless = is_lossless(&track.filetype());
+            match policy {
+                FormatPolicy::PreferLossless => !lossless as u8,
+                FormatPolicy::PreferLossy => lossless as u8,
+                FormatPolicy::ShowAll => 0,
+            }
+        };
+
+        match policy {
+            FormatPolicy::ShowAll => None,
+            _ => self.tracks.iter()
+                .min_by_key(|track| (rank(track), track.id()))
+                .map(|track| track.id()),
+        }
+    }
+}
+
+pub fn is_lossless(filetype: &str) -> bool {
+    let filetype = filetype.trim_start_matches('.').to_lowercase();
+    LOSSLESS_FORMATS.contains(&filetype.as_str())
+}
+
+//Lowercase letters & digits only, so "Song (Remastered)" & "song remastered" match
+pub fn normalize(value: &str) -> String {
+    value.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+//Per album policies are keyed on names rather than ids, so they survive rescans
+pub fn album_key(album: &str, album_artist: &str) -> String {
+    format!("{}\u{1f}{}", normalize(album), normalize(album_artist))
+}
+
+//Groups the tracks with the same normalized title, artist, album & position whose durations are within the tolerance,
+//so tracks sharing a title on one album, like two "Interlude", stay apart
+pub fn find_duplicates(tracks: &HashMap<i64, Rc<Track>>) -> Vec<DuplicateGroup> {
+    let mut buckets: HashMap<DuplicateKey, Vec<Rc<Track>>> = HashMap::new();
+    for track in tracks.values() {
+        let key = (
+            normalize(&track.title()),
+            normalize(&track.artist()),
+            album_key(&track.album(), &track.album_artist()),
+            track.disc_number(),
+            track.track_number(),
+        );
+        buckets.entry(key).or_default().push(track.clone());
+    }
+
+    let mut groups = Vec::new();
+    for ((_, _, album_key, _, _), mut bucket) in buckets {
+        if bucket.len() < 2 {
+            continue;
+        }
+
+        bucket.sort_by(|a, b| a.duration().total_cmp(&b.duration()));
+
+        let mut cluster: Vec<Rc<Track>> = Vec::new();
+        for track in bucket {
+            if let Some(last) = cluster.last() {
+                if track.duration() - last.duration() > DURATION_TOLERANCE {
+                    if cluster.len() > 1 {
+                        groups.push(DuplicateGroup { album_key: album_key.clone(), tracks: cluster.clone() });
+                    }
+                    cluster.clear();
+                }
+            }
+            cluster.push(track);
+        }
+
+        if cluster.len() > 1 {
+            groups.push(DuplicateGroup { album_key, tracks: cluster });
+        }
+    }
+
+    groups
+}
+
+//Tracks hidden from grids, queues & shuffle, per album policies take precedence over the library policy
+pub fn hidden_tracks(groups: &[DuplicateGroup], policy: FormatPolicy, album_policies: &HashMap<String, FormatPolicy>) -> HashSet<i64> {
+    let mut hidden = HashSet::new();
+    for group in groups {
+        let policy = album_policies.get(&group.album_key).copied().unwrap_or(policy);
+        if let Some(preferred) = group.preferred(policy) {
+            hidden.extend(group.tracks.iter().map(|track| track.id()).filter(|id| *id != preferred));
+        }
+    }
+    hidden
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(id: i64, title: &str, album: &str, filetype: &str, duration: f64, track_number: i64) -> Rc<Track> {
+        crate::util::set_sort_articles(&[]);
+        Rc::new(Track::new(
+            id, title.to_string(), album.to_string(), "Artist".to_string(), filetype.to_string(),
+            format!("/music/{}.{}", id, filetype), String::new(), String::new(), duration, track_number, 1,
+        ))
+    }
+
+    fn library(tracks: Vec<Rc<Track>>) -> HashMap<i64, Rc<Track>> {
+        tracks.into_iter().map(|track| (track.id(), track)).collect()
+    }
+
+    //Track ids of every group, sorted so the hashmap order does not matter
+    fn group_ids(groups: &[DuplicateGroup]) -> Vec<Vec<i64>> {
+        let mut ids: Vec<Vec<i64>> = groups.iter()
+            .map(|group| {
+                let mut ids: Vec<i64> = group.tracks.iter().map(|track| track.id()).collect();
+                ids.sort();
+                ids
+            })
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Song (Remastered)"), normalize("song  remastered"));
+        assert_eq!(album_key("The Album!", "Artist"), album_key("the album", "ARTIST"));
+        assert!(is_lossless(".FLAC"));
+        assert!(!is_lossless("mp3"));
+    }
+
+    #[test]
+    fn test_find_duplicates_tolerance() {
+        //a difference of exactly the tolerance is still the same recording
+        let tracks = library(vec![
+            track(1, "Song", "Album", "flac", 180.0, 1),
+            track(2, "Song", "Album", "mp3", 182.0, 1),
+            track(3, "Song", "Album", "ogg", 184.5, 1),
+            track(4, "Song", "Album", "opus", 186.5, 1),
+            track(5, "Song", "Album", "wav", 300.0, 1),
+        ]);
+        assert_eq!(group_ids(&find_duplicates(&tracks)), vec![vec![1, 2], vec![3, 4]]);
+    }
+
+    #[test]
+    fn test_find_duplicates_key() {
+        let tracks = library(vec![
+            track(1, "Song (Remastered)", "Album", "flac", 180.0, 1),
+            track(2, "song remastered", "album", "mp3", 181.0, 1),
+            //same title on another position of the album
+            track(3, "Interlude", "Album", "flac", 60.0, 3),
+            track(4, "Interlude", "Album", "flac", 60.0, 7),
+            //same title & position on another album
+            track(5, "Song (Remastered)", "Other", "flac", 180.0, 1),
+        ]);
+
+        let groups = find_duplicates(&tracks);
+        assert_eq!(group_ids(&groups), vec![vec![1, 2]]);
+        assert_eq!(groups[0].album_key, album_key("Album", "Artist"));
+    }
+
+    #[test]
+    fn test_hidden_tracks() {
+        let tracks = library(vec![
+            track(1, "Song", "Album", "flac", 180.0, 1),
+            track(2, "Song", "Album", "mp3", 180.0, 1),
+            track(3, "Song", "Other", "mp3", 180.0, 1),
+            track(4, "Song", "Other", "flac", 180.0, 1),
+            track(5, "Tune", "Other", "flac", 90.0, 2),
+            track(6, "Tune", "Other", "flac", 90.0, 2),
+        ]);
+        let groups = find_duplicates(&tracks);
+        let hidden = |policy: FormatPolicy, album_policies: &HashMap<String, FormatPolicy>| {
+            let mut hidden: Vec<i64> = hidden_tracks(&groups, policy, album_policies).into_iter().collect();
+            hidden.sort();
+            hidden
+        };
+
+        let no_overrides = HashMap::new();
+        assert!(hidden(FormatPolicy::ShowAll, &no_overrides).is_empty());
+        //copies of the same format keep the lowest id
+        assert_eq!(hidden(FormatPolicy::PreferLossless, &no_overrides), vec![2, 3, 6]);
+        assert_eq!(hidden(FormatPolicy::PreferLossy, &no_overrides), vec![1, 4, 6]);
+
+        let overrides = HashMap::from([(album_key("Other", "Artist"), FormatPolicy::PreferLossy)]);
+        assert_eq!(hidden(FormatPolicy::PreferLossless, &overrides), vec![2, 4, 6]);
+
+        let overrides = HashMap::from([(album_key("Other", "Artist"), FormatPolicy::ShowAll)]);
+        assert_eq!(hidden(FormatPolicy::PreferLossless, &overrides), vec![2]);
+
+        let overrides = HashMap::from([(album_key("Album", "Artist"), FormatPolicy::PreferLossless)]);
+        assert_eq!(hidden(FormatPolicy::ShowAll, &overrides), vec![2]);
+    }
+}
@@ -0,0 +1,186 @@
+/* import_progress.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::glib::Sender;
+use gtk_macros::send;
+
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+
+use super::database::DatabaseAction;
+
+//Sent by the extracting thread of a folder import
+#[derive(Clone, Debug)]
+pub enum ImportProgress {
+    Discovered((usize, usize)),
+    Processed((usize, String)),
+    FileFailed((String, String)),
+    Failed(String),
+    Cancelled,
+    Finished,
+}
+
+//Reports the progress of one folder to the database, processed files are throttled so the main loop is not flooded
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    folder: String,
+    sender: Option<Sender<DatabaseAction>>,
+    show_progress: bool,
+    cancel: Arc<AtomicBool>,
+    processed: usize,
+    last_update_time: Option<Instant>,
+}
+
+impl ProgressReporter {
+    pub fn new(folder: String, sender: Sender<DatabaseAction>, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            folder,
+            sender: Some(sender),
+            show_progress: true,
+            cancel,
+            processed: 0,
+            last_update_time: None,
+        }
+    }
+
+    //Reports only the unreadable files to the import report, rescans are not shown as imports
+    pub fn rescan(folder: String, sender: Sender<DatabaseAction>) -> Self {
+        Self {
+            folder,
+            sender: Some(sender),
+            show_progress: false,
+            cancel: Arc::new(AtomicBool::new(false)),
+            processed: 0,
+            last_update_time: None,
+        }
+    }
+
+    //Files of the folder, already_imported of them were imported before the app was closed
+    pub fn discovered(&mut self, total: usize, already_imported: usize) {
+        self.processed = already_imported;
+        self.send(ImportProgress::Discovered((total, already_imported)));
+    }
+
+    pub fn processed(&mut self, file: &str) {
+        self.processed += 1;
+        if !matches!(self.last_update_time, Some(start) if start.elapsed() <= Duration::from_millis(250)) {
+            self.last_update_time = Some(Instant::now());
+            self.send(ImportProgress::Processed((self.processed, file.to_string())));
+        }
+    }
+
+    pub fn file_failed(&self, file: &str, reason: &str) {
+        self.send(ImportProgress::FileFailed((file.to_string(), reason.to_string())));
+    }
+
+    pub fn failed(&self, reason: &str) {
+        self.send(ImportProgress::Failed(reason.to_string()));
+    }
+
+    pub fn cancelled(&self) {
+        self.send(ImportProgress::Cancelled);
+    }
+
+    pub fn finished(&self) {
+        self.send(ImportProgress::Finished);
+    }
+
+    //Set from the main thread, extractors stop at the next file
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, progress: ImportProgress) {
+        if !self.show_progress && !matches!(progress, ImportProgress::FileFailed(_)) {
+            return;
+        }
+        if let Some(sender) = self.sender.as_ref() {
+            send!(sender, DatabaseAction::ImportProgress((self.folder.clone(), progress)));
+        }
+    }
+}
+
+//A file of the import report, skipped or imported with missing tags
+#[derive(Debug, Clone)]
+pub struct ImportProblem {
+    pub folder: String,
+    pub uri: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportState {
+    Queued,
+    Extracting,
+    Cancelling,
+    Done,
+    Failed,
+}
+
+//Progress of a folder import as shown in the ui
+#[derive(Debug, Clone)]
+pub struct ImportStatus {
+    pub folder: String,
+    pub state: ImportState,
+    pub total: usize,
+    pub processed: usize,
+    pub already_imported: usize,
+    pub current_file: String,
+    pub errors: usize,
+    pub started: Option<Instant>,
+}
+
+impl ImportStatus {
+    pub fn new(folder: String) -> Self {
+        Self {
+            folder,
+            state: ImportState::Queued,
+            total: 0,
+            processed: 0,
+            already_imported: 0,
+            current_file: String::new(),
+            errors: 0,
+            started: None,
+        }
+    }
+
+    pub fn apply(&mut self, progress: ImportProgress) {
+        match progress {
+            ImportProgress::Discovered((total, already_imported)) => {
+                self.total = total;
+                self.processed = already_imported;
+                self.already_imported = already_imported;
+                self.started = Some(Instant::now());
+            },
+            ImportProgress::Processed((processed, file)) => {
+                self.processed = processed.min(self.total);
+                self.current_file = file;
+            },
+            ImportProgress::FileFailed(_) => self.errors += 1,
+            ImportProgress::Failed(_) => self.state = ImportState::Failed,
+            ImportProgress::Cancelled | ImportProgress::Finished => self.state = ImportState::Done,
+        }
+    }
+
+    pub fn fraction(&self) -> f64 {
+        match self.state {
+            ImportState::Done => 1.0,
+            _ if self.total == 0 => 0.0,
+            _ => self.processed as f64 / self.total as f64,
+        }
+    }
+
+    //Remaining time at the rate files were processed since the import started or resumed
+    pub fn eta(&self) -> Option<Duration> {
+        let processed = self.processed.saturating_sub(self.already_imported);
+        if self.state != ImportState::Extracting || processed == 0 {
+            return None;
+        }
+        let elapsed = self.started?.elapsed();
+        let remaining = (self.total - self.processed) as u32;
+        Some(elapsed / processed as u32 * remaining)
+    }
+}
@@ -7,27 +7,51 @@
 use gtk::{gio, glib::Sender};
 use gtk_macros::send;
 
-use std::cell::RefCell;
-use std::{collections::HashMap, error::Error, fmt, fs};
+use std::{collections::HashMap, error::Error, fs, path::Path};
+use std::sync::{Arc, atomic::AtomicBool};
 use log::{debug, error};
 use rusqlite::Transaction;
-use pyo3::prelude::*;
+use pyo3::FromPyObject;
 use chrono::{DateTime, Utc};
 use regex::Regex;
+use once_cell::sync::Lazy;
 
 use super::database::DatabaseAction;
+use super::duplicates::normalize;
+use super::import_progress::ProgressReporter;
+use super::playlist_file::{find_playlist_files, PlaylistFile};
+use super::tag_extractor::TagExtractorKind;
 use super::util;
-use std::time::{Duration, Instant};
+use super::i18n::i18n;
 
-#[derive(Debug)]
-struct ImportError(String);
-impl fmt::Display for ImportError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Import error: {}", self.0)
-        
-    }
-}
-impl Error for ImportError {}
+//Files extracted & committed at a time, a cancelled or interrupted import loses at most one batch
+const IMPORT_BATCH_SIZE: usize = 250;
+
+//Moved files whose durations differ by less are the same file
+const MOVED_DURATION_TOLERANCE: f32 = 1.0;
+
+const SONG_EXTENSIONS: [&str; 10] = ["mp3", "aif", "ogg", "opus", "flac", "mp4", "m4a", "asf", "wma", "wmv"];
+
+//Tags kept beyond the core fields, stored as key/value pairs in Track_Tags
+pub const EXTENDED_TAGS: [&str; 23] = [
+    "composer", "conductor", "remixer", "lyricist", "label", "bpm", "key", "language",
+    "originaldate", "discsubtitle", "titlesort", "artistsort", "albumsort", "albumartistsort", "composersort",
+    "musicbrainz_albumid", "musicbrainz_releasegroupid", "musicbrainz_artistid", "musicbrainz_albumartistid",
+    "replaygain_track_gain", "replaygain_track_peak", "replaygain_album_gain", "replaygain_album_peak",
+];
+
+//Other names of the extended tags, mostly vorbis comment conventions
+const TAG_ALIASES: [(&str, &str); 4] = [
+    ("organization", "label"),
+    ("publisher", "label"),
+    ("initialkey", "key"),
+    ("originalyear", "originaldate"),
+];
+
+//Separators of joint credits, ie "A feat. B", "A (ft. B)" or "A & B"
+static CREDIT_SEPARATORS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\s*[(\[]?\s*\b(?:feat\.?|ft\.|featuring)\s+|\s+&\s+").unwrap()
+});
 
 #[derive(Debug, FromPyObject, Clone)]
 pub enum MapVal {
@@ -38,47 +62,6 @@ pub enum MapVal {
     Bytes(Vec<u8>),
 }
 
-#[pyclass]
-struct LoggingStdout {
-    last_update_time: RefCell<Option<Instant>>,
-}
-
-
-#[pymethods]
-impl LoggingStdout {
-    #[new]
-    fn new() -> Self {
-        Self {
-            last_update_time: RefCell::new(None),
-        }
-    }
-
-    fn write(&self, data: &str) {
-        //println!("stdout from python: {:?}", data);
-        if data.contains("ERROR") || data.contains("Exception") {
-            error!("{}", data);
-        } else if data.contains("DEBUG") {
-            debug!("{}", data);
-        } else if data.contains("%"){
-            if let Some(start) = self.last_update_time.take() {
-                if start.elapsed() > Duration::from_secs(3) {
-                    if let Some(window) = util::window() {
-                        window.set_import_percentage(data);
-                    }
-                    self.last_update_time.replace(Some(Instant::now()));
-                } else {
-                    self.last_update_time.replace(Some(start));
-                }
-            } else {
-                if let Some(window) = util::window() {
-                    window.set_import_percentage(data);
-                }
-                self.last_update_time.replace(Some(Instant::now()));
-            }
-        }
-    }
-}
-
 #[derive(Debug)]
 pub struct Importer {
     pub settings: gio::Settings,
@@ -91,75 +74,113 @@ impl Importer {
         }
     }
 
-    pub fn extract_folder(&self, folder_path: String, sender: Sender<DatabaseAction>) {
+    //Extracts the folder in batches, files already in the database are skipped so an interrupted import resumes where it stopped
+    pub fn extract_folder(&self, folder_path: String, known_files: HashMap<String, i64>, cancel: Arc<AtomicBool>, sender: Sender<DatabaseAction>) {
+        let kind = self.extractor_kind();
         std::thread::spawn(move || {
-            let from_python = Python::with_gil(|py| -> Result<(), Box<dyn Error>> {
-                let sys = py.import("sys")?;
-                sys.setattr("stderr", LoggingStdout::new().into_py(py))?;
-                py.run("print('importing')", None, None)?;
-
-                let window = util::window();
-
-                if let Some(w) = window {
-                    w.set_import_message("Extracting tags from music files.");
-                
-                    let code_main = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/main.py"));
-                    let code_extracting = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/extracting.py"));
-                    let code_importer = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/importer.py"));
-                    let code_translate_dicts = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/translate_dicts.py"));
-                    let code_util = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/util.py"));
-        
-                    PyModule::from_code(py, code_util, "util", "util")?;
-                    PyModule::from_code(py, code_translate_dicts,"translate_dicts", "translate_dicts")?;
-                    PyModule::from_code(py, code_extracting, "extracting", "extracting")?;
-                    PyModule::from_code(py, code_importer, "importer", "importer")?;
-        
-                    let module = PyModule::from_code(py, code_main, "main", "")?;
-                    let args = (&folder_path,);
-                    let object: Py<PyAny> = module
-                        .getattr("tags_and_cover_art")?
-                        .call1(args)
-                        .map_err(|e| {
-                            e.print_and_set_sys_last_vars(py);
-                            e
-                        })?
-                        .into();
-        
-                    debug!("retrieving tags & cover art ... ");
-        
-                    let all: (HashMap<String, HashMap<String, HashMap<String, MapVal>>>, HashMap<String, &[u8]>) = object.extract(py)?;
-                    let (tags, bytes_map) = all;
-                    if tags.is_empty() {
-                        error!("Nothing extracted.");
-                        return Err(Box::new(ImportError("Tag Map Empty".into())));
-                    }
+            let mut progress = ProgressReporter::new(folder_path.clone(), sender.clone(), cancel);
+            let extractor = kind.extractor();
+            debug!("extracting tags of {} with {}", folder_path, extractor.name());
+
+            let mut files: Vec<String> = scan_folder(&folder_path).into_keys()
+                .filter(|uri| !known_files.contains_key(uri))
+                .collect();
+            files.sort();
+            progress.discovered(files.len() + known_files.len(), known_files.len());
+
+            let mut extracted = 0;
+            for batch in files.chunks(IMPORT_BATCH_SIZE) {
+                match extractor.extract_files(batch.to_vec(), &mut progress) {
+                    Ok((tags, vec_byte_map)) => {
+                        //a partially extracted batch is dropped
+                        if progress.is_cancelled() {
+                            break;
+                        }
+                        extracted += tags.len();
+                        if !tags.is_empty() {
+                            send!(sender, DatabaseAction::ConstructFromTags((folder_path.clone(), tags, vec_byte_map)));
+                        }
+                    },
+                    Err(e) => {
+                        error!("Unable to extract tags from {}: {}", folder_path, e);
+                        progress.failed(&e.to_string());
+                        return;
+                    },
+                }
+            }
 
-                    let mut vec_byte_map: HashMap<String, Vec<u8>> = HashMap::new();
-                    for (uri, b) in bytes_map.iter() {
-                        vec_byte_map.insert(uri.clone(), (*b).to_vec());
-                    }
+            if progress.is_cancelled() {
+                debug!("import of {} cancelled", folder_path);
+                progress.cancelled();
+            } else if extracted == 0 && known_files.is_empty() {
+                error!("Nothing extracted from {}.", folder_path);
+                progress.failed("Nothing extracted");
+            } else {
+                progress.finished();
+            }
+        });
+    }
 
-                    send!(sender, DatabaseAction::ConstructFromTags((folder_path, tags, vec_byte_map)));
-                } else {
-                    error!("Unable to retrieve window.")
-                }
-                Ok(())
-            });
-            from_python.unwrap();
+    //Compares the files on disk against the ones already in the database, only new or modified files are extracted
+    pub fn rescan_folder(&self, folder_path: String, known_files: HashMap<String, i64>, sender: Sender<DatabaseAction>) {
+        let kind = self.extractor_kind();
+        std::thread::spawn(move || {
+            let on_disk = scan_folder(&folder_path);
+
+            let removed: Vec<String> = known_files.keys()
+                .filter(|uri| !on_disk.contains_key(*uri))
+                .cloned()
+                .collect();
+
+            let changed: Vec<String> = on_disk.iter()
+                .filter(|(uri, modified)| match known_files.get(*uri) {
+                    Some(last_modified) => last_modified != *modified,
+                    None => true,
+                })
+                .map(|(uri, _)| uri.clone())
+                .collect();
+
+            debug!("rescan {}: {} changed, {} removed", folder_path, changed.len(), removed.len());
+            send_changes(kind, folder_path, changed, removed, sender);
+        });
+    }
+
+    //Reads the playlist files of a folder, the database skips the ones it already imported
+    pub fn read_folder_playlists(&self, folder_path: String, sender: Sender<DatabaseAction>) {
+        std::thread::spawn(move || {
+            let playlists: Vec<PlaylistFile> = find_playlist_files(&folder_path)
+                .iter()
+                .filter_map(|path| match PlaylistFile::read(path) {
+                    Ok(playlist) => Some(playlist),
+                    Err(e) => {
+                        error!("Unable to read playlist {:?}: {}", path, e);
+                        None
+                    },
+                })
+                .collect();
+
+            if !playlists.is_empty() {
+                send!(sender, DatabaseAction::ImportFolderPlaylists((folder_path, playlists)));
+            }
         });
     }
 
+    //Extracts a known set of changed files, used by the folder watcher
+    pub fn extract_changes(&self, folder_path: String, changed: Vec<String>, removed: Vec<String>, sender: Sender<DatabaseAction>) {
+        let kind = self.extractor_kind();
+        std::thread::spawn(move || {
+            send_changes(kind, folder_path, changed, removed, sender);
+        });
+    }
+
+    fn extractor_kind(&self) -> TagExtractorKind {
+        TagExtractorKind::new_from_settings(self.settings.int("tag-extractor"))
+    }
+
     pub fn build_database_from_tags(&self, tx: &Transaction, folder_uri: String, tags: HashMap<String, HashMap<String, HashMap<String, MapVal>>>, cover_art_map: HashMap<String, Vec<u8>>) -> Result<(), Box<dyn Error>> {
         debug!("Building database from tags");
-        let window = util::window();
         let database = util::database();
 
-        if let Some(w) = window {
-            w.set_import_message("Constructing database from tags.");
-    
-        }
-
-
         let mut added_genres: HashMap<String, i64> = HashMap::new();
         let mut added_artists: HashMap<String, i64> = HashMap::new();
         let mut added_albums: HashMap<String, i64> = HashMap::new();
@@ -168,168 +189,41 @@ impl Importer {
         let mut album_art: HashMap<i64, Vec<i64>> = HashMap::new();
 
         let mut orphan_tracks = 0;
-        let current_folder_id = database.add_folder(&tx, folder_uri)?;
+        let current_folder_id = database.add_folder(&tx, folder_uri.clone())?;
 
         let re = Regex::new(r"^[^\d]*(\d+)").unwrap();
+        let options = TagOptions::from_settings(&self.settings);
 
         for (uri, track_map) in &tags {
             debug!("getting tags from:\n\t -> {:?}", uri);
+            database.clear_import_problems(&tx, uri)?;
 
-            let mut title_tag = String::new();
-            let mut artist_tag = String::new();
-            let mut album_tag = String::new();
-            let mut albumartist_tag = String::new();
-            let mut date_tag = String::new();
-            let mut genre_tag = String::new();
             let mut cover_art = None;
-            let mut duration_tag = None;
-            let mut disc_number_tag = None;
-            let mut track_number_tag = None;
-            let mut filetype_tag = String::new();
 
             if cover_art_map.contains_key(&uri.clone()) {
                 cover_art = cover_art_map.get(&uri.clone());
             }
-            
-            for (type_key, type_map) in track_map {
-
-                match type_key.as_str() {
-                    "str_list" => {
-                        for (tag, tag_val) in type_map {
-                            if let MapVal::List(value) = tag_val {
-                                //debug!("\t\ttagging {} {:?} ", tag, value);
-
-                                match tag.as_str() {
-                                    "genre" => {
-                                        genre_tag = value[0].clone();
-                                    },
-                                    "album" => {
-                                        album_tag = value[0].clone();
-                                    },
-                                    "title" => {
-                                        title_tag = value[0].clone();
-                                    },
-                                    "albumartist" => {
-                                        albumartist_tag = value[0].clone();
-                                    },
-                                    "artist" => {
-                                        artist_tag = value[0].clone();
-                                    },
-                                    "date" => {
-                                        date_tag = value[0].clone();
-                                    },
-                                    "tracknumber" => {
-                                        let tag = re.captures(&value[0]).and_then(|cap| {
-                                            cap.get(0).map(|s| s.as_str())
-                                        });
-                                        track_number_tag = if !tag.is_none() {
-                                            match tag.unwrap().parse::<u32>() {
-                                                Ok(num) => Some(num),
-                                                Err(e) => {
-                                                    error!("{}: {}", tag.unwrap(), e);
-                                                    None
-                                                },
-                                            }
-                                        } else {
-                                            None
-                                        };
-                                    },
-                                    "discnumber" => {
-                                        let tag = re.captures(&value[0]).and_then(|cap| {
-                                            cap.get(0).map(|s| s.as_str())
-                                        });
-                                        disc_number_tag = if !tag.is_none() {
-                                            match tag.unwrap().parse::<u32>() {
-                                                Ok(num) => Some(num),
-                                                Err(e) => {
-                                                    error!("{}: {}", tag.unwrap(), e);
-                                                    None
-                                                },
-                                            }
-                                        } else {
-                                            None
-                                        };
-                                    },
-                                    _ => (),
-                                }
-                            }
-                        }
-                    }
-                    "str" => {
-                        for (tag, tag_val) in type_map {
-                            if let MapVal::Str(value) = tag_val {
-                                match tag.as_str() {
-                                    "genre" => {
-                                        genre_tag = value.clone();
-                                    }
-                                    "album" => {
-                                        album_tag = value.clone();
-                                    }
-                                    "title" => {
-                                        title_tag = value.clone();
-                                    }
-                                    "albumartist" => {
-                                        albumartist_tag = value.clone();
-                                    }
-                                    "artist" => {
-                                        artist_tag = value.clone();
-                                    }
-                                    "filetype_" => {
-                                        filetype_tag = value.clone();
-                                    },
-                                    "tracknumber" => {
-                                        let tag = re.captures(&value).and_then(|cap| {
-                                            cap.get(0).map(|s| s.as_str())
-                                        });
-                                        debug!("{:?}", tag);
-                                        track_number_tag = if !tag.is_none() {
-                                            
-                                            match tag.unwrap().parse::<u32>() {
-                                                Ok(num) => Some(num),
-                                                Err(e) => {
-                                                    error!("{}: {}", tag.unwrap(), e);
-                                                    None
-                                                },
-                                            }
-                                        } else {
-                                            None
-                                        };
-                                    },
-                                    "discnumber" => {
-                                        let tag = re.captures(&value).and_then(|cap| {
-                                            cap.get(0).map(|s| s.as_str())
-                                        });
-                                        disc_number_tag = if !tag.is_none() {
-                                            match tag.unwrap().parse::<u32>() {
-                                                Ok(num) => Some(num),
-                                                Err(e) => {
-                                                    error!("{}: {}", tag.unwrap(), e);
-                                                    None
-                                                },
-                                            }
-                                        } else {
-                                            None
-                                        };
-                                    },
-                                    _ => (),
-                                }
-                            }
-                        }
-                    }
-                    "float" => {
-                        for (tag, tag_val) in type_map {
-                            if let MapVal::Float(value) = tag_val {
-                                match tag.as_str() {
-                                    "duration" => {
-                                        duration_tag = Some(value);
-                                    }
-                                    _ => (),
-                                }
-                            }
-                        }
-                    }
-                    _ => error!("unknown key type"),
-                }
+
+            let TrackTags {
+                title: title_tag,
+                artist: artist_tag,
+                album: album_tag,
+                albumartist: albumartist_tag,
+                date: date_tag,
+                genre: genre_tag,
+                duration: duration_tag,
+                disc_number: disc_number_tag,
+                track_number: track_number_tag,
+                filetype: filetype_tag,
+                artists,
+                albumartists,
+                genres,
+                compilation,
+                extended,
+            } = read_tags(track_map, &re, &options);
+
+            for reason in tag_problems(uri, &title_tag, &album_tag, &albumartist_tag, track_number_tag) {
+                database.add_import_problem(&tx, &folder_uri, uri, &reason)?;
             }
 
             let album_key = format!("{}{}", album_tag, albumartist_tag);
@@ -359,30 +253,17 @@ impl Importer {
                 },
             };
    
-            // add genre to the database table
-            let genre_id = if !genre_tag.is_empty() {
-                if !added_genres.contains_key(&genre_tag) {
-                    Some(database.add_genre(&tx, genre_tag.clone())?)
-                } else {
-                    //debug!("Already added genre {}, retrieving from map.", genre_tag);
-                    Some(added_genres[&genre_tag])
-                }
-            } else {
-                None
-            };
+            // add genres to the database table
+            let genre_ids = add_genres(&tx, &genres, &mut added_genres)?;
 
-            // add artists to the database table
-            let artist_id = if !albumartist_tag.is_empty() {
-                if !added_artists.contains_key(&albumartist_tag) {
-                    database.add_artist(&tx, albumartist_tag.clone())?
-                } else {
-                    //debug!("Already added artist {}, retrieving from map.", albumartist_tag);
-                    added_artists[&albumartist_tag]
-                }
-            } else {
+            // add artists to the database table, the first album artist is the primary one
+            if albumartists.is_empty() {
                 error!("No artist found for track {}", title_tag);
+                database.add_import_problem(&tx, &folder_uri, uri, &i18n("Skipped, no artist"))?;
                 continue;
-            };
+            }
+            let album_artist_ids = add_artists(&tx, &albumartists, &mut added_artists)?;
+            let track_artist_ids = add_artists(&tx, &artists, &mut added_artists)?;
 
             // add cover art to the database table
             let cover_art_id = if let Some(bytes) = cover_art {
@@ -402,12 +283,13 @@ impl Importer {
                 database.add_album_full(
                     &tx,
                     album_tag.clone(),
-                    artist_tag,
+                    albumartist_tag.clone(),
                     date_tag.clone(),
                     genre_tag.clone(),
                     cover_art_id,
-                    artist_id,
-                    genre_id,
+                    &album_artist_ids,
+                    &genre_ids,
+                    compilation,
                 )?
             } else {
                 //debug!("Already added {}, retrieving from map.", album_key);
@@ -415,17 +297,24 @@ impl Importer {
             };
         
             let duration = match duration_tag {
-                Some(d) => *d,
+                Some(d) => d,
                 None => {
                     error!("No track duration for {}", song_key);
+                    database.add_import_problem(&tx, &folder_uri, uri, &i18n("Skipped, unknown duration"))?;
                     continue;
                 },
             };
 
             
 
-            let metadata = fs::metadata(uri.to_string()).unwrap();
-            let modification_time = metadata.modified().unwrap();
+            let modification_time = match fs::metadata(uri.to_string()).and_then(|metadata| metadata.modified()) {
+                Ok(modification_time) => modification_time,
+                Err(e) => {
+                    error!("Unable to read metadata of {}: {}", uri, e);
+                    database.add_import_problem(&tx, &folder_uri, uri, &i18n("Skipped, file could not be read"))?;
+                    continue;
+                },
+            };
             let modification_time_dt = DateTime::<Utc>::from(modification_time);
 
             // if let Some(w) = window {
@@ -438,23 +327,23 @@ impl Importer {
                 filetype_tag,
                 album_tag,
                 albumartist_tag.clone(),
+                artist_tag,
                 date_tag,
+                genre_tag,
                 duration,
                 tracknumber,
                 discnumber,
                 album_id,
-                artist_id,
+                &track_artist_ids,
+                &genre_ids,
                 cover_art_id,
                 uri.to_string(),
                 modification_time_dt,
                 current_folder_id,
             )?;
-
-
-            if !genre_tag.is_empty() && !genre_id.is_none(){
-                added_genres.insert(genre_tag, genre_id.unwrap());
-            }
-            added_artists.insert(albumartist_tag, artist_id);
+            database.sync_album_genres(&tx, album_id)?;
+            database.set_track_tags(&tx, track_id, &extended)?;
+            add_musicbrainz_ids(&tx, album_id, &album_artist_ids, &track_artist_ids, &extended)?;
 
 
             if let Some(data) = cover_art {
@@ -487,6 +376,238 @@ impl Importer {
 
         Ok(())
     }
+
+    //Applies the tags of new or modified files of an already imported folder, existing tracks are updated in place
+    pub fn update_database_from_tags(&self, tx: &Transaction, folder_id: i64, folder_uri: &str, tags: HashMap<String, HashMap<String, HashMap<String, MapVal>>>, cover_art_map: HashMap<String, Vec<u8>>) -> Result<Vec<i64>, Box<dyn Error>> {
+        debug!("Updating database from tags");
+        let database = util::database();
+
+        let re = Regex::new(r"^[^\d]*(\d+)").unwrap();
+        let options = TagOptions::from_settings(&self.settings);
+        let mut updated_tracks = Vec::new();
+        let mut added_artists: HashMap<String, i64> = HashMap::new();
+        let mut added_genres: HashMap<String, i64> = HashMap::new();
+
+        for (uri, track_map) in &tags {
+            debug!("getting tags from:\n\t -> {:?}", uri);
+            database.clear_import_problems(&tx, uri)?;
+
+            let TrackTags {
+                title: title_tag,
+                artist: artist_tag,
+                album: album_tag,
+                albumartist: albumartist_tag,
+                date: date_tag,
+                genre: genre_tag,
+                duration: duration_tag,
+                disc_number: disc_number_tag,
+                track_number: track_number_tag,
+                filetype: filetype_tag,
+                artists,
+                albumartists,
+                genres,
+                compilation,
+                extended,
+            } = read_tags(track_map, &re, &options);
+
+            let duration = match duration_tag {
+                Some(d) => d,
+                None => {
+                    error!("No track duration for {}", uri);
+                    database.add_import_problem(&tx, folder_uri, uri, &i18n("Skipped, unknown duration"))?;
+                    continue;
+                },
+            };
+
+            if albumartists.is_empty() {
+                error!("No artist found for track {}", title_tag);
+                database.add_import_problem(&tx, folder_uri, uri, &i18n("Skipped, no artist"))?;
+                continue;
+            }
+
+            for reason in tag_problems(uri, &title_tag, &album_tag, &albumartist_tag, track_number_tag) {
+                database.add_import_problem(&tx, folder_uri, uri, &reason)?;
+            }
+
+            let discnumber = disc_number_tag.unwrap_or(1);
+            let tracknumber = match track_number_tag {
+                Some(number) => number,
+                None => {
+                    if album_tag == "Unknown Album" && albumartist_tag == "Unknown Artist" {
+                        database.query_orphan_tracks(&tx)? as u32 + 1
+                    } else {
+                        error!("No track number for {}", uri);
+                        1
+                    }
+                },
+            };
+
+            let genre_ids = add_genres(&tx, &genres, &mut added_genres)?;
+
+            let album_artist_ids = add_artists(&tx, &albumartists, &mut added_artists)?;
+            let artist_id = album_artist_ids[0];
+            let track_artist_ids = add_artists(&tx, &artists, &mut added_artists)?;
+
+            let cover_art_id = match cover_art_map.get(uri) {
+                Some(bytes) => match database.query_cover_art_id_by_data(&tx, bytes.as_slice())? {
+                    Some(id) => Some(id),
+                    None => Some(database.add_cover_art(&tx, bytes.as_slice())?),
+                },
+                None => None,
+            };
+
+            let album_id = match database.query_album_id_by_title_and_artist(&tx, &album_tag, artist_id)? {
+                Some(id) => {
+                    if let Some(art_id) = cover_art_id {
+                        if !database.album_has_cover_art(&tx, id)? {
+                            database.update_album_art(&tx, id, art_id)?;
+                        }
+                    }
+                    id
+                },
+                None => database.add_album_full(
+                    &tx,
+                    album_tag.clone(),
+                    albumartist_tag.clone(),
+                    date_tag.clone(),
+                    genre_tag.clone(),
+                    cover_art_id,
+                    &album_artist_ids,
+                    &genre_ids,
+                    compilation,
+                )?,
+            };
+
+            let metadata = match fs::metadata(uri.to_string()) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    error!("Unable to read metadata of {}: {}", uri, e);
+                    database.add_import_problem(&tx, folder_uri, uri, &i18n("Skipped, file could not be read"))?;
+                    continue;
+                },
+            };
+            let modification_time_dt = DateTime::<Utc>::from(metadata.modified()?);
+
+            let track_id = match database.query_track_id_by_uri(&tx, uri)? {
+                Some(track_id) => {
+                    database.update_track_full(
+                        &tx,
+                        track_id,
+                        title_tag,
+                        filetype_tag,
+                        album_tag,
+                        albumartist_tag,
+                        artist_tag,
+                        date_tag,
+                        genre_tag,
+                        duration,
+                        tracknumber,
+                        discnumber,
+                        album_id,
+                        &track_artist_ids,
+                        &genre_ids,
+                        cover_art_id,
+                        modification_time_dt,
+                    )?;
+                    track_id
+                },
+                None => {
+                    database.add_track_full(
+                        &tx,
+                        title_tag,
+                        filetype_tag,
+                        album_tag,
+                        albumartist_tag,
+                        artist_tag,
+                        date_tag,
+                        genre_tag,
+                        duration,
+                        tracknumber,
+                        discnumber,
+                        album_id,
+                        &track_artist_ids,
+                        &genre_ids,
+                        cover_art_id,
+                        uri.to_string(),
+                        modification_time_dt,
+                        folder_id,
+                    )?
+                },
+            };
+
+            database.sync_album_genres(&tx, album_id)?;
+            database.set_track_tags(&tx, track_id, &extended)?;
+            add_musicbrainz_ids(&tx, album_id, &album_artist_ids, &track_artist_ids, &extended)?;
+            updated_tracks.push(track_id);
+        }
+
+        Ok(updated_tracks)
+    }
+
+    //Pairs removed files with new files of the same title, album, album artist, duration & size, as (old uri, new uri)
+    pub fn match_moved_files(&self, tx: &Transaction, removed: &[String], tags: &HashMap<String, HashMap<String, HashMap<String, MapVal>>>) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut moved = Vec::new();
+        if removed.is_empty() {
+            return Ok(moved);
+        }
+
+        let database = util::database();
+        let re = Regex::new(r"^[^\d]*(\d+)").unwrap();
+        let options = TagOptions::from_settings(&self.settings);
+
+        let mut candidates = Vec::new();
+        for (uri, track_map) in tags {
+            if database.query_track_id_by_uri(tx, uri)?.is_some() {
+                continue;
+            }
+            let tags = read_tags(track_map, &re, &options);
+            if let Some(duration) = tags.duration {
+                let key = (normalize(&tags.title), normalize(&tags.album), normalize(&tags.albumartist));
+                let size = fs::metadata(uri).map(|metadata| metadata.len() as i64).unwrap_or(0);
+                candidates.push((uri.clone(), key, duration, size));
+            }
+        }
+
+        for old_uri in removed {
+            let (title, album, album_artist, duration, size) = match database.query_file_identity(tx, old_uri)? {
+                Some(identity) => identity,
+                None => continue,
+            };
+            let key = (normalize(&title), normalize(&album), normalize(&album_artist));
+
+            //sizes of files imported before they were recorded are unknown
+            let position = candidates.iter().position(|(_, candidate_key, candidate_duration, candidate_size)| {
+                *candidate_key == key
+                    && (candidate_duration - duration).abs() < MOVED_DURATION_TOLERANCE
+                    && (size == 0 || *candidate_size == 0 || *candidate_size == size)
+            });
+
+            if let Some(position) = position {
+                let (new_uri, _, _, _) = candidates.remove(position);
+                moved.push((old_uri.clone(), new_uri));
+            }
+        }
+
+        Ok(moved)
+    }
+}
+
+//Tags the extractors filled in with placeholders, the track is still imported
+fn tag_problems(uri: &str, title: &str, album: &str, albumartist: &str, track_number: Option<u32>) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let file_name = Path::new(uri).file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    if title == file_name {
+        problems.push(i18n("No title, the file name is used"));
+    }
+
+    if album == "Unknown Album" && albumartist == "Unknown Artist" {
+        problems.push(i18n("No album or artist, added to Unknown Album"));
+    } else if track_number.is_none() {
+        problems.push(i18n("Missing or invalid track number"));
+    }
+
+    problems
 }
 
 fn most_frequest_id(array: Vec<i64>) -> Option<i64> {
@@ -511,4 +632,356 @@ fn most_frequest_id(array: Vec<i64>) -> Option<i64> {
     }
 
     element_having_max_freq
-}
\ No newline at end of file
+}
+
+fn send_changes(kind: TagExtractorKind, folder_path: String, changed: Vec<String>, removed: Vec<String>, sender: Sender<DatabaseAction>) {
+    if changed.is_empty() {
+        send!(sender, DatabaseAction::ApplyRescan((folder_path, removed, HashMap::new(), HashMap::new())));
+        return;
+    }
+
+    let mut progress = ProgressReporter::rescan(folder_path.clone(), sender.clone());
+    match kind.extractor().extract_files(changed.clone(), &mut progress) {
+        Ok((tags, vec_byte_map)) => {
+            send!(sender, DatabaseAction::ApplyRescan((folder_path, removed, tags, vec_byte_map)));
+        },
+        Err(e) => {
+            error!("Unable to extract changes in {}: {}", folder_path, e);
+            //the changed files go to the import report & are read again on the next rescan, the removed ones are still removed
+            let reason = e.to_string();
+            for uri in changed.iter() {
+                progress.file_failed(uri, &reason);
+            }
+            send!(sender, DatabaseAction::ApplyRescan((folder_path, removed, HashMap::new(), HashMap::new())));
+        },
+    }
+}
+
+//Walks a music folder, returning every music file with its modification timestamp
+pub fn scan_folder(folder_path: &str) -> HashMap<String, i64> {
+    let mut files = HashMap::new();
+    let (_dirs, paths) = util::walk_folder(Path::new(folder_path));
+
+    for path in paths {
+        let hidden = matches!(path.file_name(), Some(name) if name.to_string_lossy().starts_with('.'));
+        if !hidden && is_song(&path) {
+            if let (Ok(metadata), Some(uri)) = (fs::metadata(&path), path.to_str()) {
+                if let Ok(modified) = metadata.modified() {
+                    files.insert(uri.to_string(), DateTime::<Utc>::from(modified).timestamp());
+                }
+            }
+        }
+    }
+
+    files
+}
+
+//Same extensions as Importer.is_song in importer.py
+pub fn is_song(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => SONG_EXTENSIONS.contains(&ext),
+        None => false,
+    }
+}
+
+#[derive(Debug, Default)]
+struct TrackTags {
+    title: String,
+    artist: String,
+    album: String,
+    albumartist: String,
+    date: String,
+    genre: String,
+    duration: Option<f32>,
+    disc_number: Option<u32>,
+    track_number: Option<u32>,
+    filetype: String,
+    artists: Vec<String>,
+    albumartists: Vec<String>,
+    genres: Vec<String>,
+    compilation: bool,
+    extended: HashMap<String, String>,
+}
+
+//Import preferences applied while reading the tags
+struct TagOptions {
+    split_credits: bool,
+    genre_separators: Vec<char>,
+    various_artists: String,
+}
+
+impl TagOptions {
+    fn from_settings(settings: &gio::Settings) -> TagOptions {
+        TagOptions {
+            split_credits: settings.boolean("split-artist-credits"),
+            genre_separators: settings.string("genre-separators")
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect(),
+            various_artists: match settings.string("various-artists-name").trim() {
+                "" => "Various Artists".to_string(),
+                name => name.to_string(),
+            },
+        }
+    }
+}
+
+fn parse_number(re: &Regex, value: &str) -> Option<u32> {
+    let tag = re.captures(value).and_then(|cap| {
+        cap.get(0).map(|s| s.as_str())
+    });
+    match tag {
+        Some(tag) => match tag.parse::<u32>() {
+            Ok(num) => Some(num),
+            Err(e) => {
+                error!("{}: {}", tag, e);
+                None
+            },
+        },
+        None => None,
+    }
+}
+
+//Translates the tag map of a single file, as returned by extracting.translate
+fn read_tags(track_map: &HashMap<String, HashMap<String, MapVal>>, re: &Regex, options: &TagOptions) -> TrackTags {
+    let mut tags = TrackTags::default();
+
+    for (type_key, type_map) in track_map {
+        match type_key.as_str() {
+            "str_list" | "str" => {
+                for (tag, tag_val) in type_map {
+                    //genre tags may hold several values, each split on the separators
+                    if tag == "genre" {
+                        let values = match tag_val {
+                            MapVal::List(values) => values.clone(),
+                            MapVal::Str(value) => vec![value.clone()],
+                            _ => continue,
+                        };
+                        tags.genres = genre_names(&values, &options.genre_separators);
+                        tags.genre = tags.genres.join(", ");
+                        continue;
+                    }
+
+                    //artist tags may hold several values, all of them are kept
+                    if tag == "artist" || tag == "albumartist" {
+                        let values = match tag_val {
+                            MapVal::List(values) => values.clone(),
+                            MapVal::Str(value) => vec![value.clone()],
+                            _ => continue,
+                        };
+                        let names = artist_names(&values, options.split_credits);
+                        if names.is_empty() {
+                            continue;
+                        }
+
+                        let display = values.iter()
+                            .map(|v| v.trim())
+                            .filter(|v| !v.is_empty())
+                            .collect::<Vec<&str>>()
+                            .join(", ");
+
+                        if tag == "artist" {
+                            tags.artist = display;
+                            tags.artists = names;
+                        } else {
+                            tags.albumartist = display;
+                            tags.albumartists = names;
+                        }
+                        continue;
+                    }
+
+                    //extended tags keep every value of the tag
+                    if let Some(key) = extended_tag_key(tag) {
+                        let values = match tag_val {
+                            MapVal::List(values) => values.clone(),
+                            MapVal::Str(value) => vec![value.clone()],
+                            _ => continue,
+                        };
+                        let value = values.iter()
+                            .map(|v| v.trim())
+                            .filter(|v| !v.is_empty())
+                            .collect::<Vec<&str>>()
+                            .join(", ");
+                        if !value.is_empty() {
+                            tags.extended.entry(key.to_string()).or_insert(value);
+                        }
+                        continue;
+                    }
+
+                    let value = match tag_val {
+                        MapVal::List(value) => match value.first() {
+                            Some(v) => v.clone(),
+                            None => continue,
+                        },
+                        MapVal::Str(value) => value.clone(),
+                        _ => continue,
+                    };
+
+                    match tag.as_str() {
+                        "album" => tags.album = value,
+                        "title" => tags.title = value,
+                        "date" => tags.date = value,
+                        "filetype_" => tags.filetype = value,
+                        "tracknumber" => tags.track_number = parse_number(re, &value),
+                        "discnumber" => tags.disc_number = parse_number(re, &value),
+                        "compilation" => tags.compilation = matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"),
+                        _ => (),
+                    }
+                }
+            }
+            "float" => {
+                for (tag, tag_val) in type_map {
+                    if let MapVal::Float(value) = tag_val {
+                        match tag.as_str() {
+                            "duration" => {
+                                tags.duration = Some(*value);
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
+            _ => error!("unknown key type"),
+        }
+    }
+
+    //fall back on each other, so every track has an artist & every album an album artist
+    if tags.artists.is_empty() {
+        tags.artist = tags.albumartist.clone();
+        tags.artists = tags.albumartists.clone();
+    } else if tags.albumartists.is_empty() {
+        tags.albumartist = tags.artist.clone();
+        tags.albumartists = tags.artists.clone();
+    }
+
+    //compilations are grouped into one album, whatever the album artist of each track
+    if tags.compilation {
+        tags.albumartist = options.various_artists.clone();
+        tags.albumartists = vec![options.various_artists.clone()];
+    }
+
+    tags
+}
+
+//Name an extended tag is stored under, None for the tags not kept
+fn extended_tag_key(tag: &str) -> Option<&'static str> {
+    if let Some(key) = EXTENDED_TAGS.iter().find(|key| **key == tag) {
+        return Some(key);
+    }
+    TAG_ALIASES.iter().find(|(alias, _)| *alias == tag).map(|(_, key)| *key)
+}
+
+//Unique artist names of the tag values in credit order, optionally splitting joint credits
+fn artist_names(values: &[String], split_credits: bool) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for value in values {
+        let parts: Vec<String> = if split_credits {
+            CREDIT_SEPARATORS.split(value)
+                .map(|part| part.trim_matches(|c: char| c.is_whitespace() || "()[]".contains(c)).to_string())
+                .collect()
+        } else {
+            vec![value.trim().to_string()]
+        };
+
+        for part in parts {
+            if !part.is_empty() && !names.contains(&part) {
+                names.push(part);
+            }
+        }
+    }
+
+    names
+}
+
+//Unique genre names of the tag values, ie "Jazz; Funk" is Jazz & Funk
+fn genre_names(values: &[String], separators: &[char]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for value in values {
+        for part in value.split(|c| separators.contains(&c)) {
+            let part = part.trim().to_string();
+            if !part.is_empty() && !names.contains(&part) {
+                names.push(part);
+            }
+        }
+    }
+
+    names
+}
+
+//Returns the ids of the genres, adding the ones not in the database yet
+fn add_genres(tx: &Transaction, names: &[String], added_genres: &mut HashMap<String, i64>) -> Result<Vec<i64>, Box<dyn Error>> {
+    let database = util::database();
+    let mut ids = Vec::new();
+
+    for name in names {
+        let id = match added_genres.get(name) {
+            Some(id) => *id,
+            None => {
+                let id = match database.query_genre_id_by_name(tx, name)? {
+                    Some(id) => id,
+                    None => database.add_genre(tx, name.clone())?,
+                };
+                added_genres.insert(name.clone(), id);
+                id
+            },
+        };
+
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    Ok(ids)
+}
+
+//Stores the musicbrainz ids of the album & of its artists, artist ids are matched by credit order
+fn add_musicbrainz_ids(tx: &Transaction, album_id: i64, album_artist_ids: &[i64], track_artist_ids: &[i64], extended: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+    let database = util::database();
+
+    let release_id = extended.get("musicbrainz_albumid").map(|id| id.as_str()).unwrap_or_default();
+    let release_group_id = extended.get("musicbrainz_releasegroupid").map(|id| id.as_str()).unwrap_or_default();
+    if !release_id.is_empty() || !release_group_id.is_empty() {
+        database.set_album_musicbrainz_ids(tx, album_id, release_id, release_group_id)?;
+    }
+
+    for (tag, artist_ids) in [("musicbrainz_albumartistid", album_artist_ids), ("musicbrainz_artistid", track_artist_ids)] {
+        if let Some(ids) = extended.get(tag) {
+            let ids: Vec<&str> = ids.split(',').map(|id| id.trim()).filter(|id| !id.is_empty()).collect();
+            if ids.len() == artist_ids.len() {
+                for (artist_id, musicbrainz_id) in artist_ids.iter().zip(ids) {
+                    database.set_artist_musicbrainz_id(tx, *artist_id, musicbrainz_id)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+//Returns the ids of the artists in credit order, adding the ones not in the database yet
+fn add_artists(tx: &Transaction, names: &[String], added_artists: &mut HashMap<String, i64>) -> Result<Vec<i64>, Box<dyn Error>> {
+    let database = util::database();
+    let mut ids = Vec::new();
+
+    for name in names {
+        let id = match added_artists.get(name) {
+            Some(id) => *id,
+            None => {
+                let id = match database.query_artist_id_by_name(tx, name)? {
+                    Some(id) => id,
+                    None => database.add_artist(tx, name.clone())?,
+                };
+                added_artists.insert(name.clone(), id);
+                id
+            },
+        };
+
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    Ok(ids)
+}
@@ -14,7 +14,18 @@ mod views;
 mod i18n;
 mod toasts;
 mod importer;
+mod import_progress;
+mod tag_extractor;
+mod watcher;
+mod analysis;
+mod migrations;
+mod backup;
 mod search;
+mod duplicates;
+mod statistics;
+mod rating;
+mod smart_playlist;
+mod playlist_file;
 mod sort;
 mod web;
 
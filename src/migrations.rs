@@ -0,0 +1,688 @@
+/* migrations.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use rusqlite::Connection;
+
+use std::{error::Error, fmt};
+use log::{debug, error};
+
+#[derive(Debug)]
+pub struct MigrationError {
+    pub version: u32,
+    pub message: String,
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Migration to schema version {} failed: {}", self.version, self.message)
+    }
+}
+impl Error for MigrationError {}
+
+struct Migration {
+    version: u32,
+    description: &'static str,
+    sql: &'static str,
+}
+
+// Schema history, the database stores the last applied version in PRAGMA user_version.
+// Never edit an applied migration, append a new one with the next version instead.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Music_Folders
+        (
+            id  INTEGER PRIMARY KEY,
+            uri TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        File_URIs
+        (
+            id  INTEGER PRIMARY KEY,
+            uri TEXT NOT NULL,
+            last_modified TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Folder_File_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            folder_id INTEGER NOT NULL,
+            file_id INTEGER NOT NULL,
+            FOREIGN KEY (folder_id) REFERENCES Music_Folders(id),
+            FOREIGN KEY (file_id) REFERENCES File_URIs(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Genres
+        (
+            id  INTEGER PRIMARY KEY,
+            name TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Artists
+        (
+            id  INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            image_fetched INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Albums
+        (
+            id  INTEGER PRIMARY KEY,
+            title TEXT NOT NULL,
+            artist TEXT NOT NULL,
+            date TEXT NOT NULL,
+            genre TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Cover_Art
+        (
+            id  INTEGER PRIMARY KEY,
+            data BLOB NOT NULL,
+            thing INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Tracks
+        (
+            id  INTEGER PRIMARY KEY,
+            title TEXT NOT NULL,
+            filetype TEXT NOT NULL,
+            album_name TEXT NOT NULL,
+            date TEXT NOT NULL,
+            duration REAL NOT NULL,
+            track_number INTEGER NOT NULL,
+            disc_number INTEGER NOT NULL,
+            album_artist TEXT NOT NULL,
+            file_uri_id INTEGER NOT NULL,
+            FOREIGN KEY (file_uri_id) REFERENCES File_URIs(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Plays
+        (
+            id  INTEGER PRIMARY KEY,
+            playtime TIMESTAMP,
+            track_id INTEGER NOT NULL,
+            album_id INTEGER NOT NULL,
+            album_artist_id INTEGER NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES tracks(id),
+            FOREIGN KEY (album_id) REFERENCES albums(id),
+            FOREIGN KEY (album_artist_id) REFERENCES artists(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Playlists
+        (
+            id  INTEGER PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            creation_time TIMESTAMP,
+            modify_time TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Playlist_Entries
+        (
+            id  INTEGER PRIMARY KEY,
+            playlist_position INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Playlist_Entry_Track_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            playlist_entry_id INTEGER NOT NULL,
+            track_id INTEGER NOT NULL,
+            FOREIGN KEY (playlist_entry_id) REFERENCES Playlist_Entries(id),
+            FOREIGN KEY (track_id) REFERENCES Tracks(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Playlist_Entry_Playlist_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            playlist_entry_id INTEGER NOT NULL,
+            playlist_id INTEGER NOT NULL,
+            FOREIGN KEY (playlist_entry_id) REFERENCES Playlist_Entries(id),
+            FOREIGN KEY (playlist_id) REFERENCES Playlists(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Track_Folder_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            folder_id INTEGER NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES Tracks(id),
+            FOREIGN KEY (folder_id) REFERENCES Music_Folders(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Track_Album_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            album_id INTEGER NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES Tracks(id),
+            FOREIGN KEY (album_id) REFERENCES Albums(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Track_Artist_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            artist_id INTEGER NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES Tracks(id),
+            FOREIGN KEY (artist_id) REFERENCES Artists(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Track_Cover_Art_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            cover_art_id INTEGER NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES Tracks(id),
+            FOREIGN KEY (cover_art_id) REFERENCES Cover_Art(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Album_Cover_Art_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            album_id INTEGER NOT NULL,
+            cover_art_id INTEGER NOT NULL,
+            FOREIGN KEY (album_id) REFERENCES Albums(id),
+            FOREIGN KEY (cover_art_id) REFERENCES Cover_Art(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Album_Artist_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            album_id INTEGER NOT NULL,
+            artist_id INTEGER NOT NULL,
+            FOREIGN KEY (album_id) REFERENCES Albums(id),
+            FOREIGN KEY (artist_id) REFERENCES Artists(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Album_Genre_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            album_id INTEGER NOT NULL,
+            genre_id INTEGER NOT NULL,
+            FOREIGN KEY (album_id) REFERENCES Albums(id),
+            FOREIGN KEY (genre_id) REFERENCES Genres(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Discog_Artist_Image
+        (
+            id  INTEGER PRIMARY KEY,
+            url TEXT NOT NULL,
+            data BLOB NOT NULL,
+            thing INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS
+        Artist_Discog_Artist_Image_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            artist_id INTEGER NOT NULL,
+            image_id INTEGER NOT NULL,
+            FOREIGN KEY (artist_id) REFERENCES Artists(id),
+            FOREIGN KEY (image_id) REFERENCES Discog_Artist_Image(id)
+        );
+        "#,
+    },
+    Migration {
+        version: 2,
+        description: "junction table indexes",
+        sql: r#"
+        CREATE INDEX IF NOT EXISTS idx_folder_file_junction_folder_id ON Folder_File_Junction (folder_id);
+        CREATE INDEX IF NOT EXISTS idx_folder_file_junction_file_id ON Folder_File_Junction (file_id);
+        CREATE INDEX IF NOT EXISTS idx_track_folder_junction_track_id ON Track_Folder_Junction (track_id);
+        CREATE INDEX IF NOT EXISTS idx_track_folder_junction_folder_id ON Track_Folder_Junction (folder_id);
+        CREATE INDEX IF NOT EXISTS idx_track_album_junction_track_id ON Track_Album_Junction (track_id);
+        CREATE INDEX IF NOT EXISTS idx_track_album_junction_album_id ON Track_Album_Junction (album_id);
+        CREATE INDEX IF NOT EXISTS idx_track_artist_junction_track_id ON Track_Artist_Junction (track_id);
+        CREATE INDEX IF NOT EXISTS idx_track_artist_junction_artist_id ON Track_Artist_Junction (artist_id);
+        CREATE INDEX IF NOT EXISTS idx_track_cover_art_junction_track_id ON Track_Cover_Art_Junction (track_id);
+        CREATE INDEX IF NOT EXISTS idx_track_cover_art_junction_cover_art_id ON Track_Cover_Art_Junction (cover_art_id);
+        CREATE INDEX IF NOT EXISTS idx_album_cover_art_junction_album_id ON Album_Cover_Art_Junction (album_id);
+        CREATE INDEX IF NOT EXISTS idx_album_cover_art_junction_cover_art_id ON Album_Cover_Art_Junction (cover_art_id);
+        CREATE INDEX IF NOT EXISTS idx_album_artist_junction_album_id ON Album_Artist_Junction (album_id);
+        CREATE INDEX IF NOT EXISTS idx_album_artist_junction_artist_id ON Album_Artist_Junction (artist_id);
+        CREATE INDEX IF NOT EXISTS idx_album_genre_junction_album_id ON Album_Genre_Junction (album_id);
+        CREATE INDEX IF NOT EXISTS idx_album_genre_junction_genre_id ON Album_Genre_Junction (genre_id);
+        CREATE INDEX IF NOT EXISTS idx_playlist_entry_track_junction_playlist_entry_id ON Playlist_Entry_Track_Junction (playlist_entry_id);
+        CREATE INDEX IF NOT EXISTS idx_playlist_entry_track_junction_track_id ON Playlist_Entry_Track_Junction (track_id);
+        CREATE INDEX IF NOT EXISTS idx_playlist_entry_playlist_junction_playlist_entry_id ON Playlist_Entry_Playlist_Junction (playlist_entry_id);
+        CREATE INDEX IF NOT EXISTS idx_playlist_entry_playlist_junction_playlist_id ON Playlist_Entry_Playlist_Junction (playlist_id);
+        CREATE INDEX IF NOT EXISTS idx_artist_discog_artist_image_junction_artist_id ON Artist_Discog_Artist_Image_Junction (artist_id);
+        CREATE INDEX IF NOT EXISTS idx_artist_discog_artist_image_junction_image_id ON Artist_Discog_Artist_Image_Junction (image_id);
+        CREATE INDEX IF NOT EXISTS idx_file_uris_uri ON File_URIs (uri);
+        CREATE INDEX IF NOT EXISTS idx_tracks_file_uri_id ON Tracks (file_uri_id);
+        CREATE INDEX IF NOT EXISTS idx_plays_track_id ON Plays (track_id);
+        "#,
+    },
+    Migration {
+        version: 3,
+        description: "multiple artists per track and album",
+        sql: r#"
+        ALTER TABLE Tracks ADD COLUMN artist TEXT NOT NULL DEFAULT '';
+        ALTER TABLE Track_Artist_Junction ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE Album_Artist_Junction ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+        UPDATE Tracks SET artist = album_artist;
+        "#,
+    },
+    Migration {
+        version: 4,
+        description: "multiple genres per track and album",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Track_Genre_Junction
+        (
+            id  INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            genre_id INTEGER NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES Tracks(id),
+            FOREIGN KEY (genre_id) REFERENCES Genres(id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_track_genre_junction_track_id ON Track_Genre_Junction (track_id);
+        CREATE INDEX IF NOT EXISTS idx_track_genre_junction_genre_id ON Track_Genre_Junction (genre_id);
+
+        ALTER TABLE Tracks ADD COLUMN genre TEXT NOT NULL DEFAULT '';
+        UPDATE Tracks SET genre = COALESCE((
+            SELECT Albums.genre FROM Albums INNER JOIN Track_Album_Junction ON Albums.id = Track_Album_Junction.album_id
+            WHERE Track_Album_Junction.track_id = Tracks.id LIMIT 1
+        ), '');
+        INSERT INTO Track_Genre_Junction (track_id, genre_id)
+            SELECT Track_Album_Junction.track_id, Album_Genre_Junction.genre_id
+            FROM Track_Album_Junction INNER JOIN Album_Genre_Junction ON Track_Album_Junction.album_id = Album_Genre_Junction.album_id;
+        "#,
+    },
+    Migration {
+        version: 5,
+        description: "compilation albums",
+        sql: r#"
+        ALTER TABLE Albums ADD COLUMN compilation INTEGER NOT NULL DEFAULT 0;
+        "#,
+    },
+    Migration {
+        version: 6,
+        description: "extended track tags",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Track_Tags
+        (
+            id  INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES Tracks(id)
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_track_tags_track_id_key ON Track_Tags (track_id, key);
+        -- the next rescan reads every flagged file again, filling in the tags of existing tracks
+        ALTER TABLE File_URIs ADD COLUMN reextract INTEGER NOT NULL DEFAULT 0;
+        UPDATE File_URIs SET reextract = 1;
+        "#,
+    },
+    Migration {
+        version: 7,
+        description: "musicbrainz ids",
+        sql: r#"
+        ALTER TABLE Albums ADD COLUMN musicbrainz_id TEXT NOT NULL DEFAULT '';
+        ALTER TABLE Albums ADD COLUMN musicbrainz_release_group_id TEXT NOT NULL DEFAULT '';
+        ALTER TABLE Artists ADD COLUMN musicbrainz_id TEXT NOT NULL DEFAULT '';
+
+        CREATE TABLE IF NOT EXISTS
+        MusicBrainz_Cover_Art
+        (
+            id  INTEGER PRIMARY KEY,
+            album_id INTEGER NOT NULL,
+            art_path TEXT,
+            lookup_time TIMESTAMP,
+            FOREIGN KEY (album_id) REFERENCES Albums(id)
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_musicbrainz_cover_art_album_id ON MusicBrainz_Cover_Art (album_id);
+
+        -- the ids are read from the files on the next rescan
+        UPDATE File_URIs SET reextract = 1;
+        "#,
+    },
+    Migration {
+        version: 8,
+        description: "replaygain tags",
+        sql: r#"
+        -- the replaygain tags are read from the files on the next rescan
+        UPDATE File_URIs SET reextract = 1;
+        "#,
+    },
+    Migration {
+        version: 9,
+        description: "track analysis",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Track_Analysis
+        (
+            id  INTEGER PRIMARY KEY,
+            track_id INTEGER NOT NULL,
+            track_gain REAL,
+            track_peak REAL,
+            bpm REAL,
+            analysis_time TIMESTAMP,
+            FOREIGN KEY (track_id) REFERENCES Tracks(id)
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_track_analysis_track_id ON Track_Analysis (track_id);
+        "#,
+    },
+    Migration {
+        version: 10,
+        description: "import report",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Import_Report
+        (
+            id  INTEGER PRIMARY KEY,
+            folder TEXT NOT NULL,
+            uri TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            report_time TIMESTAMP
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_import_report_uri_reason ON Import_Report (uri, reason);
+        CREATE INDEX IF NOT EXISTS idx_import_report_folder ON Import_Report (folder);
+        "#,
+    },
+    Migration {
+        version: 11,
+        description: "album format policies",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Album_Format_Policy
+        (
+            id  INTEGER PRIMARY KEY,
+            album_key TEXT NOT NULL,
+            policy INTEGER NOT NULL
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_album_format_policy_album_key ON Album_Format_Policy (album_key);
+        "#,
+    },
+    Migration {
+        version: 12,
+        description: "file sizes, to match moved files",
+        sql: r#"
+        ALTER TABLE File_URIs ADD COLUMN size INTEGER NOT NULL DEFAULT 0;
+        "#,
+    },
+    Migration {
+        version: 13,
+        description: "skips & track added time",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Skips
+        (
+            id  INTEGER PRIMARY KEY,
+            skiptime TIMESTAMP,
+            track_id INTEGER NOT NULL,
+            FOREIGN KEY (track_id) REFERENCES tracks(id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_skips_track_id ON Skips (track_id);
+        ALTER TABLE Tracks ADD COLUMN added_time INTEGER NOT NULL DEFAULT 0;
+        -- the file time on disk, or the first play when that is earlier or unknown
+        UPDATE Tracks SET added_time = COALESCE(
+            MIN(
+                (SELECT NULLIF(last_modified, 0) FROM File_URIs WHERE File_URIs.id = Tracks.file_uri_id),
+                (SELECT MIN(playtime) FROM Plays WHERE Plays.track_id = Tracks.id)
+            ),
+            (SELECT NULLIF(last_modified, 0) FROM File_URIs WHERE File_URIs.id = Tracks.file_uri_id),
+            (SELECT MIN(playtime) FROM Plays WHERE Plays.track_id = Tracks.id),
+            0
+        );
+        "#,
+    },
+    Migration {
+        version: 14,
+        description: "favorites & star ratings",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Track_Ratings
+        (
+            id  INTEGER PRIMARY KEY,
+            uri TEXT NOT NULL,
+            favorite INTEGER NOT NULL DEFAULT 0,
+            stars INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_track_ratings_uri ON Track_Ratings (uri);
+        CREATE TABLE IF NOT EXISTS
+        Album_Ratings
+        (
+            id  INTEGER PRIMARY KEY,
+            album_key TEXT NOT NULL,
+            favorite INTEGER NOT NULL DEFAULT 0,
+            stars INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_album_ratings_album_key ON Album_Ratings (album_key);
+        "#,
+    },
+    Migration {
+        version: 15,
+        description: "smart playlists",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Smart_Playlists
+        (
+            id  INTEGER PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            rules TEXT NOT NULL,
+            creation_time TIMESTAMP,
+            modify_time TIMESTAMP
+        );
+        "#,
+    },
+    Migration {
+        version: 16,
+        description: "playlist files imported from music folders",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS
+        Playlist_Files
+        (
+            id  INTEGER PRIMARY KEY,
+            uri TEXT NOT NULL,
+            last_modified INTEGER NOT NULL,
+            playlist_id INTEGER
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_playlist_files_uri ON Playlist_Files (uri);
+        "#,
+    },
+];
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+//Applies every migration newer than the database, each one in its own transaction
+pub fn migrate(conn: &mut Connection) -> Result<(), MigrationError> {
+    let current: u32 = conn
+        .query_row("PRAGMA user_version;", [], |row| row.get(0))
+        .map_err(|e| MigrationError { version: 0, message: e.to_string() })?;
+
+    debug!("schema version: {}, latest: {}", current, latest_version());
+
+    if current > latest_version() {
+        return Err(MigrationError {
+            version: current,
+            message: "the library was created by a newer version of Resonance".to_string(),
+        });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        debug!("applying migration {}: {}", migration.version, migration.description);
+
+        let apply = |conn: &mut Connection| -> Result<(), rusqlite::Error> {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration.sql)?;
+            tx.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()
+        };
+
+        if let Err(e) = apply(conn) {
+            error!("migration {} ({}) failed: {}", migration.version, migration.description, e);
+            return Err(MigrationError {
+                version: migration.version,
+                message: e.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.query_row("PRAGMA user_version;", [], |row| row.get(0)).unwrap()
+    }
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({});", table)).unwrap();
+        let columns = stmt.query_map([], |row| row.get(1)).unwrap();
+        columns.map(|column| column.unwrap()).collect()
+    }
+
+    //Leaves the database as an older release of Resonance created it
+    fn migrate_to(conn: &Connection, version: u32) {
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            conn.execute_batch(migration.sql).unwrap();
+            conn.pragma_update(None, "user_version", migration.version).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_versions() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as u32 + 1);
+        }
+        assert_eq!(latest_version(), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn test_migrate_new_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+
+        assert_eq!(user_version(&conn), latest_version());
+        assert!(columns(&conn, "File_URIs").contains(&"reextract".to_string()));
+        assert!(columns(&conn, "File_URIs").contains(&"size".to_string()));
+        assert!(columns(&conn, "Tracks").contains(&"added_time".to_string()));
+        assert!(columns(&conn, "Albums").contains(&"musicbrainz_id".to_string()));
+        for table in ["Track_Tags", "Skips", "Track_Ratings", "Album_Ratings", "Smart_Playlists", "Playlist_Files"] {
+            assert!(!columns(&conn, table).is_empty(), "{} is missing", table);
+        }
+    }
+
+    #[test]
+    fn test_migrate_twice() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        conn.execute("INSERT INTO Music_Folders (uri) VALUES ('/music');", []).unwrap();
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), latest_version());
+        let folders: i64 = conn.query_row("SELECT COUNT(*) FROM Music_Folders;", [], |row| row.get(0)).unwrap();
+        assert_eq!(folders, 1);
+    }
+
+    #[test]
+    fn test_migrate_old_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate_to(&conn, 5);
+        conn.execute_batch(
+            "INSERT INTO File_URIs (id, uri, last_modified) VALUES (1, '/music/a.flac', 500), (2, '/music/b.flac', 0);
+            INSERT INTO Tracks (id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id)
+                VALUES (1, 'A', 'flac', 'Album', '', 1.0, 1, 1, 'Artist', 1), (2, 'B', 'flac', 'Album', '', 1.0, 2, 1, 'Artist', 2);
+            INSERT INTO Plays (playtime, track_id, album_id, album_artist_id) VALUES (300, 1, 1, 1), (800, 2, 1, 1), (900, 2, 1, 1);",
+        ).unwrap();
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), latest_version());
+
+        //existing files are read again on the next rescan, their time on disk is kept
+        let files: Vec<(i64, i64)> = conn
+            .prepare("SELECT last_modified, reextract FROM File_URIs ORDER BY id;").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .map(|file| file.unwrap())
+            .collect();
+        assert_eq!(files, vec![(500, 1), (0, 1)]);
+
+        //the earliest of the file time & the first play, an unknown file time is skipped
+        let added: Vec<i64> = conn
+            .prepare("SELECT added_time FROM Tracks ORDER BY id;").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .map(|added| added.unwrap())
+            .collect();
+        assert_eq!(added, vec![300, 800]);
+    }
+
+    #[test]
+    fn test_retag_played_track() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        conn.execute_batch(
+            "INSERT INTO File_URIs (id, uri, last_modified) VALUES (1, '/music/a.flac', 500);
+            INSERT INTO Artists (id, name, image_fetched) VALUES (1, 'Old Artist', 0), (2, 'New Artist', 0);
+            INSERT INTO Albums (id, title, artist, date, genre) VALUES (1, 'Old', 'Old Artist', '', ''), (2, 'New', 'New Artist', '', '');
+            INSERT INTO Album_Artist_Junction (album_id, artist_id) VALUES (1, 1), (2, 2);
+            INSERT INTO Tracks (id, title, filetype, album_name, date, duration, track_number, disc_number, album_artist, file_uri_id)
+                VALUES (1, 'A', 'flac', 'Old', '', 1.0, 1, 1, 'Old Artist', 1);
+            INSERT INTO Track_Album_Junction (track_id, album_id) VALUES (1, 1);
+            INSERT INTO Plays (playtime, track_id, album_id, album_artist_id) VALUES (300, 1, 1, 1), (400, 1, 1, 1);
+            UPDATE Track_Album_Junction SET album_id = 2 WHERE track_id = 1;",
+        ).unwrap();
+
+        //the old album & artist lost their last track, so the prune removes them
+        let prune = "DELETE FROM Album_Artist_Junction WHERE album_id = 1; DELETE FROM Albums WHERE id = 1; DELETE FROM Artists WHERE id = 1;";
+
+        let tx = conn.transaction().unwrap();
+        assert!(tx.execute_batch(prune).is_err());
+        tx.rollback().unwrap();
+
+        assert_eq!(crate::database::repoint_plays(&conn, 1, 2).unwrap(), 2);
+        conn.execute_batch(prune).unwrap();
+
+        let plays: Vec<(i64, i64)> = conn
+            .prepare("SELECT album_id, album_artist_id FROM Plays ORDER BY playtime;").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .map(|play| play.unwrap())
+            .collect();
+        assert_eq!(plays, vec![(2, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_migrate_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+
+        let error = migrate(&mut conn).unwrap_err();
+        assert_eq!(error.version, latest_version() + 1);
+        assert_eq!(user_version(&conn), latest_version() + 1);
+    }
+}
@@ -9,9 +9,12 @@ use gtk::subclass::prelude::*;
 use gtk::{glib, gio};
 
 use std::{cell::RefCell, cell::Cell, rc::Rc, collections::{HashSet, HashMap}};
-use regex::Regex;
 
 use super::track::Track;
+use crate::util;
+use crate::duplicates;
+use crate::rating::{self, Rating};
+use crate::i18n::i18n;
 
 mod imp {
     use super::*;
@@ -27,12 +30,15 @@ mod imp {
         pub sort_string: RefCell<String>,
         pub sort_title: RefCell<String>,
         pub sort_artist: RefCell<String>,
-        pub genre_id: Cell<Option<i64>>,
+        pub genre_ids: RefCell<Vec<i64>>,
         pub artist_id: Cell<i64>,
+        pub artist_ids: RefCell<Vec<i64>>,
         pub cover_art_id: Cell<Option<i64>>,
+        pub compilation: Cell<bool>,
         pub track_ids: RefCell<HashSet<i64>>,
         pub discs: RefCell<HashMap<i64, HashMap<i64, Rc<Track>>>>,
         pub total_duration: Cell<f64>,
+        pub rating: Cell<Rating>,
         // genre_parent: RefCell<Option<RefCell<Genre>>>,
         // artist_parent: RefCell<Option<RefCell<Artist>>>,
         pub menu: gio::Menu,
@@ -67,18 +73,7 @@ impl Album {
 
     pub fn load_info(&self, id: i64, title: String, album_artist: String, artist_id: i64, date: String, genre: String) {
         let imp = self.imp();
-        
-        let re = Regex::new(r"^(the|a|an)\s+").unwrap();
-        
-        let lowercase_title = title.to_lowercase();
-        let stripped_title: std::borrow::Cow<str> = re.replace(&lowercase_title, "");
 
-        let lowercase_artist= album_artist.to_lowercase();
-        let stripped_artist = re.replace(&lowercase_artist, "");
-
-        imp.sort_title.replace(format!("{}", stripped_title));
-        imp.sort_artist.replace(format!("{}", stripped_artist));
-        imp.sort_string.replace(format!("{} {}", stripped_title, stripped_artist));
         imp.search_string.replace(format!("{} {}", title, album_artist));
 
         imp.id.set(id);
@@ -87,6 +82,8 @@ impl Album {
         imp.date.replace(date);
         imp.genre.replace(genre);
         imp.artist_id.set(artist_id);
+        imp.artist_ids.replace(vec![artist_id]);
+        self.update_sort_keys();
         //self.create_menu();
     }
 
@@ -100,15 +97,93 @@ impl Album {
             imp.total_duration.set(duration);
             //debug!("ADDED {} to {}", track.title(), self.title.borrow());
             imp.discs.borrow_mut().entry(disc_number).or_default().insert(track.track_number(), track.clone());
+
+            if track.tag("albumsort").is_some() || track.tag("albumartistsort").is_some() {
+                self.update_sort_keys();
+            }
+
+            //album wide tags of the tracks are matched by the album search too
+            for key in ["label", "composer"] {
+                if let Some(value) = track.tag(key) {
+                    let mut search_string = imp.search_string.borrow_mut();
+                    if !search_string.contains(&value) {
+                        search_string.push(' ');
+                        search_string.push_str(&value);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn remove_track(&self, track: &Track) {
+        let imp = self.imp();
+        if imp.track_ids.borrow_mut().remove(&track.id()) {
+            let disc_number = std::cmp::max(0, track.disc_number() - 1);
+            let duration = imp.total_duration.get() - track.duration();
+            imp.total_duration.set(duration);
+
+            let mut discs = imp.discs.borrow_mut();
+            if let Some(disc) = discs.get_mut(&disc_number) {
+                disc.retain(|_track_n, t| t.id() != track.id());
+                if disc.is_empty() {
+                    discs.remove(&disc_number);
+                }
+            }
+            drop(discs);
+
+            if track.tag("albumsort").is_some() || track.tag("albumartistsort").is_some() {
+                self.update_sort_keys();
+            }
         }
     }
 
+    //Sort tags of the tracks take precedence over the names stripped of their article
+    pub fn update_sort_keys(&self) {
+        let imp = self.imp();
+        let sort_title = match self.tag("albumsort") {
+            Some(sort) => sort.to_lowercase(),
+            None => util::sort_name(&imp.title.borrow()),
+        };
+        let sort_artist = match self.tag("albumartistsort") {
+            Some(sort) => sort.to_lowercase(),
+            None => util::sort_name(&imp.album_artist.borrow()),
+        };
+
+        imp.sort_string.replace(format!("{} {}", sort_title, sort_artist));
+        imp.sort_title.replace(sort_title);
+        imp.sort_artist.replace(sort_artist);
+    }
+
+    //First value of an extended tag among the tracks, in disc & track order
+    pub fn tag(&self, key: &str) -> Option<String> {
+        self.tracks().iter().find_map(|track| track.tag(key))
+    }
+
     pub fn add_genre(&self, genre_id: i64) {
-        self.imp().genre_id.set(Some(genre_id));
+        let mut genre_ids = self.imp().genre_ids.borrow_mut();
+        if !genre_ids.contains(&genre_id) {
+            genre_ids.push(genre_id);
+        }
+    }
+
+    pub fn remove_genre(&self, genre_id: i64) {
+        self.imp().genre_ids.borrow_mut().retain(|id| *id != genre_id);
     }
 
+    //Credits another album artist, the primary artist_id is set on load
     pub fn add_artist(&self, artist_id: i64) {
-        self.imp().artist_id.set(artist_id);
+        let mut artist_ids = self.imp().artist_ids.borrow_mut();
+        if !artist_ids.contains(&artist_id) {
+            artist_ids.push(artist_id);
+        }
+    }
+
+    pub fn set_compilation(&self, compilation: bool) {
+        self.imp().compilation.set(compilation);
+    }
+
+    pub fn is_compilation(&self) -> bool {
+        self.imp().compilation.get()
     }
 
     pub fn add_cover_art_id(&self, cover_art_option: Option<i64>) {
@@ -127,6 +202,18 @@ impl Album {
         self.imp().id.get()
     }
 
+    pub fn artist_id(&self) -> i64 {
+        self.imp().artist_id.get()
+    }
+
+    pub fn artist_ids(&self) -> Vec<i64> {
+        self.imp().artist_ids.borrow().clone()
+    }
+
+    pub fn genre_ids(&self) -> Vec<i64> {
+        self.imp().genre_ids.borrow().clone()
+    }
+
     pub fn title(&self) -> String {
         self.imp().title.borrow().clone()
     }
@@ -178,6 +265,40 @@ impl Album {
         array
     }
 
+    pub fn play_count(&self) -> u64 {
+        self.imp().discs.borrow().values().flat_map(|disc| disc.values()).map(|track| track.play_count()).sum()
+    }
+
+    pub fn last_played(&self) -> Option<i64> {
+        self.imp().discs.borrow().values().flat_map(|disc| disc.values()).filter_map(|track| track.last_played()).max()
+    }
+
+    pub fn skip_count(&self) -> u64 {
+        self.imp().discs.borrow().values().flat_map(|disc| disc.values()).map(|track| track.skip_count()).sum()
+    }
+
+    //The album counts as added when its latest track was
+    pub fn added_time(&self) -> i64 {
+        self.imp().discs.borrow().values().flat_map(|disc| disc.values()).map(|track| track.added_time()).max().unwrap_or(0)
+    }
+
+    //Ratings are keyed on the names of the album, like the per album format policies
+    pub fn album_key(&self) -> String {
+        duplicates::album_key(&self.title(), &self.artist())
+    }
+
+    pub fn set_rating(&self, rating: Rating) {
+        self.imp().rating.set(rating);
+    }
+
+    pub fn rating(&self) -> Rating {
+        self.imp().rating.get()
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.imp().rating.get().favorite
+    }
+
     pub fn track_ids(&self) -> Vec<i64> {
         let mut ret = Vec::new();
         for track in self.tracks() {
@@ -219,7 +340,6 @@ impl Album {
         self.imp().discs.borrow().len()
     }
 
-    #[allow(dead_code)]
     fn create_menu(&self) {
         let imp = self.imp();
 
@@ -258,9 +378,15 @@ impl Album {
         menu.append_item(&menu_item);
 
         imp.menu.append_section(Some("Playlist"), &menu);
+
+        let menu = rating::rating_menu("win.toggle-favorite-album", "win.rate-album", imp.id.get(), self.rating());
+        imp.menu.append_section(Some(&i18n("Rate")), &menu);
     }
 
+    //Rebuilt when shown, so the labels follow the favorite & rating of the album
     pub fn menu_model(&self)-> &gio::Menu {
+        self.imp().menu.remove_all();
+        self.create_menu();
         &self.imp().menu
     }
 }
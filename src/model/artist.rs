@@ -7,9 +7,9 @@
 use gtk::{subclass::prelude::*, glib};
 
 use std::{cell::Cell, cell::RefCell, rc::Rc};
-use regex::Regex;
 
 use super::album::Album;
+use crate::util;
 
 mod imp {
     use super::*;
@@ -18,9 +18,11 @@ mod imp {
     pub struct ArtistPriv {
         pub name: RefCell<String>,
         pub sort_name: RefCell<String>,
+        pub sort_tag: RefCell<Option<String>>,
         pub id: Cell<i64>,
         pub image_id: Cell<Option<i64>>,
         pub albums: RefCell<Option<Vec<Rc<Album>>>>,
+        pub appears_on: RefCell<Vec<Rc<Album>>>,
     }
 
 
@@ -58,17 +60,46 @@ impl Artist {
         imp.id.set(id);
         imp.image_id.set(image_optional);
 
-        let re = Regex::new(r"^(the|a|an)\s+").unwrap();
-        
-        let lowercase_name = name.to_lowercase();
-        let stripped_name: std::borrow::Cow<str> = re.replace(&lowercase_name, "");
-        imp.sort_name.replace(format!("{}", stripped_name));
-        
         imp.name.replace(name);
+        self.update_sort_keys();
+    }
+
+    pub fn update_sort_keys(&self) {
+        let imp = self.imp();
+        let sort_name = match imp.sort_tag.borrow().as_ref() {
+            Some(sort) => sort.to_lowercase(),
+            None => util::sort_name(&imp.name.borrow()),
+        };
+        imp.sort_name.replace(sort_name);
+    }
+
+    //Takes the artistsort or albumartistsort tag of an album crediting only this artist
+    pub fn add_sort_tag_from(&self, album: &Album) {
+        if self.imp().sort_tag.borrow().is_some() {
+            return;
+        }
+
+        let id = self.id();
+        let mut sort_tag = None;
+        if album.artist_ids() == [id] {
+            sort_tag = album.tag("albumartistsort");
+        }
+        if sort_tag.is_none() {
+            sort_tag = album.tracks()
+                .iter()
+                .filter(|track| track.artist_ids() == [id])
+                .find_map(|track| track.tag("artistsort"));
+        }
+
+        if sort_tag.is_some() {
+            self.imp().sort_tag.replace(sort_tag);
+            self.update_sort_keys();
+        }
     }
 
     pub fn add_album(&self, album: Rc<Album>) {
         let imp = self.imp();
+        self.add_sort_tag_from(&album);
 
         if None == imp.albums.borrow().as_ref() {
             imp.albums.replace(Some(vec![album]));
@@ -81,6 +112,23 @@ impl Artist {
         }
     }
 
+    //Album of another artist the artist is credited on
+    pub fn add_appearance(&self, album: Rc<Album>) {
+        self.add_sort_tag_from(&album);
+        let mut appears_on = self.imp().appears_on.borrow_mut();
+        if !appears_on.iter().any(|a| a.id() == album.id()) {
+            appears_on.push(album);
+        }
+    }
+
+    pub fn remove_appearance(&self, album_id: i64) {
+        self.imp().appears_on.borrow_mut().retain(|album| album.id() != album_id);
+    }
+
+    pub fn appears_on(&self) -> Vec<Rc<Album>> {
+        self.imp().appears_on.borrow().clone()
+    }
+
     pub fn id(&self) -> i64 {
         self.imp().id.get()
     }
@@ -97,6 +145,12 @@ impl Artist {
         self.imp().image_id.get().clone()
     }
 
+    pub fn remove_album(&self, album_id: i64) {
+        if let Some(albums) = self.imp().albums.borrow_mut().as_mut() {
+            albums.retain(|album| album.id() != album_id);
+        }
+    }
+
     pub fn albums(&self) -> Option<Vec<Rc<Album>>> {
         if let Some(albums) = self.imp().albums.borrow().as_ref() {
             Some(albums.clone())
@@ -105,6 +159,14 @@ impl Artist {
         }
     }
 
+    //Artists like "Various Artists", only credited as the album artist of compilations
+    pub fn only_compilations(&self) -> bool {
+        match self.imp().albums.borrow().as_ref() {
+            Some(albums) => !albums.is_empty() && albums.iter().all(|album| album.is_compilation()),
+            None => false,
+        }
+    }
+
     pub fn n_albums(&self) -> usize {
         match self.imp().albums.borrow().as_ref() {
             Some(albums) => albums.len(),
@@ -4,13 +4,12 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
-use gtk::{glib, gdk, gdk_pixbuf::Pixbuf};
+use gtk::glib;
 use gtk::subclass::prelude::*;
 
-use std::{cell::{RefCell, Cell}, rc::Rc};
-use log::debug;
+use std::cell::Cell;
 
-use super::cover_art_pixbuf_loader::CoverArtPixbufLoader;
+//A handle to a Cover_Art row, the image itself is loaded on demand by the CoverArtCache
 
 mod imp {
     use super::*;
@@ -18,7 +17,6 @@ mod imp {
     #[derive(Debug, Default)]
     pub struct CoverArt {
         pub id: Cell<i64>,
-        pub capl: RefCell<Option<CoverArtPixbufLoader>>,
     }
 
     #[glib::object_subclass]
@@ -43,42 +41,13 @@ glib::wrapper! {
 
 
 impl CoverArt {
-    pub fn new(id: i64, data: Vec<u8>)-> CoverArt {
+    pub fn new(id: i64) -> CoverArt {
         let cover_art: CoverArt = glib::Object::builder::<CoverArt>().build();
-        cover_art.load(id, data);
+        cover_art.imp().id.set(id);
         cover_art
     }
 
-    pub fn load(&self, id: i64, data: Vec<u8>) {
-        let imp = self.imp();
-        imp.id.set(id);
-        let capl = CoverArtPixbufLoader::new();
-        let result = capl.load(data);
-        match result {
-            Ok(()) => {
-                imp.capl.replace(Some(capl));
-            },
-            Err(err) => debug!("{}", err),
-        }
-    }
-
-
-    pub fn pixbuf(&self) ->  Result<Rc<Pixbuf>, String> {
-        match self.imp().capl.borrow().as_ref() {
-            Some(capl) => {
-                capl.pixbuf()
-            },
-            None => Err("No Pixbuf".to_string())
-        }
-    }
-
-    pub fn palette(&self) ->  Result<Option<Vec<gdk::RGBA>>, String> {
-        match self.imp().capl.borrow().as_ref() {
-            Some(capl) => {
-                Ok(capl.palette())
-            },
-            None => Err("No Palette".to_string())
-        }
+    pub fn id(&self) -> i64 {
+        self.imp().id.get()
     }
 }
-    
\ No newline at end of file
@@ -0,0 +1,317 @@
+/* cover_art_cache.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::{gdk, glib, glib::{Receiver, Sender}, gdk_pixbuf, gdk_pixbuf::Pixbuf, prelude::*};
+use gtk_macros::send;
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+use rusqlite::{Connection, OpenFlags};
+use directories_next::BaseDirs;
+use log::{debug, error};
+
+use crate::util;
+
+//Number of decoded pixbufs kept in memory, the least recently used are dropped first
+const MEMORY_CACHE_SIZE: usize = 400;
+
+//Number of threads decoding cover art
+const DECODE_THREADS: usize = 2;
+
+//Size the color palette is computed from
+const PALETTE_SIZE: i32 = 64;
+
+type ArtKey = (i64, i32);
+type ArtCallback = Box<dyn FnOnce(Result<Rc<Pixbuf>, String>)>;
+
+//Pixbufs can not cross threads, the workers send back the raw pixels
+pub struct DecodedArt {
+    key: ArtKey,
+    result: Result<RawPixels, String>,
+}
+
+struct RawPixels {
+    bytes: Vec<u8>,
+    has_alpha: bool,
+    width: i32,
+    height: i32,
+    rowstride: i32,
+}
+
+impl RawPixels {
+    fn from_pixbuf(pixbuf: &Pixbuf) -> RawPixels {
+        RawPixels {
+            bytes: pixbuf.read_pixel_bytes().to_vec(),
+            has_alpha: pixbuf.has_alpha(),
+            width: pixbuf.width(),
+            height: pixbuf.height(),
+            rowstride: pixbuf.rowstride(),
+        }
+    }
+
+    fn into_pixbuf(self) -> Pixbuf {
+        Pixbuf::from_bytes(
+            &glib::Bytes::from_owned(self.bytes),
+            gdk_pixbuf::Colorspace::Rgb,
+            self.has_alpha,
+            8,
+            self.width,
+            self.height,
+            self.rowstride,
+        )
+    }
+}
+
+//Loads cover art by id on demand, decoded at the requested size off the main thread.
+//Decoded art is kept in a bounded memory cache and as png thumbnails in the cache dir.
+pub struct CoverArtCache {
+    pixbufs: RefCell<HashMap<ArtKey, Rc<Pixbuf>>>,
+    recent: RefCell<VecDeque<ArtKey>>,
+    pending: RefCell<HashMap<ArtKey, Vec<ArtCallback>>>,
+    jobs: RefCell<Option<mpsc::Sender<ArtKey>>>,
+    sender: Sender<DecodedArt>,
+    receiver: RefCell<Option<Receiver<DecodedArt>>>,
+}
+
+impl fmt::Debug for CoverArtCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CoverArtCache")
+            .field("cached", &self.pixbufs.borrow().len())
+            .field("pending", &self.pending.borrow().len())
+            .finish()
+    }
+}
+
+impl CoverArtCache {
+    pub fn new() -> CoverArtCache {
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT_IDLE);
+        CoverArtCache {
+            pixbufs: RefCell::new(HashMap::new()),
+            recent: RefCell::new(VecDeque::new()),
+            pending: RefCell::new(HashMap::new()),
+            jobs: RefCell::new(None),
+            sender,
+            receiver: RefCell::new(Some(receiver)),
+        }
+    }
+
+    //The owner attaches the receiver and hands every result back to finish
+    pub fn take_receiver(&self) -> Option<Receiver<DecodedArt>> {
+        self.receiver.borrow_mut().take()
+    }
+
+    //Starts the decoding threads, each reads the art from its own read only connection
+    pub fn start(&self, database_path: PathBuf) {
+        if self.jobs.borrow().is_some() {
+            return;
+        }
+
+        let dir = thumbnail_dir();
+        if let Err(e) = fs::create_dir_all(&dir) {
+            error!("Unable to create thumbnail cache {:?}: {}", dir, e);
+        }
+
+        let (jobs, job_receiver) = mpsc::channel::<ArtKey>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for _ in 0..DECODE_THREADS {
+            let database_path = database_path.clone();
+            let dir = dir.clone();
+            let job_receiver = job_receiver.clone();
+            let sender = self.sender.clone();
+            thread::spawn(move || decode_worker(database_path, dir, job_receiver, sender));
+        }
+
+        self.jobs.replace(Some(jobs));
+    }
+
+    //Calls back with the art at size x size, immediately if it is cached
+    pub fn request(&self, id: i64, size: i32, callback: impl FnOnce(Result<Rc<Pixbuf>, String>) + 'static) {
+        let key = (id, size);
+
+        let cached = self.pixbufs.borrow().get(&key).cloned();
+        if let Some(pixbuf) = cached {
+            self.touch(key);
+            callback(Ok(pixbuf));
+            return;
+        }
+
+        if let Some(callbacks) = self.pending.borrow_mut().get_mut(&key) {
+            callbacks.push(Box::new(callback));
+            return;
+        }
+
+        let queued = match self.jobs.borrow().as_ref() {
+            Some(jobs) => jobs.send(key).is_ok(),
+            None => false,
+        };
+
+        if queued {
+            self.pending.borrow_mut().insert(key, vec![Box::new(callback)]);
+        } else {
+            callback(Err("Cover art cache not started".to_string()));
+        }
+    }
+
+    //Calls back with the palette of the art, computed from a small thumbnail
+    pub fn palette(&self, id: i64, callback: impl FnOnce(Option<Vec<gdk::RGBA>>) + 'static) {
+        self.request(id, PALETTE_SIZE, move |result| {
+            callback(result.ok().and_then(|pixbuf| util::load_palette(&pixbuf)));
+        });
+    }
+
+    pub fn finish(&self, decoded: DecodedArt) {
+        let key = decoded.key;
+        let result = match decoded.result {
+            Ok(raw) => {
+                let pixbuf = Rc::new(raw.into_pixbuf());
+                self.insert(key, pixbuf.clone());
+                Ok(pixbuf)
+            },
+            Err(msg) => {
+                debug!("Unable to load cover art {}: {}", key.0, msg);
+                Err(msg)
+            },
+        };
+
+        let callbacks = self.pending.borrow_mut().remove(&key).unwrap_or_default();
+        for callback in callbacks {
+            callback(result.clone());
+        }
+    }
+
+    //Drops every size of the art, used when a cover art id is reused
+    pub fn forget(&self, id: i64) {
+        self.pixbufs.borrow_mut().retain(|(art_id, _), _| *art_id != id);
+        self.recent.borrow_mut().retain(|(art_id, _)| *art_id != id);
+    }
+
+    pub fn clear(&self) {
+        self.pixbufs.borrow_mut().clear();
+        self.recent.borrow_mut().clear();
+    }
+
+    fn insert(&self, key: ArtKey, pixbuf: Rc<Pixbuf>) {
+        let mut pixbufs = self.pixbufs.borrow_mut();
+        let mut recent = self.recent.borrow_mut();
+
+        if pixbufs.insert(key, pixbuf).is_some() {
+            recent.retain(|k| *k != key);
+        }
+        recent.push_back(key);
+
+        while pixbufs.len() > MEMORY_CACHE_SIZE {
+            match recent.pop_front() {
+                Some(oldest) => _ = pixbufs.remove(&oldest),
+                None => break,
+            }
+        }
+    }
+
+    fn touch(&self, key: ArtKey) {
+        let mut recent = self.recent.borrow_mut();
+        recent.retain(|k| *k != key);
+        recent.push_back(key);
+    }
+}
+
+fn decode_worker(database_path: PathBuf, dir: PathBuf, jobs: Arc<Mutex<mpsc::Receiver<ArtKey>>>, sender: Sender<DecodedArt>) {
+    let conn = Connection::open_with_flags(&database_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string());
+
+    loop {
+        let key = match jobs.lock() {
+            Ok(jobs) => match jobs.recv() {
+                Ok(key) => key,
+                Err(_) => break,
+            },
+            Err(_) => break,
+        };
+
+        let result = match &conn {
+            Ok(conn) => decode(conn, &dir, key),
+            Err(msg) => Err(msg.clone()),
+        };
+
+        send!(sender, DecodedArt { key, result });
+    }
+}
+
+fn decode(conn: &Connection, dir: &Path, key: ArtKey) -> Result<RawPixels, String> {
+    let (id, size) = key;
+    let thumbnail = thumbnail_path(dir, id, size);
+
+    if let Ok(pixbuf) = Pixbuf::from_file(&thumbnail) {
+        return Ok(RawPixels::from_pixbuf(&pixbuf));
+    }
+
+    let data: Vec<u8> = conn
+        .query_row("SELECT data FROM Cover_Art WHERE id = (?);", [id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    let loader = gdk_pixbuf::PixbufLoader::new();
+    loader.write(&data[..]).map_err(|e| e.to_string())?;
+    loader.close().map_err(|e| e.to_string())?;
+
+    let pixbuf = loader.pixbuf().ok_or("Unable to decode cover art")?;
+
+    //the longer side is scaled to the size, keeping the aspect ratio of non-square art
+    let (width, height) = (pixbuf.width(), pixbuf.height());
+    let scaling_factor = size as f64 / width.max(height) as f64;
+    let scaled_width = ((width as f64 * scaling_factor).round() as i32).max(1);
+    let scaled_height = ((height as f64 * scaling_factor).round() as i32).max(1);
+    let pixbuf = pixbuf
+        .scale_simple(scaled_width, scaled_height, gdk_pixbuf::InterpType::Bilinear)
+        .ok_or("Unable to scale cover art")?;
+
+    if let Err(e) = pixbuf.savev(&thumbnail, "png", &[]) {
+        debug!("Unable to save thumbnail {:?}: {}", thumbnail, e);
+    }
+
+    Ok(RawPixels::from_pixbuf(&pixbuf))
+}
+
+pub fn thumbnail_dir() -> PathBuf {
+    if let Some(base_dirs) = BaseDirs::new() {
+        base_dirs.cache_dir().join("io.github.nate_xyz.Resonance").join("thumbnails")
+    } else {
+        PathBuf::from("thumbnails")
+    }
+}
+
+fn thumbnail_path(dir: &Path, id: i64, size: i32) -> PathBuf {
+    dir.join(format!("{}-{}.png", id, size))
+}
+
+//Deletes the thumbnails of a cover art that was removed from the database, its id may be reused
+pub fn remove_thumbnails(id: i64) {
+    let entries = match fs::read_dir(thumbnail_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let prefix = format!("{}-", id);
+    for entry in entries.flatten() {
+        let is_thumbnail = entry.file_name()
+            .to_str()
+            .map(|name| name.starts_with(&prefix))
+            .unwrap_or(false);
+
+        if is_thumbnail {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                error!("Unable to remove thumbnail {:?}: {}", entry.path(), e);
+            }
+        }
+    }
+}
@@ -8,9 +8,9 @@ use adw::subclass::prelude::*;
 use gtk::glib;
 
 use std::{cell::Cell, cell::RefCell, rc::Rc};
-use regex::Regex;
 
 use super::album::Album;
+use crate::util;
 
 mod imp {
     use super::*;
@@ -55,12 +55,14 @@ impl Genre {
 
         imp.id.replace(id);
 
-        let re = Regex::new(r"^(the|a|an)\s+").unwrap();
-        let lowercase_name = name.to_lowercase();
-        let stripped_name: std::borrow::Cow<str> = re.replace(&lowercase_name, "");
-        imp.sort_name.replace(format!("{}", stripped_name));
-
         imp.name.replace(name);
+        self.update_sort_keys();
+    }
+
+    pub fn update_sort_keys(&self) {
+        let imp = self.imp();
+        let sort_name = util::sort_name(&imp.name.borrow());
+        imp.sort_name.replace(sort_name);
     }
     
     pub fn id(&self) -> i64 {
@@ -84,12 +86,20 @@ impl Genre {
         } 
 
         if let Some(albums)  = imp.albums.borrow_mut().as_mut() {
-            albums.push(album);
+            if !albums.iter().any(|a| a.id() == album.id()) {
+                albums.push(album);
+            }
             return;
         }
 
     }
 
+    pub fn remove_album(&self, album_id: i64) {
+        if let Some(albums) = self.imp().albums.borrow_mut().as_mut() {
+            albums.retain(|album| album.id() != album_id);
+        }
+    }
+
     pub fn albums(&self) -> Option<Vec<Rc<Album>>> {
         if let Some(albums) = self.imp().albums.borrow().as_ref() {
             Some(albums.clone())
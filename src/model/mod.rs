@@ -4,6 +4,7 @@ pub mod artist;
 pub mod genre;
 pub mod track;
 pub mod cover_art;
+pub mod cover_art_cache;
 pub mod cover_art_pixbuf_loader;
 pub mod playlist;
 pub mod playlist_entry;
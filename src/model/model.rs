@@ -5,21 +5,25 @@
  */
 
 use adw::subclass::prelude::*;
-use gtk::{gio, glib, prelude::*};
+use gtk::{gio, glib, glib::clone, prelude::*};
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::fmt;
 use std::error::Error;
 use log::{debug, error};
 
 use crate::database::Database;
+use crate::duplicates::{self, DuplicateGroup, FormatPolicy};
+use crate::rating::Rating;
+use crate::smart_playlist::{AutoPlaylist, SmartRules, SmartSort, RECENT_ALBUMS_LIMIT, RECENT_TRACKS_LIMIT};
 use crate::util;
 
 use super::album::Album;
 use super::artist::Artist;
 use super::cover_art::CoverArt;
+use super::cover_art_cache::CoverArtCache;
 use super::genre::Genre;
 use super::track::Track;
 use super::playlist::Playlist;
@@ -47,8 +51,11 @@ pub enum ModelAction {
     PopulateAlbum(u64),
     PopulateTrack(u64),
     PopulatePlaylist(u64),
+    PopulateSmartPlaylists,
     PopulateArtist(u64),
     PopulateGenre(u64),
+    UpdateTracks(Vec<u64>),
+    RemoveTracks(Vec<u64>),
 }
 
 mod imp {
@@ -61,12 +68,15 @@ mod imp {
         pub settings: gio::Settings,
         pub database: RefCell<Option<Rc<Database>>>,
         pub art: RefCell<Option<HashMap<i64, Rc<CoverArt>>>>,
+        pub cover_art_cache: CoverArtCache,
         pub genres: RefCell<Option<HashMap<i64, Rc<Genre>>>>,
         pub artists: RefCell<Option<HashMap<i64, Rc<Artist>>>>,
         pub albums: RefCell<Option<HashMap<i64, Rc<Album>>>>,
         pub tracks: RefCell<Option<HashMap<i64, Rc<Track>>>>,
         pub playlists: RefCell<Option<HashMap<i64, Rc<Playlist>>>>,
         pub artist_images: RefCell<Option<HashMap<i64, Rc<ArtistImage>>>>,
+        pub duplicates: RefCell<Vec<DuplicateGroup>>,
+        pub hidden_tracks: RefCell<HashSet<i64>>,
     }
     
     #[glib::object_subclass]
@@ -80,12 +90,15 @@ mod imp {
                 settings: util::settings_manager(),
                 database: RefCell::new(None),
                 art: RefCell::new(None),
+                cover_art_cache: CoverArtCache::new(),
                 genres: RefCell::new(None),
                 artists: RefCell::new(None),
                 albums: RefCell::new(None),
                 tracks: RefCell::new(None),
                 playlists: RefCell::new(None),
                 artist_images: RefCell::new(None),
+                duplicates: RefCell::new(Vec::new()),
+                hidden_tracks: RefCell::new(HashSet::new()),
             }
         }
 
@@ -94,6 +107,9 @@ mod imp {
     impl ObjectImpl for ModelPriv {
         fn constructed(&self) {
             self.parent_constructed();
+            self.obj().setup_cover_art_cache();
+            self.obj().setup_settings();
+            self.obj().setup_smart_playlists();
         }
 
         fn signals() -> &'static [Signal] {
@@ -106,6 +122,8 @@ mod imp {
                     Signal::builder("refresh-tracks").build(),
                     Signal::builder("refresh-playlists").build(),
                     Signal::builder("refresh-plays").build(),
+                    Signal::builder("refresh-ratings").build(),
+                    Signal::builder("refresh-smart-playlists").build(),
                     Signal::builder("refresh-playlist").param_types([<u64>::static_type()]).build(),
                 ]
             });
@@ -146,8 +164,33 @@ impl Model {
         imp.tracks.replace(Some(HashMap::new()));
         imp.playlists.replace(Some(HashMap::new()));
         imp.artist_images.replace(Some(HashMap::new()));
+        imp.duplicates.replace(Vec::new());
+        imp.hidden_tracks.replace(HashSet::new());
     }
 
+    fn setup_settings(&self) {
+        self.imp().settings.connect_changed(
+            Some("duplicate-policy"),
+            clone!(@weak self as this => move |_settings, _name| {
+                if this.imp().tracks.borrow().is_some() {
+                    this.populate_all();
+                }
+            }),
+        );
+    }
+
+
+    //Plays & ratings change without a library refresh, the rules depending on them are evaluated again
+    fn setup_smart_playlists(&self) {
+        for signal in ["refresh-plays", "refresh-ratings"] {
+            self.connect_local(signal, false,
+                clone!(@weak self as this => @default-return None, move |_args| {
+                    this.refresh_smart_playlists();
+                    None
+                })
+            );
+        }
+    }
 
     fn populate(&self) -> Result<(), Box<dyn Error>> {
         match self.populate_art() {
@@ -169,6 +212,7 @@ impl Model {
             Ok(_) => (),
             Err(e) => error!("Unable to populate tracks: {}", e),
         }
+        self.hide_duplicates();
         
         self.emit_by_name::<()>("refresh-tracks", &[]);
 
@@ -190,6 +234,11 @@ impl Model {
             Ok(_) => (),
             Err(e) => error!("Unable to populate playlists: {}", e),
         }
+        match self.populate_smart_playlists() {
+            Ok(_) => (),
+            Err(e) => error!("Unable to populate smart playlists: {}", e),
+        }
+        self.populate_auto_playlists();
         self.emit_by_name::<()>("refresh-playlists", &[]);
 
         Ok(())
@@ -200,14 +249,16 @@ impl Model {
         let imp = self.imp();
         
         debug!("populate art");
-        let list = self.database().query_art()?;
+        //ids may have been reused since the art was decoded
+        imp.cover_art_cache.clear();
+        let list = self.database().query_art_ids()?;
         if list.is_empty() {
             imp.art.replace(Some(HashMap::new()));
             return Err(Box::new(ModelError("Art Query Empty".into())));
         }
         let mut art_map = HashMap::new();
-        for (id, data) in list {
-            let art = Rc::new(CoverArt::new(id, data));
+        for id in list {
+            let art = Rc::new(CoverArt::new(id));
             art_map.insert(id, art);
         }
         imp.art.replace(Some(art_map));
@@ -271,7 +322,7 @@ impl Model {
             return Err(Box::new(ModelError("Artists Query Empty".into())));
         }
         let mut artist_map = HashMap::new();
-        for (id, name, image_optional, albums_optional) in list {
+        for (id, name, image_optional, albums_optional, appearances) in list {
             let artist = Rc::new(Artist::new(name, id, image_optional));
 
             if let Some(albums) = albums_optional {
@@ -283,6 +334,12 @@ impl Model {
                 }
             }
 
+            for album_id in appearances {
+                if let Ok(album) = self.album(album_id) {
+                    artist.add_appearance(album);
+                }
+            }
+
             artist_map.insert(id, artist);
         }
         
@@ -300,11 +357,14 @@ impl Model {
             imp.albums.replace(Some(HashMap::new()));
             return Err(Box::new(ModelError("Album Query Empty".into())));
         }
+        let ratings = self.database().query_album_ratings()?;
         let mut album_map = HashMap::new();
-        for (id, title, album_artist, date, genre, cover_art_option, artist_id) in list
+        for (id, title, album_artist, date, genre, cover_art_option, artist_id, compilation) in list
         {
             let album = Rc::new(Album::new(id, title, album_artist, artist_id, date, genre));
             album.add_cover_art_id(cover_art_option);
+            album.set_compilation(compilation);
+            album.set_rating(ratings.get(&album.album_key()).copied().unwrap_or_default());
             album_map.insert(id, album);
         }
         imp.albums.replace(Some(album_map));
@@ -321,6 +381,8 @@ impl Model {
             imp.tracks.replace(Some(HashMap::new()));
             return Err(Box::new(ModelError("Track Query Empty".into())));
         }
+        let mut play_stats = self.database().query_play_stats(None)?;
+        let ratings = self.database().query_track_ratings()?;
         let mut track_map = HashMap::new();
         for (
             id,
@@ -336,6 +398,11 @@ impl Model {
             artist_id,
             album_id,
             cover_art_option,
+            artist,
+            artist_ids,
+            genre,
+            genre_ids,
+            tags,
         ) in list
         {
             let album = self.album(album_id)?;
@@ -347,13 +414,18 @@ impl Model {
                 filetype,
                 uri,
                 date,
-                album.genre(),
+                if genre.is_empty() { album.genre() } else { genre },
                 duration,
                 track_number,
                 disc_number,
             ));
             track.add_artist_id(artist_id);
+            track.add_artists(artist, artist_ids);
+            track.add_genre_ids(genre_ids);
+            track.add_tags(tags);
             track.add_album_id(album_id);
+            track.set_play_stats(play_stats.remove(&id).unwrap_or_default());
+            track.set_rating(ratings.get(&track.uri()).copied().unwrap_or_default());
 
             if cover_art_option.is_none() {
                 track.add_cover_art_option(album.cover_art_option());
@@ -445,6 +517,117 @@ impl Model {
 
 
 
+    fn populate_smart_playlists(&self) -> Result<(), Box<dyn Error>> {
+        debug!("populate smart playlists");
+        let list = self.database().query_smart_playlists()?;
+
+        let mut smart_playlists = Vec::new();
+        for (smart_id, title, description, rules, creation_time, modify_time) in list {
+            let rules = match SmartRules::from_json(&rules) {
+                Ok(rules) => rules,
+                Err(e) => {
+                    error!("Unable to read the rules of smart playlist {}: {}", smart_id, e);
+                    continue;
+                },
+            };
+            let playlist = Rc::new(Playlist::new_smart(smart_id, title, description, creation_time, modify_time, rules.clone()));
+            playlist.set_tracks(rules.evaluate(self.smart_playlist_candidates()));
+            smart_playlists.push(playlist);
+        }
+
+        let mut playlists = self.imp().playlists.borrow_mut();
+        let playlists = playlists.get_or_insert_with(HashMap::new);
+        playlists.retain(|_id, playlist| !playlist.is_smart());
+        for playlist in smart_playlists {
+            playlists.insert(playlist.id(), playlist);
+        }
+        Ok(())
+    }
+
+    //Collections are kept in the playlist map next to the smart playlists, added again whenever the map is rebuilt
+    fn populate_auto_playlists(&self) {
+        debug!("populate collections");
+        let collections: Vec<Rc<Playlist>> = {
+            let mut playlists = self.imp().playlists.borrow_mut();
+            let playlists = playlists.get_or_insert_with(HashMap::new);
+            AutoPlaylist::all().into_iter()
+                .map(|kind| playlists.entry(kind.id()).or_insert_with(|| Rc::new(Playlist::new_auto(kind))).clone())
+                .collect()
+        };
+
+        for playlist in collections.iter() {
+            self.refresh_auto_playlist(playlist);
+        }
+    }
+
+    fn refresh_auto_playlist(&self, playlist: &Playlist) {
+        let kind = match playlist.auto_playlist() {
+            Some(kind) => kind,
+            None => return,
+        };
+
+        match self.auto_playlist_tracks(kind) {
+            Ok(tracks) => playlist.set_tracks(tracks),
+            Err(e) => error!("Unable to refresh collection {:?}: {}", kind, e),
+        }
+    }
+
+    fn auto_playlist_tracks(&self, kind: AutoPlaylist) -> Result<Vec<Rc<Track>>, Box<dyn Error>> {
+        let tracks = match kind {
+            AutoPlaylist::RecentlyAdded => {
+                let mut tracks = Vec::new();
+                for album_id in self.database().query_most_recent_albums(RECENT_ALBUMS_LIMIT)? {
+                    if let Ok(album) = self.album(album_id) {
+                        tracks.extend(album.tracks());
+                    }
+                }
+                tracks
+            },
+            AutoPlaylist::RecentlyPlayed => {
+                self.database().query_most_recent_tracks(RECENT_TRACKS_LIMIT)?
+                    .into_iter()
+                    .filter_map(|track_id| self.track(track_id).ok())
+                    .collect()
+            },
+            AutoPlaylist::NeverPlayed => {
+                let never_played = self.smart_playlist_candidates().into_iter()
+                    .filter(|track| track.play_count() == 0)
+                    .collect();
+                SmartRules { sort: SmartSort::Album, ..SmartRules::default() }.evaluate(never_played)
+            },
+        };
+        Ok(tracks)
+    }
+
+    //Evaluates the rules & collections again in place, so the pages holding the playlists stay valid
+    fn refresh_smart_playlists(&self) {
+        let virtual_playlists: Vec<Rc<Playlist>> = match self.imp().playlists.borrow().as_ref() {
+            Some(map) => map.values().filter(|playlist| playlist.is_virtual()).cloned().collect(),
+            None => return,
+        };
+        if virtual_playlists.is_empty() {
+            return;
+        }
+
+        for playlist in virtual_playlists.iter() {
+            if let Some(rules) = playlist.smart_rules() {
+                playlist.set_tracks(rules.evaluate(self.smart_playlist_candidates()));
+            } else {
+                self.refresh_auto_playlist(playlist);
+            }
+        }
+        self.emit_by_name::<()>("refresh-smart-playlists", &[]);
+    }
+
+    //Library tracks a smart playlist can hold, without the copies hidden by the format policies
+    fn smart_playlist_candidates(&self) -> Vec<Rc<Track>> {
+        let hidden = self.imp().hidden_tracks.borrow();
+        match self.imp().tracks.borrow().as_ref() {
+            Some(map) => map.values().filter(|track| !hidden.contains(&track.id())).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
     // fn populate_artists_by_id(&self, id: u64) -> Result<(), Box<dyn Error>> {
     //     debug!("populate artist {}", id);
     //     let (artist_id, name) = self.database().query_artist_by_id(id)?;
@@ -463,11 +646,12 @@ impl Model {
     // }
     fn populate_albums_by_id(&self, id: u64) -> Result<(), Box<dyn Error>> {
         debug!("populate album {}", id);
-        let (album_id, title, album_artist, date, genre, cover_art_option, artist_id, genre_option) = self.database().query_album_by_id(id)?;
+        let (album_id, title, album_artist, date, genre, cover_art_option, artist_id, genre_ids, compilation) = self.database().query_album_by_id(id)?;
         let album = Rc::new(Album::new(album_id, title, album_artist, artist_id, date, genre));
         album.add_cover_art_id(cover_art_option);
-        if !genre_option.is_none() {
-            let genre_id = genre_option.unwrap();
+        album.set_compilation(compilation);
+        album.set_rating(self.database().query_album_ratings()?.remove(&album.album_key()).unwrap_or_default());
+        for genre_id in genre_ids {
             if let Ok(genre) = self.genre(genre_id) {
                 album.add_genre(genre_id);
                 genre.add_album(album.clone());
@@ -489,6 +673,334 @@ impl Model {
         Ok(())
     }
 
+    // TARGETED UPDATES, used after a rescan or a folder change instead of repopulating everything
+
+    fn update_tracks(&self, ids: Vec<u64>) -> Result<(), Box<dyn Error>> {
+        debug!("update tracks {:?}", ids);
+        let database = self.database();
+        let ratings = database.query_track_ratings()?;
+
+        for id in ids {
+            let (
+                id,
+                title,
+                filetype,
+                album_name,
+                date,
+                duration,
+                track_number,
+                disc_number,
+                album_artist,
+                uri,
+                artist_id,
+                album_id,
+                cover_art_option,
+                artist,
+                artist_ids,
+                genre,
+                genre_ids,
+                tags,
+            ) = database.query_track_by_id(id)?;
+
+            //the existing object is reused so queues & playlists holding it stay valid
+            let track = match self.track(id) {
+                Ok(track) => {
+                    self.detach_track(&track);
+                    track
+                },
+                Err(_) => Rc::new(Track::default()),
+            };
+
+            let album = match self.album(album_id) {
+                Ok(album) => album,
+                Err(_) => self.add_album_by_id(album_id)?,
+            };
+
+            track.load_info(
+                id,
+                title,
+                album_name,
+                album_artist,
+                filetype,
+                uri,
+                date,
+                if genre.is_empty() { album.genre() } else { genre },
+                duration,
+                track_number,
+                disc_number,
+            );
+            track.add_artist_id(artist_id);
+            track.add_artists(artist, artist_ids);
+            track.add_genre_ids(genre_ids);
+            track.add_tags(tags);
+            track.add_album_id(album_id);
+            track.set_play_stats(database.query_play_stats(Some(id))?.remove(&id).unwrap_or_default());
+            track.set_rating(ratings.get(&track.uri()).copied().unwrap_or_default());
+
+            //genres of the track may be new to the library or to the album
+            for genre_id in track.genre_ids() {
+                let genre = match self.genre(genre_id) {
+                    Ok(genre) => genre,
+                    Err(_) => {
+                        let (genre_id, name) = database.query_genre_by_id(genre_id as u64)?;
+                        let genre = Rc::new(Genre::new(name, genre_id));
+                        self.imp().genres.borrow_mut().as_mut().ok_or("Genres not populated")?.insert(genre_id, genre.clone());
+                        genre
+                    },
+                };
+                album.add_genre(genre_id);
+                genre.add_album(album.clone());
+            }
+
+            //featured artists may be new to the library
+            for artist_id in track.artist_ids() {
+                let artist = match self.artist(artist_id) {
+                    Ok(artist) => artist,
+                    Err(_) => {
+                        let (artist_id, name) = database.query_artist_by_id(artist_id as u64)?;
+                        let artist = Rc::new(Artist::new(name, artist_id, None));
+                        self.imp().artists.borrow_mut().as_mut().ok_or("Artists not populated")?.insert(artist_id, artist.clone());
+                        artist
+                    },
+                };
+
+                if !album.artist_ids().contains(&artist_id) {
+                    artist.add_appearance(album.clone());
+                }
+            }
+
+            if cover_art_option.is_none() {
+                track.add_cover_art_option(album.cover_art_option());
+            } else {
+                self.add_art_by_id(cover_art_option.unwrap())?;
+                track.add_cover_art_option(cover_art_option);
+            }
+
+            album.add_track(track.clone());
+            for artist_id in album.artist_ids().iter().chain(track.artist_ids().iter()) {
+                if let Ok(artist) = self.artist(*artist_id) {
+                    artist.add_sort_tag_from(&album);
+                }
+            }
+            self.imp().tracks.borrow_mut().as_mut().ok_or("Tracks not populated")?.insert(id, track);
+        }
+
+        Ok(())
+    }
+
+    fn remove_tracks(&self, ids: Vec<u64>) {
+        debug!("remove tracks {:?}", ids);
+        for id in ids {
+            let removed = self.imp().tracks.borrow_mut().as_mut().and_then(|tracks| tracks.remove(&(id as i64)));
+            if let Some(track) = removed {
+                self.detach_track(&track);
+            }
+        }
+    }
+
+    //Removes a track from its album, dropping the album, artist & genre once they are empty
+    fn detach_track(&self, track: &Track) {
+        let imp = self.imp();
+
+        let album = match self.album(track.album_id()) {
+            Ok(album) => album,
+            Err(_) => return,
+        };
+
+        album.remove_track(track);
+
+        //drop appearances no other track of the album credits anymore
+        let remaining = album.tracks();
+        for artist_id in track.artist_ids() {
+            if album.artist_ids().contains(&artist_id) || remaining.iter().any(|t| t.artist_ids().contains(&artist_id)) {
+                continue;
+            }
+            if let Ok(artist) = self.artist(artist_id) {
+                artist.remove_appearance(album.id());
+                self.remove_artist_if_empty(&artist);
+            }
+        }
+
+        //same for genres
+        for genre_id in track.genre_ids() {
+            if remaining.iter().any(|t| t.genre_ids().contains(&genre_id)) {
+                continue;
+            }
+            album.remove_genre(genre_id);
+            if let Ok(genre) = self.genre(genre_id) {
+                genre.remove_album(album.id());
+                self.remove_genre_if_empty(&genre);
+            }
+        }
+
+        if album.n_tracks() > 0 {
+            return;
+        }
+
+        if let Some(albums) = imp.albums.borrow_mut().as_mut() {
+            albums.remove(&album.id());
+        }
+
+        for artist_id in album.artist_ids() {
+            if let Ok(artist) = self.artist(artist_id) {
+                artist.remove_album(album.id());
+                self.remove_artist_if_empty(&artist);
+            }
+        }
+
+        for genre_id in album.genre_ids() {
+            if let Ok(genre) = self.genre(genre_id) {
+                genre.remove_album(album.id());
+                self.remove_genre_if_empty(&genre);
+            }
+        }
+    }
+
+    //Detaches the copies hidden by the format policies from their albums, they stay in the track map for playlists.
+    //Returns true when a hidden copy is shown again & the model needs repopulating.
+    fn hide_duplicates(&self) -> bool {
+        let imp = self.imp();
+        let groups = match imp.tracks.borrow().as_ref() {
+            Some(tracks) => duplicates::find_duplicates(tracks),
+            None => Vec::new(),
+        };
+
+        let policy = FormatPolicy::new_from_settings(imp.settings.int("duplicate-policy"));
+        let album_policies = match self.database().query_album_format_policies() {
+            Ok(album_policies) => album_policies,
+            Err(e) => {
+                error!("Unable to query album format policies: {}", e);
+                HashMap::new()
+            },
+        };
+        let hidden = duplicates::hidden_tracks(&groups, policy, &album_policies);
+
+        let shown_again = imp.hidden_tracks.borrow().iter().any(|id| !hidden.contains(id) && self.track(*id).is_ok());
+
+        for group in groups.iter() {
+            for track in group.tracks.iter() {
+                if hidden.contains(&track.id()) {
+                    self.detach_track(track);
+                } else if let Ok(album) = self.album(track.album_id()) {
+                    //copies in the same album share a disc & track number, the kept one takes the slot back
+                    album.remove_track(track);
+                    album.add_track(track.clone());
+                }
+            }
+        }
+
+        debug!("{} duplicated tracks, {} hidden", groups.len(), hidden.len());
+        imp.duplicates.replace(groups);
+        imp.hidden_tracks.replace(hidden);
+        shown_again
+    }
+
+    //Stores the rating by file path & updates the loaded track, the views follow "refresh-ratings"
+    pub fn set_track_rating(&self, track_id: i64, rating: Rating) -> Result<(), Box<dyn Error>> {
+        let track = self.track(track_id)?;
+        self.database().set_track_rating(&track.uri(), rating)?;
+        track.set_rating(rating);
+        self.emit_by_name::<()>("refresh-ratings", &[]);
+        Ok(())
+    }
+
+    pub fn set_album_rating(&self, album_id: i64, rating: Rating) -> Result<(), Box<dyn Error>> {
+        let album = self.album(album_id)?;
+        self.database().set_album_rating(&album.album_key(), rating)?;
+        album.set_rating(rating);
+        self.emit_by_name::<()>("refresh-ratings", &[]);
+        Ok(())
+    }
+
+    pub fn duplicates(&self) -> Vec<DuplicateGroup> {
+        self.imp().duplicates.borrow().clone()
+    }
+
+    //Copy of a duplicated track hidden by the format policies
+    pub fn is_hidden(&self, track_id: i64) -> bool {
+        self.imp().hidden_tracks.borrow().contains(&track_id)
+    }
+
+    fn remove_artist_if_empty(&self, artist: &Artist) {
+        if artist.n_albums() == 0 && artist.appears_on().is_empty() {
+            if let Some(artists) = self.imp().artists.borrow_mut().as_mut() {
+                artists.remove(&artist.id());
+            }
+        }
+    }
+
+    fn remove_genre_if_empty(&self, genre: &Genre) {
+        if genre.n_albums() == 0 {
+            if let Some(genres) = self.imp().genres.borrow_mut().as_mut() {
+                genres.remove(&genre.id());
+            }
+        }
+    }
+
+    fn add_album_by_id(&self, id: i64) -> Result<Rc<Album>, Box<dyn Error>> {
+        let database = self.database();
+        let (_, _, _, _, _, cover_art_option, artist_id, genre_ids, _) = database.query_album_by_id(id as u64)?;
+
+        if self.artist(artist_id).is_err() {
+            let (artist_id, name) = database.query_artist_by_id(artist_id as u64)?;
+            let artist = Rc::new(Artist::new(name, artist_id, None));
+            self.imp().artists.borrow_mut().as_mut().ok_or("Artists not populated")?.insert(artist_id, artist);
+        }
+
+        for genre_id in genre_ids {
+            if self.genre(genre_id).is_err() {
+                let (genre_id, name) = database.query_genre_by_id(genre_id as u64)?;
+                let genre = Rc::new(Genre::new(name, genre_id));
+                self.imp().genres.borrow_mut().as_mut().ok_or("Genres not populated")?.insert(genre_id, genre);
+            }
+        }
+
+        if let Some(cover_art_id) = cover_art_option {
+            self.add_art_by_id(cover_art_id)?;
+        }
+
+        self.populate_albums_by_id(id as u64)?;
+        Ok(self.album(id)?)
+    }
+
+    fn add_art_by_id(&self, id: i64) -> Result<(), Box<dyn Error>> {
+        if self.cover_art(id).is_ok() {
+            return Ok(());
+        }
+
+        let id = self.database().query_art_by_id(id as u64)?;
+        self.imp().cover_art_cache.forget(id);
+        let art = Rc::new(CoverArt::new(id));
+        self.imp().art.borrow_mut().as_mut().ok_or("Art not populated")?.insert(id, art);
+        Ok(())
+    }
+
+    //Recomputes every sort name, after the sort articles changed
+    pub fn update_sort_keys(&self) {
+        let imp = self.imp();
+        if let Some(tracks) = imp.tracks.borrow().as_ref() {
+            tracks.values().for_each(|track| track.update_sort_keys());
+        }
+        if let Some(albums) = imp.albums.borrow().as_ref() {
+            albums.values().for_each(|album| album.update_sort_keys());
+        }
+        if let Some(artists) = imp.artists.borrow().as_ref() {
+            artists.values().for_each(|artist| artist.update_sort_keys());
+        }
+        if let Some(genres) = imp.genres.borrow().as_ref() {
+            genres.values().for_each(|genre| genre.update_sort_keys());
+        }
+        self.emit_library_refresh();
+    }
+
+    fn emit_library_refresh(&self) {
+        self.emit_by_name::<()>("refresh-tracks", &[]);
+        self.emit_by_name::<()>("refresh-albums", &[]);
+        self.emit_by_name::<()>("refresh-artists", &[]);
+        self.emit_by_name::<()>("refresh-genres", &[]);
+        self.refresh_smart_playlists();
+    }
+
     pub fn cover_art(&self, id: i64) -> Result<Rc<CoverArt>, String> {
         match self.imp().art.borrow().as_ref() {
             Some(map) => match map.get(&id) {
@@ -499,6 +1011,22 @@ impl Model {
         }
     }
 
+    pub fn cover_art_cache(&self) -> &CoverArtCache {
+        &self.imp().cover_art_cache
+    }
+
+    fn setup_cover_art_cache(&self) {
+        if let Some(receiver) = self.imp().cover_art_cache.take_receiver() {
+            receiver.attach(
+                None,
+                clone!(@weak self as this => @default-return glib::Continue(false), move |decoded| {
+                    this.imp().cover_art_cache.finish(decoded);
+                    glib::Continue(true)
+                }),
+            );
+        }
+    }
+
     pub fn artist_image(&self, id: i64) -> Result<Rc<ArtistImage>, String> {
         match self.imp().artist_images.borrow().as_ref() {
             Some(map) => match map.get(&id) {
@@ -584,6 +1112,7 @@ impl Model {
     }
 
     pub fn load_database(&self, database: Rc<Database>) {
+        self.imp().cover_art_cache.start(database.database_location());
         self.imp().database.replace(Some(database));
     }
 
@@ -619,8 +1148,22 @@ impl Model {
                     Ok(_) => (),
                     Err(e) => error!("Unable to populate playlists: {}", e),
                 }
+                match self.populate_smart_playlists() {
+                    Ok(_) => (),
+                    Err(e) => error!("Unable to populate smart playlists: {}", e),
+                }
+                self.populate_auto_playlists();
                 self.emit_by_name::<()>("refresh-playlists", &[]);
             },
+            ModelAction::PopulateSmartPlaylists => {
+                match self.populate_smart_playlists() {
+                    Ok(_) => (),
+                    Err(e) => error!("Unable to populate smart playlists: {}", e),
+                }
+                self.populate_auto_playlists();
+                self.emit_by_name::<()>("refresh-playlists", &[]);
+                self.emit_by_name::<()>("refresh-smart-playlists", &[]);
+            },
             ModelAction::PopulatePlaylist(id) => {
                 match self.populate_playlist_by_id(id) {
                     Ok(_) => (),
@@ -646,6 +1189,33 @@ impl Model {
                 }
                 self.emit_by_name::<()>("refresh-artists", &[]);
             },
+            ModelAction::UpdateTracks(ids) => {
+                if self.imp().tracks.borrow().is_none() {
+                    self.populate_all();
+                } else {
+                    match self.update_tracks(ids) {
+                        Ok(_) => {
+                            if self.hide_duplicates() {
+                                self.populate_all();
+                            } else {
+                                self.emit_library_refresh();
+                            }
+                        },
+                        Err(e) => {
+                            error!("Unable to update tracks, repopulating: {}", e);
+                            self.populate_all();
+                        },
+                    }
+                }
+            },
+            ModelAction::RemoveTracks(ids) => {
+                self.remove_tracks(ids);
+                if self.hide_duplicates() {
+                    self.populate_all();
+                } else {
+                    self.emit_library_refresh();
+                }
+            },
             _ => debug!("Received action {:?}", action),
         }
         glib::Continue(true)
@@ -13,6 +13,7 @@ use std::{cell::Cell, cell::RefCell, rc::Rc};
 
 use super::track::Track;
 use super::playlist_entry::PlaylistEntry;
+use crate::smart_playlist::{AutoPlaylist, SmartRules};
 
 use chrono::{DateTime, Utc};
 
@@ -29,6 +30,8 @@ mod imp {
         pub cover_art_ids: RefCell<HashSet<i64>>,
         pub entries: RefCell<HashMap<i64, Rc<PlaylistEntry>>>,
         pub total_duration: Cell<f64>,
+        pub smart_rules: RefCell<Option<SmartRules>>,
+        pub auto_playlist: Cell<Option<AutoPlaylist>>,
         pub menu: gio::Menu,
     }
 
@@ -71,6 +74,62 @@ impl Playlist {
         //self.create_menu();
     }
 
+    //Smart playlists share the playlist map keyed on their negated id, their tracks come from the rules
+    pub fn new_smart(smart_id: i64, title: String, description: String, creation_time: DateTime<Utc>, modify_time: DateTime<Utc>, rules: SmartRules) -> Playlist {
+        let playlist = Self::new(-smart_id, title, description, creation_time, modify_time);
+        playlist.imp().smart_rules.replace(Some(rules));
+        playlist
+    }
+
+    pub fn is_smart(&self) -> bool {
+        self.imp().smart_rules.borrow().is_some()
+    }
+
+    //Id of the Smart_Playlists row
+    pub fn smart_id(&self) -> Option<i64> {
+        if self.is_smart() {
+            Some(-self.id())
+        } else {
+            None
+        }
+    }
+
+    pub fn smart_rules(&self) -> Option<SmartRules> {
+        self.imp().smart_rules.borrow().clone()
+    }
+
+    //Collections are created by the model, their tracks are refreshed as the library & plays change
+    pub fn new_auto(kind: AutoPlaylist) -> Playlist {
+        let now = chrono::offset::Utc::now();
+        let playlist = Self::new(kind.id(), kind.title(), kind.description(), now, now);
+        playlist.imp().auto_playlist.set(Some(kind));
+        playlist
+    }
+
+    pub fn auto_playlist(&self) -> Option<AutoPlaylist> {
+        self.imp().auto_playlist.get()
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.auto_playlist().is_some()
+    }
+
+    //Smart playlists & collections, their tracks are not stored in the database
+    pub fn is_virtual(&self) -> bool {
+        self.is_smart() || self.is_auto()
+    }
+
+    //Replaces the tracks of a smart playlist or collection
+    pub fn set_tracks(&self, tracks: Vec<Rc<Track>>) {
+        let imp = self.imp();
+        imp.entries.borrow_mut().clear();
+        imp.cover_art_ids.borrow_mut().clear();
+        imp.total_duration.set(0.0);
+        for (position, track) in tracks.into_iter().enumerate() {
+            self.add_track(position as i64, position as i64, track);
+        }
+    }
+
     pub fn id(&self) -> i64 {
         self.imp().id.get().clone()
     }
@@ -95,6 +154,14 @@ impl Playlist {
         self.imp().total_duration.get().clone()
     }
 
+    pub fn play_count(&self) -> u64 {
+        self.imp().entries.borrow().values().map(|entry| entry.track().play_count()).sum()
+    }
+
+    pub fn last_played(&self) -> Option<i64> {
+        self.imp().entries.borrow().values().filter_map(|entry| entry.track().last_played()).max()
+    }
+
     pub fn n_tracks(&self) -> usize {
         self.imp().entries.borrow().len()
     }
@@ -116,6 +183,10 @@ impl Playlist {
     }
 
     pub fn tracks(&self) -> Vec<Rc<Track>> {
+        //smart playlists can match nothing
+        if self.n_tracks() == 0 {
+            return Vec::new();
+        }
         let mut v = vec![self.track(0).unwrap(); self.n_tracks()];
         for playlist_entry in self.entry_map().values() {
             let track = playlist_entry.track();
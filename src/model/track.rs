@@ -8,8 +8,15 @@ use adw::subclass::prelude::*;
 use gtk::{glib, gio};
 use glib::prelude::ToVariant;
 
-use std::{cell::Cell, cell::RefCell};
-use regex::Regex;
+use std::{cell::Cell, cell::RefCell, collections::HashMap};
+
+use crate::util;
+use crate::statistics::PlayStats;
+use crate::rating::{self, Rating};
+use crate::i18n::i18n;
+
+//Extended tags matched by the full search
+const SEARCHABLE_TAGS: [&str; 5] = ["composer", "conductor", "remixer", "lyricist", "label"];
 
 mod imp {
     use super::*;
@@ -20,6 +27,7 @@ mod imp {
         pub title: RefCell<String>,
         pub album_name: RefCell<String>,
         pub album_artist: RefCell<String>,
+        pub artist: RefCell<String>,
         pub search_string: RefCell<String>,
         pub sort_string: RefCell<String>,
         pub sort_title: RefCell<String>,
@@ -34,11 +42,15 @@ mod imp {
         pub disc_number: Cell<i64>,
         // artist_parent: RefCell<Option<Artist>>,
         // album_parent: RefCell<Option<Album>>,
-        pub genre_id: Cell<Option<i64>>,
+        pub genre_ids: RefCell<Vec<i64>>,
+        pub tags: RefCell<HashMap<String, String>>,
         pub album_id: Cell<i64>,
         pub artist_id: Cell<i64>,
+        pub artist_ids: RefCell<Vec<i64>>,
         // img_data: RefCell<Option<Vec<u8>>>,
         pub cover_art_id: Cell<Option<i64>>,
+        pub play_stats: Cell<PlayStats>,
+        pub rating: Cell<Rating>,
         pub menu: gio::Menu,
     }
 
@@ -77,23 +89,7 @@ impl Track {
 
     pub fn load_info(&self, id: i64, title: String, album_name: String, album_artist: String, filetype: String, uri: String, date: String, genre: String, duration: f64, track_number: i64, disc_number: i64) {
         let imp = self.imp();
-        
-        let re = Regex::new(r"^(the|a|an)\s+").unwrap();
-        
-        let lowercase_title = title.to_lowercase();
-        let stripped_title = re.replace(&lowercase_title, "");
-
-        let lowercase_artist= album_artist.to_lowercase();
-        let stripped_artist = re.replace(&lowercase_artist, "");
-
-        let lowercase_album= album_name.to_lowercase();
-        let stripped_album = re.replace(&lowercase_album, "");
-        
-        imp.sort_title.replace(format!("{}", stripped_title));
-        imp.sort_artist.replace(format!("{}", stripped_artist));
-        imp.sort_album.replace(format!("{}", stripped_album));
 
-        imp.sort_string.replace(format!("{} {} {}", stripped_title, stripped_album, stripped_artist));
         imp.search_string.replace(format!("{} {} {}", title, album_name, album_artist));
 
         imp.id.set(id);
@@ -107,16 +103,128 @@ impl Track {
         imp.duration.set(duration);
         imp.track_number.set(track_number);
         imp.disc_number.set(disc_number);
+        imp.tags.borrow_mut().clear();
+        self.update_sort_keys();
         //self.create_menu();
     }
-    pub fn add_genre_id(&self, genre_id: i64) {
-        self.imp().genre_id.set(Some(genre_id));
+    pub fn add_genre_ids(&self, genre_ids: Vec<i64>) {
+        self.imp().genre_ids.replace(genre_ids);
+    }
+
+    pub fn genre_ids(&self) -> Vec<i64> {
+        self.imp().genre_ids.borrow().clone()
+    }
+
+    //Extended tags like composer or label, keyed by the tag name
+    pub fn add_tags(&self, tags: HashMap<String, String>) {
+        let imp = self.imp();
+        let mut search_string = imp.search_string.borrow().clone();
+        for key in SEARCHABLE_TAGS {
+            if let Some(value) = tags.get(key) {
+                search_string.push(' ');
+                search_string.push_str(value);
+            }
+        }
+        imp.search_string.replace(search_string);
+        imp.tags.replace(tags);
+        self.update_sort_keys();
+    }
+
+    //Sort tags of the file take precedence over the names stripped of their article
+    pub fn update_sort_keys(&self) {
+        let imp = self.imp();
+        let sort_key = |tag: &str, name: &RefCell<String>| match self.tag(tag) {
+            Some(sort) => sort.to_lowercase(),
+            None => util::sort_name(&name.borrow()),
+        };
+
+        let sort_title = sort_key("titlesort", &imp.title);
+        let sort_album = sort_key("albumsort", &imp.album_name);
+        //the artistsort tag only applies when the track artist is the album artist
+        let same_artist = imp.artist.borrow().is_empty() || *imp.artist.borrow() == *imp.album_artist.borrow();
+        let sort_artist = match self.tag("albumartistsort") {
+            None if same_artist => sort_key("artistsort", &imp.album_artist),
+            _ => sort_key("albumartistsort", &imp.album_artist),
+        };
+
+        imp.sort_string.replace(format!("{} {} {}", sort_title, sort_album, sort_artist));
+        imp.sort_title.replace(sort_title);
+        imp.sort_album.replace(sort_album);
+        imp.sort_artist.replace(sort_artist);
+    }
+
+    pub fn tag(&self, key: &str) -> Option<String> {
+        self.imp().tags.borrow().get(key).cloned()
+    }
+
+    //Tagged or analyzed tempo, the first value when the tag has several
+    pub fn bpm(&self) -> Option<f64> {
+        self.tag("bpm")
+            .and_then(|bpm| bpm.split(',').next().and_then(|bpm| bpm.trim().parse::<f64>().ok()))
+            .filter(|bpm| *bpm > 0.0)
+    }
+
+    pub fn set_play_stats(&self, play_stats: PlayStats) {
+        self.imp().play_stats.set(play_stats);
+    }
+
+    //Counted as soon as the play is committed, so sorting by plays stays current
+    pub fn add_play(&self, timestamp: i64) {
+        let mut play_stats = self.imp().play_stats.get();
+        play_stats.play_count += 1;
+        play_stats.last_played = Some(timestamp);
+        self.imp().play_stats.set(play_stats);
+    }
+
+    pub fn add_skip(&self) {
+        let mut play_stats = self.imp().play_stats.get();
+        play_stats.skip_count += 1;
+        self.imp().play_stats.set(play_stats);
+    }
+
+    pub fn play_count(&self) -> u64 {
+        self.imp().play_stats.get().play_count
+    }
+
+    pub fn last_played(&self) -> Option<i64> {
+        self.imp().play_stats.get().last_played
+    }
+
+    pub fn skip_count(&self) -> u64 {
+        self.imp().play_stats.get().skip_count
+    }
+
+    pub fn added_time(&self) -> i64 {
+        self.imp().play_stats.get().added_time
+    }
+
+    pub fn set_rating(&self, rating: Rating) {
+        self.imp().rating.set(rating);
+    }
+
+    pub fn rating(&self) -> Rating {
+        self.imp().rating.get()
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.imp().rating.get().favorite
     }
 
     pub fn add_artist_id(&self, artist_id: i64) {
         self.imp().artist_id.set(artist_id);
     }
 
+    //Every artist credited on the track, artist_id stays the primary album artist
+    pub fn add_artists(&self, artist: String, artist_ids: Vec<i64>) {
+        let imp = self.imp();
+        if !artist.is_empty() && artist != *imp.album_artist.borrow() {
+            let search_string = format!("{} {}", imp.search_string.borrow(), artist);
+            imp.search_string.replace(search_string);
+        }
+        imp.artist.replace(artist);
+        imp.artist_ids.replace(artist_ids);
+    }
+
     pub fn add_album_id(&self, album_id: i64) {
         self.imp().album_id.set(album_id);
     }
@@ -170,10 +278,39 @@ impl Track {
         self.imp().genre.borrow().clone()
     }
 
+    //Genres split at import, the same ones listed on the genre pages, or the genre tag if none were stored
+    pub fn genre_names(&self) -> Vec<String> {
+        let names: Vec<String> = self.genre_ids()
+            .iter()
+            .filter_map(|id| util::model().genre(*id).ok())
+            .map(|genre| genre.name())
+            .collect();
+
+        if names.is_empty() {
+            vec![self.genre()]
+        } else {
+            names
+        }
+    }
+
+    //Credited artist of the track, falls back on the album artist
     pub fn artist(&self) -> String {
+        let imp = self.imp();
+        if imp.artist.borrow().is_empty() {
+            imp.album_artist.borrow().clone()
+        } else {
+            imp.artist.borrow().clone()
+        }
+    }
+
+    pub fn album_artist(&self) -> String {
         self.imp().album_artist.borrow().clone()
     }
 
+    pub fn artist_ids(&self) -> Vec<i64> {
+        self.imp().artist_ids.borrow().clone()
+    }
+
     pub fn sort_artist(&self) -> String {
         self.imp().sort_artist.borrow().clone()
     }
@@ -190,6 +327,10 @@ impl Track {
         self.imp().duration.get().clone()
     }
 
+    pub fn filetype(&self) -> String {
+        self.imp().filetype.borrow().clone()
+    }
+
     pub fn search_string(&self) -> String {
         self.imp().search_string.borrow().clone()
     }
@@ -198,7 +339,6 @@ impl Track {
         self.imp().sort_string.borrow().clone()
     }
     
-    #[allow(dead_code)]
     fn create_menu(&self) {
         let imp = self.imp();
     
@@ -209,7 +349,7 @@ impl Track {
         menu.append_item(&menu_item);
     
         let menu_item = gio::MenuItem::new(Some(&format!("Add «{}» to Queue", self.title())), None);
-        menu_item.set_action_and_target_value(Some("win.add-track"), Some(&imp.id.get().to_variant()));
+        menu_item.set_action_and_target_value(Some("win.add-track-to-queue"), Some(&imp.id.get().to_variant()));
         menu.append_item(&menu_item);
     
         // let menu_item = gio::MenuItem::new(Some(&format!("Play «{}» from «{}»", self.album(), self.title())), None);
@@ -220,11 +360,11 @@ impl Track {
     
         let menu = gio::Menu::new();
     
-        let menu_item = gio::MenuItem::new(Some(&format!("Go to Album «{}» Detail", self.title())), None);
-        menu_item.set_action_and_target_value(Some("win.go-to-album-detail"), Some(&imp.id.get().to_variant()));
+        let menu_item = gio::MenuItem::new(Some(&format!("Go to Album «{}» Detail", self.album())), None);
+        menu_item.set_action_and_target_value(Some("win.go-to-album-detail"), Some(&imp.album_id.get().to_variant()));
         menu.append_item(&menu_item);
     
-        let menu_item = gio::MenuItem::new(Some(&format!("Go to Artist {} Detail", self.artist())), None);
+        let menu_item = gio::MenuItem::new(Some(&format!("Go to Artist {} Detail", self.album_artist())), None);
         menu_item.set_action_and_target_value(Some("win.go-to-artist-detail"), Some(&imp.artist_id.get().to_variant()));
         menu.append_item(&menu_item);
     
@@ -241,9 +381,15 @@ impl Track {
         menu.append_item(&menu_item);
     
         imp.menu.append_section(Some("Playlist"), &menu);
+
+        let menu = rating::rating_menu("win.toggle-favorite-track", "win.rate-track", imp.id.get(), self.rating());
+        imp.menu.append_section(Some(&i18n("Rate")), &menu);
     }
     
+    //Rebuilt when shown, so the labels follow the favorite & rating of the track
     pub fn menu_model(&self)-> &gio::Menu {
+        self.imp().menu.remove_all();
+        self.create_menu();
         &self.imp().menu
     }
 }
\ No newline at end of file
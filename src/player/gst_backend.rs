@@ -36,6 +36,32 @@ impl Default for BackendPlaybackState {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayGainMode {
+    Off,
+    Track,
+    Album,
+    Auto,
+}
+
+impl Default for ReplayGainMode {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+impl ReplayGainMode {
+    pub fn new_from_settings(mode: i32) -> Self {
+        match mode {
+            0 => ReplayGainMode::Off,
+            1 => ReplayGainMode::Track,
+            2 => ReplayGainMode::Album,
+            3 => ReplayGainMode::Auto,
+            _ => ReplayGainMode::Off,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GstPlayer {
     pub sender: Sender<PlaybackAction>,
@@ -46,7 +72,9 @@ pub struct GstPlayer {
     // pub tick: Cell<u64>,
     pub duration: RefCell<Option<f64>>,
     pub volume: Cell<f64>,
-
+    //rgvolume ! rglimiter bin, None if the replaygain elements are not installed
+    pub replaygain_filter: Option<gst::Element>,
+    pub rgvolume: Option<gst::Element>,
 }
 
 impl GstPlayer {
@@ -55,6 +83,14 @@ impl GstPlayer {
             .unwrap()
             .downcast::<gst::Pipeline>()
             .unwrap();
+
+        let (replaygain_filter, rgvolume) = match Self::replaygain_bin() {
+            Ok((bin, rgvolume)) => (Some(bin), Some(rgvolume)),
+            Err(e) => {
+                error!("Unable to create replaygain elements, replaygain disabled: {}", e);
+                (None, None)
+            }
+        };
         
         let gstplayer = Rc::new(Self {
             sender: player_sender,
@@ -65,7 +101,8 @@ impl GstPlayer {
             // tick: Cell::new(0),
             duration: RefCell::new(None),
             volume: Cell::new(0.0),
-
+            replaygain_filter,
+            rgvolume,
         });
 
         gstplayer.clone().connect_bus();
@@ -76,6 +113,52 @@ impl GstPlayer {
         &self.pipeline
     }
 
+    //audioconvert ! rgvolume ! rglimiter ! audioconvert, used as the audio-filter of playbin3
+    fn replaygain_bin() -> Result<(gst::Element, gst::Element), glib::BoolError> {
+        let bin = gst::Bin::builder().name("replaygain").build();
+        let convert_in = gst::ElementFactory::make("audioconvert").build()?;
+        let rgvolume = gst::ElementFactory::make("rgvolume").build()?;
+        let rglimiter = gst::ElementFactory::make("rglimiter").build()?;
+        let convert_out = gst::ElementFactory::make("audioconvert").build()?;
+
+        bin.add_many(&[&convert_in, &rgvolume, &rglimiter, &convert_out])?;
+        gst::Element::link_many(&[&convert_in, &rgvolume, &rglimiter, &convert_out])?;
+
+        let sink_pad = gst::GhostPad::with_target(Some("sink"), &convert_in.static_pad("sink").unwrap())?;
+        let src_pad = gst::GhostPad::with_target(Some("src"), &convert_out.static_pad("src").unwrap())?;
+        bin.add_pad(&sink_pad)?;
+        bin.add_pad(&src_pad)?;
+
+        Ok((bin.upcast(), rgvolume))
+    }
+
+    // REPLAYGAIN
+    // Only applied when the pipeline is not playing, the player sets it before loading each track.
+    // rgvolume reads the gain from the stream tags, the fallback gain covers files
+    // whose replaygain tags gstreamer does not parse.
+    pub fn set_replaygain(&self, album_mode: Option<bool>, pre_amp: f64, fallback_gain: f64) {
+        let (filter, rgvolume) = match (&self.replaygain_filter, &self.rgvolume) {
+            (Some(filter), Some(rgvolume)) => (filter, rgvolume),
+            _ => return,
+        };
+
+        match album_mode {
+            Some(album_mode) => {
+                rgvolume.set_property("album-mode", album_mode);
+                rgvolume.set_property("pre-amp", pre_amp);
+                rgvolume.set_property("fallback-gain", fallback_gain);
+                if self.pipeline.property::<Option<gst::Element>>("audio-filter").as_ref() != Some(filter) {
+                    self.pipeline.set_property("audio-filter", filter);
+                }
+            },
+            None => {
+                if self.pipeline.property::<Option<gst::Element>>("audio-filter").is_some() {
+                    self.pipeline.set_property("audio-filter", None::<gst::Element>);
+                }
+            },
+        }
+    }
+
 
     // STATE
     pub fn set_state(&self, state: BackendPlaybackState) {
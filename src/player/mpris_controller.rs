@@ -17,17 +17,21 @@ use mpris_player::{LoopStatus, Metadata, MprisPlayer, PlaybackStatus};
 
 use crate::model::track::Track;
 use crate::web::music_brainz::MusicBrainzAction;
+use crate::util::model;
 
 use super::player::PlaybackAction;
 use super::gst_backend::BackendPlaybackState;
 use super::queue::RepeatMode;
 
+//Shown until the cover art of the release is found
+const PLACEHOLDER_ART_URL: &str = "https://upload.wikimedia.org/wikipedia/commons/b/b6/12in-Vinyl-LP-Record-Angle.jpg";
 
 #[derive(Debug)]
 pub struct MprisController {
     sender: Sender<PlaybackAction>,
     mpris: Arc<MprisPlayer>,
     current_track: RefCell<Option<Rc<Track>>>,
+    art_url: RefCell<String>,
     mb_sender: Sender<MusicBrainzAction>,
     mb_receiver: RefCell<Option<Receiver<(i64, String)>>>,
 }
@@ -52,6 +56,7 @@ impl MprisController {
             sender,
             mpris,
             current_track: RefCell::new(None),
+            art_url: RefCell::new(PLACEHOLDER_ART_URL.to_string()),
             mb_sender,
             mb_receiver: RefCell::new(Some(mb_receiver)),
         };
@@ -137,19 +142,9 @@ impl MprisController {
     }
 
     fn update_metadata(&self) {
-        let mut metadata = Metadata::new();
         if let Some(track) = self.current_track.take() {
-            metadata.length = Some(track.duration() as i64);
-            metadata.album = Some(track.album());
-            metadata.artist = Some(vec![track.artist()]);
-            metadata.disc_number = Some(track.disc_number() as i32);
-            metadata.genre = Some(vec![track.genre()]);
-            metadata.title = Some(track.title());
-            metadata.track_number = Some(track.track_number() as i32);
-            metadata.art_url = Some("https://upload.wikimedia.org/wikipedia/commons/b/b6/12in-Vinyl-LP-Record-Angle.jpg".to_string());
-
-            //let length = Duration::from_secs().as_micros() as i64;
-            self.mpris.set_metadata(metadata);
+            self.art_url.replace(PLACEHOLDER_ART_URL.to_string());
+            self.publish_metadata(&track);
             self.current_track.replace(Some(track.clone()));
             send!(self.mb_sender, MusicBrainzAction::FindRelease((true, track)));
         }        
@@ -158,21 +153,36 @@ impl MprisController {
     fn receive_id(&self, album_id: i64, art_url: String) -> glib::Continue {
         if let Some(track) = self.current_track.borrow().as_ref() {
             if track.album_id() == album_id {
-                let mut metadata = Metadata::new();
-                metadata.length = Some(track.duration() as i64);
-                metadata.album = Some(track.album());
-                metadata.artist = Some(vec![track.artist()]);
-                metadata.disc_number = Some(track.disc_number() as i32);
-                metadata.genre = Some(vec![track.genre()]);
-                metadata.title = Some(track.title());
-                metadata.track_number = Some(track.track_number() as i32);
-                metadata.art_url = Some(art_url);
-                self.mpris.set_metadata(metadata);
+                self.art_url.replace(art_url);
+                self.publish_metadata(track);
             }
         }
         glib::Continue(true)
     }
 
+    //The rating of the playing track changed
+    pub fn refresh_rating(&self, track_id: i64) {
+        if let Some(track) = self.current_track.borrow().as_ref() {
+            if track.id() == track_id {
+                self.publish_metadata(track);
+            }
+        }
+    }
+
+    fn publish_metadata(&self, track: &Track) {
+        let mut metadata = Metadata::new();
+        metadata.length = Some(track.duration() as i64);
+        metadata.album = Some(track.album());
+        metadata.artist = Some(artist_names(track));
+        metadata.disc_number = Some(track.disc_number() as i32);
+        metadata.genre = Some(track.genre_names());
+        metadata.title = Some(track.title());
+        metadata.track_number = Some(track.track_number() as i32);
+        metadata.art_url = Some(self.art_url.borrow().clone());
+        metadata.user_rating = Some(track.rating().user_rating());
+        self.mpris.set_metadata(metadata);
+    }
+
 
     pub fn set_playback_state(&self, state: &BackendPlaybackState) {
         self.mpris.set_can_play(true);
@@ -202,4 +212,19 @@ impl MprisController {
             RepeatMode::Shuffle => self.mpris.set_loop_status(LoopStatus::Playlist),
         }
     }
-}
\ No newline at end of file
+}
+
+//Every credited artist of the track, as mpris takes a list
+fn artist_names(track: &Track) -> Vec<String> {
+    let names: Vec<String> = track.artist_ids()
+        .iter()
+        .filter_map(|id| model().artist(*id).ok())
+        .map(|artist| artist.name())
+        .collect();
+
+    if names.is_empty() {
+        vec![track.artist()]
+    } else {
+        names
+    }
+}
@@ -19,9 +19,9 @@ use crate::web::{
     discord::{ResonanceDiscord, DiscordAction},
     last_fm::{ResonanceLastFM, LastFmAction},
 };
-use crate::util::{database, settings_manager};
+use crate::util::{database, model, settings_manager};
 
-use super::gst_backend::{GstPlayer, BackendPlaybackState};
+use super::gst_backend::{GstPlayer, BackendPlaybackState, ReplayGainMode};
 use super::queue::{Queue, QueueAction, RepeatMode};
 use super::state::PlayerState;
 use super::mpris_controller::MprisController;
@@ -61,6 +61,8 @@ pub struct Player {
     pub lastfm: Rc<ResonanceLastFM>,
     pub lastfm_enabled: Cell<bool>,
     pub lastfm_sender: Sender<LastFmAction>,
+    pub replaygain_mode: Cell<ReplayGainMode>,
+    pub replaygain_preamp: Cell<f64>,
 }
 
 impl Player {
@@ -102,6 +104,8 @@ impl Player {
             lastfm,
             lastfm_enabled: Cell::new(false),
             lastfm_sender,
+            replaygain_mode: Cell::new(ReplayGainMode::default()),
+            replaygain_preamp: Cell::new(0.0),
         };
         
         let player = Rc::new(p);
@@ -121,6 +125,8 @@ impl Player {
         self.discord_enabled.set(settings.boolean("discord-rich-presence"));
         self.lastfm_enabled.set(settings.boolean("last-fm-enabled"));
         self.commit_threshold.set(settings.double("play-commit-threshold"));
+        self.replaygain_mode.set(ReplayGainMode::new_from_settings(settings.int("replaygain-mode")));
+        self.replaygain_preamp.set(settings.double("replaygain-preamp"));
     }
 
     fn setup_channels(self: Rc<Self>) {
@@ -179,7 +185,13 @@ impl Player {
             PlaybackAction::TogglePlayPause => self.toggle_play_pause(),
             PlaybackAction::Stop => self.stop(),
             PlaybackAction::Tick(tick) => self.update_tick(tick),
-            PlaybackAction::EOS => self.next(),
+            PlaybackAction::EOS => {
+                //a track played to the end is never a skip, even if the last tick missed the threshold
+                if !self.committed.get() {
+                    self.record_play();
+                }
+                self.next();
+            },
             PlaybackAction::Error => error!("Player error"),
             PlaybackAction::PlaybackState(state) => self.set_state_state(state),
             PlaybackAction::QueueRepeatMode(mode) => {_ = self.process_queue_action(QueueAction::QueueRepeatMode(mode))},
@@ -278,6 +290,7 @@ impl Player {
         self.committed.set(false);
         if let Some(track) = self.queue().current_track() {
             self.backend.set_state(BackendPlaybackState::Loading);
+            self.set_replaygain(&track);
             self.backend.set_uri(track.uri());
             self.backend.set_state(BackendPlaybackState::Playing);
             self.set_current_track(Some(track.clone()));
@@ -300,6 +313,7 @@ impl Player {
     }
 
     pub fn next(&self) {
+        self.record_skip();
         self.queue().get_next();
         self.play();
     }
@@ -358,13 +372,35 @@ impl Player {
     fn record_play(&self) {
         if let Some(track) = self.state().current_track() {
             send!(self.lastfm_sender, LastFmAction::Scrobble(track.clone()));
-            match database().add_play(track, chrono::offset::Utc::now()) {
-                Ok(_) => self.committed.set(true),
+            let now = chrono::offset::Utc::now();
+            match database().add_play(track.clone(), now) {
+                Ok(_) => {
+                    self.committed.set(true);
+                    track.add_play(now.timestamp());
+                    model().emit_by_name::<()>("refresh-plays", &[]);
+                },
                 Err(e) => error!("An error occurred adding track to playlist: {}", e),
             };
         }
     }
 
+    //Skipping to the next track before the play threshold counts as a skip
+    fn record_skip(&self) {
+        if self.committed.get() {
+            return;
+        }
+
+        if let Some(track) = self.state().current_track() {
+            match database().add_skip(track.clone(), chrono::offset::Utc::now()) {
+                Ok(_) => {
+                    track.add_skip();
+                    model().emit_by_name::<()>("refresh-plays", &[]);
+                },
+                Err(e) => error!("Unable to record skip: {}", e),
+            };
+        }
+    }
+
     pub fn has_next(&self) -> bool {
         return true;
     }
@@ -400,6 +436,30 @@ impl Player {
         self.state().set_playback_state(state);
     }
 
+    //auto mode uses the album gain when the whole queue is one album
+    fn set_replaygain(&self, track: &Track) {
+        let album_mode = match self.replaygain_mode.get() {
+            ReplayGainMode::Off => {
+                self.backend.set_replaygain(None, 0.0, 0.0);
+                return;
+            },
+            ReplayGainMode::Track => false,
+            ReplayGainMode::Album => true,
+            ReplayGainMode::Auto => self.queue().is_single_album(),
+        };
+
+        let gain_tag = |key: &str| track.tag(key).and_then(|gain| parse_gain(&gain));
+        let stored_gain = if album_mode {
+            gain_tag("replaygain_album_gain").or_else(|| gain_tag("replaygain_track_gain"))
+        } else {
+            gain_tag("replaygain_track_gain").or_else(|| gain_tag("replaygain_album_gain"))
+        };
+
+        let pre_amp = self.replaygain_preamp.get();
+        let fallback_gain = stored_gain.map(|gain| gain + pre_amp).unwrap_or(0.0).clamp(-60.0, 60.0);
+        self.backend.set_replaygain(Some(album_mode), pre_amp, fallback_gain);
+    }
+
     pub fn set_volume(&self, volume: f64) {
         self.backend.set_volume(volume);
         self.state.set_volume(volume);
@@ -435,4 +495,14 @@ impl Player {
         &self.queue
     }
 }
-    
\ No newline at end of file
+    
+
+//Gain in dB of a replaygain tag, ie "-6.54 dB"
+fn parse_gain(gain: &str) -> Option<f64> {
+    gain.trim()
+        .trim_end_matches("dB")
+        .trim_end_matches("db")
+        .trim()
+        .parse::<f64>()
+        .ok()
+}
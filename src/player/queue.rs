@@ -387,6 +387,15 @@ impl Queue {
         self.queue.borrow().len() == 0
     }
 
+    //Whether every track of the queue comes from the same album
+    pub fn is_single_album(&self) -> bool {
+        let queue = self.queue.borrow();
+        match queue.first() {
+            Some(first) => queue.iter().all(|track| track.album_id() == first.album_id()),
+            None => false,
+        }
+    }
+
     fn queue_len(&self) -> usize {
         self.queue.borrow().len()
     }
@@ -0,0 +1,703 @@
+/* playlist_file.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{collections::{BTreeMap, HashMap}, error::Error, fs, path::{Component, Path, PathBuf}, rc::Rc};
+use log::debug;
+
+use crate::model::track::Track;
+use crate::util;
+
+pub const PLAYLIST_EXTENSIONS: [&str; 4] = ["m3u", "m3u8", "xspf", "pls"];
+
+static XSPF_TRACK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<track\b[^>]*>(.*?)</track>").unwrap());
+static XSPF_PLAYLIST_TITLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<title>(.*?)</title>.*<trackList").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaylistFormat {
+    M3u8,
+    Xspf,
+    Pls,
+}
+
+impl PlaylistFormat {
+    pub fn all() -> [PlaylistFormat; 3] {
+        [Self::M3u8, Self::Xspf, Self::Pls]
+    }
+
+    //Plain .m3u files are read the same way, they are only written as .m3u8
+    pub fn from_path(path: &Path) -> Option<PlaylistFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "m3u" | "m3u8" => Some(Self::M3u8),
+            "xspf" => Some(Self::Xspf),
+            "pls" => Some(Self::Pls),
+            _ => None,
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<PlaylistFormat> {
+        Self::from_path(Path::new(&format!("playlist.{}", extension)))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::M3u8 => "m3u8",
+            Self::Xspf => "xspf",
+            Self::Pls => "pls",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::M3u8 => "M3U8",
+            Self::Xspf => "XSPF",
+            Self::Pls => "PLS",
+        }
+    }
+}
+
+//One entry of a playlist file, the tags are only known to extended M3U, XSPF & PLS
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistFileEntry {
+    pub path: Option<String>,
+    pub location: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration: Option<f64>,
+}
+
+impl PlaylistFileEntry {
+    //"Artist - Title" of the entry, or its location when it has no tags
+    pub fn describe(&self) -> String {
+        match (self.artist.is_empty(), self.title.is_empty()) {
+            (false, false) => format!("{} - {}", self.artist, self.title),
+            (true, false) => self.title.clone(),
+            _ => self.location.clone(),
+        }
+    }
+
+    //EXTINF & PLS titles are usually "Artist - Title"
+    fn set_display_title(&mut self, display: &str) {
+        match display.split_once(" - ") {
+            Some((artist, title)) => {
+                self.artist = artist.trim().to_string();
+                self.title = title.trim().to_string();
+            },
+            None => self.title = display.trim().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaylistFile {
+    pub path: PathBuf,
+    pub title: String,
+    pub last_modified: i64,
+    pub entries: Vec<PlaylistFileEntry>,
+}
+
+impl PlaylistFile {
+    pub fn read(path: &Path) -> Result<PlaylistFile, Box<dyn Error>> {
+        let format = PlaylistFormat::from_path(path).ok_or(format!("{:?} is not a playlist file", path))?;
+        let bytes = fs::read(path)?;
+        //old .m3u files are often latin-1, lossy decoding keeps the ascii paths readable
+        let text = String::from_utf8_lossy(&bytes);
+        let text = text.trim_start_matches('\u{feff}');
+        let base = path.parent().unwrap_or(Path::new("/"));
+
+        let (title, entries) = match format {
+            PlaylistFormat::M3u8 => parse_m3u(text, base),
+            PlaylistFormat::Xspf => parse_xspf(text, base),
+            PlaylistFormat::Pls => parse_pls(text, base),
+        };
+
+        let title = match title {
+            Some(title) if !title.trim().is_empty() => title.trim().to_string(),
+            _ => path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default(),
+        };
+
+        let last_modified = fs::metadata(path)?.modified()
+            .map(|modified| DateTime::<Utc>::from(modified).timestamp())
+            .unwrap_or(0);
+
+        debug!("read {} entries from {:?}", entries.len(), path);
+        Ok(PlaylistFile {
+            path: path.to_path_buf(),
+            title,
+            last_modified,
+            entries,
+        })
+    }
+}
+
+fn parse_m3u(text: &str, base: &Path) -> (Option<String>, Vec<PlaylistFileEntry>) {
+    let mut title = None;
+    let mut entries = Vec::new();
+    let mut pending = PlaylistFileEntry::default();
+
+    for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (duration, display) = info.split_once(',').unwrap_or((info, ""));
+            //attributes like tvg-id="" may follow the duration
+            let duration = duration.split_whitespace().next().and_then(|d| d.parse::<f64>().ok());
+            pending.duration = duration.filter(|d| *d > 0.0);
+            pending.set_display_title(display);
+        } else if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+            title = Some(name.to_string());
+        } else if let Some(album) = line.strip_prefix("#EXTALB:") {
+            pending.album = album.trim().to_string();
+        } else if let Some(artist) = line.strip_prefix("#EXTART:") {
+            if pending.artist.is_empty() {
+                pending.artist = artist.trim().to_string();
+            }
+        } else if !line.starts_with('#') {
+            let mut entry = std::mem::take(&mut pending);
+            entry.location = line.to_string();
+            entry.path = resolve_location(line, base);
+            entries.push(entry);
+        }
+    }
+
+    (title, entries)
+}
+
+fn parse_pls(text: &str, base: &Path) -> (Option<String>, Vec<PlaylistFileEntry>) {
+    let mut entries: BTreeMap<usize, PlaylistFileEntry> = BTreeMap::new();
+    let mut title = None;
+
+    for line in text.lines().map(|line| line.trim()) {
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim().to_lowercase(), value.trim()),
+            None => continue,
+        };
+
+        if key == "x-gnome-title" || key == "title" {
+            title = Some(value.to_string());
+            continue;
+        }
+
+        let split = key.find(|c: char| c.is_ascii_digit()).unwrap_or(key.len());
+        let (field, index) = key.split_at(split);
+        let index = match index.parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => continue,
+        };
+
+        let entry = entries.entry(index).or_default();
+        match field {
+            "file" => {
+                entry.location = value.to_string();
+                entry.path = resolve_location(value, base);
+            },
+            "title" => entry.set_display_title(value),
+            "length" => entry.duration = value.parse::<f64>().ok().filter(|d| *d > 0.0),
+            _ => (),
+        }
+    }
+
+    let entries = entries.into_values().filter(|entry| !entry.location.is_empty()).collect();
+    (title, entries)
+}
+
+fn parse_xspf(text: &str, base: &Path) -> (Option<String>, Vec<PlaylistFileEntry>) {
+    let title = XSPF_PLAYLIST_TITLE.captures(text)
+        .and_then(|captures| captures.get(1))
+        .map(|title| decode_xml(title.as_str()));
+
+    let mut entries = Vec::new();
+    for captures in XSPF_TRACK.captures_iter(text) {
+        let track = &captures[1];
+        let location = xml_element(track, "location").unwrap_or_default();
+        //relative locations are percent encoded too
+        let location = match location.contains("://") {
+            true => location,
+            false => urlencoding::decode(&location).map(|l| l.into_owned()).unwrap_or(location),
+        };
+        entries.push(PlaylistFileEntry {
+            path: resolve_location(&location, base),
+            location,
+            title: xml_element(track, "title").unwrap_or_default(),
+            artist: xml_element(track, "creator").unwrap_or_default(),
+            album: xml_element(track, "album").unwrap_or_default(),
+            //xspf durations are in milliseconds
+            duration: xml_element(track, "duration")
+                .and_then(|d| d.parse::<f64>().ok())
+                .map(|d| d / 1000.0),
+        });
+    }
+
+    (title, entries)
+}
+
+fn xml_element(text: &str, name: &str) -> Option<String> {
+    let start = text.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = text[start..].find(&format!("</{}>", name))? + start;
+    Some(decode_xml(&text[start..end]))
+}
+
+fn decode_xml(text: &str) -> String {
+    let text = text.trim();
+    let text = text.strip_prefix("<![CDATA[").and_then(|t| t.strip_suffix("]]>")).unwrap_or(text);
+    html_escape::decode_html_entities(text).trim().to_string()
+}
+
+//Absolute path of an entry, relative paths start at the folder of the playlist file, streams have none
+fn resolve_location(location: &str, base: &Path) -> Option<String> {
+    let location = location.trim();
+    if location.is_empty() {
+        return None;
+    }
+
+    let path = if let Some(uri) = location.strip_prefix("file://") {
+        let uri = uri.strip_prefix("localhost").unwrap_or(uri);
+        PathBuf::from(urlencoding::decode(uri).ok()?.into_owned())
+    } else if location.contains("://") {
+        return None;
+    } else {
+        //playlists written on windows
+        PathBuf::from(location.replace('\\', "/"))
+    };
+
+    let path = if path.is_absolute() { path } else { base.join(path) };
+    Some(normalize(&path).to_string_lossy().to_string())
+}
+
+//Removes the "." & ".." of a path without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            },
+            component => normalized.push(component.as_os_str()),
+        }
+    }
+    normalized
+}
+
+//Path of a track as written in a playlist file saved to dir
+fn relative_path(path: &Path, dir: &Path) -> PathBuf {
+    let path_components: Vec<Component> = path.components().collect();
+    let dir_components: Vec<Component> = dir.components().collect();
+    let common = path_components.iter()
+        .zip(dir_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    //nothing in common but the root, a relative path would only be longer
+    if common <= 1 {
+        return path.to_path_buf();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..dir_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    relative
+}
+
+pub fn write_playlist(path: &Path, format: PlaylistFormat, title: &str, tracks: &[Rc<Track>], relative: bool) -> Result<(), Box<dyn Error>> {
+    let entries: Vec<PlaylistFileEntry> = tracks.iter()
+        .map(|track| PlaylistFileEntry {
+            path: Some(track.uri()),
+            location: track.uri(),
+            title: track.title(),
+            artist: track.artist(),
+            album: track.album(),
+            duration: Some(track.duration()),
+        })
+        .collect();
+    write_entries(path, format, title, &entries, relative)
+}
+
+//Entries are written at their path, made relative to the folder of the playlist file if asked
+fn write_entries(path: &Path, format: PlaylistFormat, title: &str, entries: &[PlaylistFileEntry], relative: bool) -> Result<(), Box<dyn Error>> {
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let location = |entry: &PlaylistFileEntry| -> String {
+        let uri = PathBuf::from(entry.path.as_deref().unwrap_or(&entry.location));
+        if relative {
+            relative_path(&uri, dir).to_string_lossy().to_string()
+        } else {
+            uri.to_string_lossy().to_string()
+        }
+    };
+    let duration = |entry: &PlaylistFileEntry| entry.duration.unwrap_or(0.0);
+
+    let mut text = String::new();
+    match format {
+        PlaylistFormat::M3u8 => {
+            text.push_str("#EXTM3U\n");
+            text.push_str(&format!("#PLAYLIST:{}\n", title));
+            for entry in entries {
+                text.push_str(&format!("#EXTINF:{},{} - {}\n", duration(entry).round() as i64, entry.artist, entry.title));
+                text.push_str(&format!("{}\n", location(entry)));
+            }
+        },
+        PlaylistFormat::Pls => {
+            text.push_str("[playlist]\n");
+            text.push_str(&format!("X-GNOME-Title={}\n", title));
+            for (i, entry) in entries.iter().enumerate() {
+                text.push_str(&format!("File{}={}\n", i + 1, location(entry)));
+                text.push_str(&format!("Title{}={} - {}\n", i + 1, entry.artist, entry.title));
+                text.push_str(&format!("Length{}={}\n", i + 1, duration(entry).round() as i64));
+            }
+            text.push_str(&format!("NumberOfEntries={}\n", entries.len()));
+            text.push_str("Version=2\n");
+        },
+        PlaylistFormat::Xspf => {
+            text.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+            text.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+            text.push_str(&format!("  <title>{}</title>\n", html_escape::encode_text(title)));
+            text.push_str("  <trackList>\n");
+            for entry in entries {
+                //xspf locations are uris, relative ones are resolved against the playlist
+                let location = location(entry)
+                    .split('/')
+                    .map(|part| urlencoding::encode(part).into_owned())
+                    .collect::<Vec<String>>()
+                    .join("/");
+                let location = if location.starts_with('/') { format!("file://{}", location) } else { location };
+                text.push_str("    <track>\n");
+                text.push_str(&format!("      <location>{}</location>\n", html_escape::encode_text(&location)));
+                text.push_str(&format!("      <title>{}</title>\n", html_escape::encode_text(&entry.title)));
+                text.push_str(&format!("      <creator>{}</creator>\n", html_escape::encode_text(&entry.artist)));
+                text.push_str(&format!("      <album>{}</album>\n", html_escape::encode_text(&entry.album)));
+                text.push_str(&format!("      <duration>{}</duration>\n", (duration(entry) * 1000.0).round() as i64));
+                text.push_str("    </track>\n");
+            }
+            text.push_str("  </trackList>\n");
+            text.push_str("</playlist>\n");
+        },
+    }
+
+    fs::write(path, text)?;
+    debug!("wrote {} entries to {:?}", entries.len(), path);
+    Ok(())
+}
+
+//Playlist files inside a music folder, hidden folders are skipped
+pub fn find_playlist_files(folder_path: &str) -> Vec<PathBuf> {
+    let root = Path::new(folder_path);
+    let (_dirs, files) = util::walk_folder(root);
+
+    let mut files: Vec<PathBuf> = files.into_iter()
+        .filter(|path| {
+            let hidden = matches!(path.strip_prefix(root), Ok(relative)
+                if relative.components().any(|component| component.as_os_str().to_string_lossy().starts_with('.')));
+            !hidden && PlaylistFormat::from_path(path).is_some()
+        })
+        .collect();
+
+    files.sort();
+    files
+}
+
+//Tags of a library track used to find the entries of a playlist file
+#[derive(Debug, Clone)]
+pub struct LibraryTrack {
+    pub id: i64,
+    pub path: String,
+    pub title: String,
+    pub artist: String,
+    pub album_artist: String,
+    pub album: String,
+    pub duration: f64,
+}
+
+//Finds the library track of an entry by its path, then by its tags if the file moved or was renamed
+pub struct TrackMatcher {
+    by_path: HashMap<String, i64>,
+    by_title: HashMap<String, Vec<LibraryTrack>>,
+}
+
+impl TrackMatcher {
+    pub fn new(tracks: Vec<LibraryTrack>) -> TrackMatcher {
+        let mut by_path = HashMap::new();
+        let mut by_title: HashMap<String, Vec<LibraryTrack>> = HashMap::new();
+        for track in tracks {
+            by_path.insert(track.path.clone(), track.id);
+            by_title.entry(simplify(&track.title)).or_default().push(track);
+        }
+        TrackMatcher { by_path, by_title }
+    }
+
+    pub fn find(&self, entry: &PlaylistFileEntry) -> Option<i64> {
+        if let Some(id) = entry.path.as_ref().and_then(|path| self.by_path.get(path)) {
+            return Some(*id);
+        }
+
+        if entry.title.is_empty() {
+            return None;
+        }
+
+        let artist = simplify(&entry.artist);
+        let album = simplify(&entry.album);
+        self.by_title.get(&simplify(&entry.title))?
+            .iter()
+            .filter(|track| artist.is_empty() || simplify(&track.artist).contains(&artist) || simplify(&track.album_artist).contains(&artist))
+            .filter(|track| album.is_empty() || simplify(&track.album) == album)
+            .filter(|track| match entry.duration {
+                Some(duration) => (track.duration - duration).abs() < 3.0,
+                None => true,
+            })
+            .map(|track| track.id)
+            .next()
+    }
+
+    //Track ids of the found entries in playlist order & the descriptions of the others
+    pub fn match_entries(&self, entries: &[PlaylistFileEntry]) -> (Vec<i64>, Vec<String>) {
+        let mut track_ids = Vec::new();
+        let mut unmatched = Vec::new();
+        for entry in entries {
+            match self.find(entry) {
+                Some(id) => track_ids.push(id),
+                None => unmatched.push(entry.describe()),
+            }
+        }
+        (track_ids, unmatched)
+    }
+}
+
+//Case & punctuation are often different between taggers
+fn simplify(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //A fresh folder for each test, the tests run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("resonance-playlist-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(path: &str, title: &str, artist: &str, album: &str, duration: f64) -> PlaylistFileEntry {
+        PlaylistFileEntry {
+            path: Some(path.to_string()),
+            location: path.to_string(),
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            duration: Some(duration),
+        }
+    }
+
+    fn library_track(id: i64, path: &str, title: &str, artist: &str, album: &str, duration: f64) -> LibraryTrack {
+        LibraryTrack {
+            id,
+            path: path.to_string(),
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album_artist: artist.to_string(),
+            album: album.to_string(),
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_parse_m3u() {
+        let text = "#EXTM3U\n\
+            #PLAYLIST:Road Trip\n\
+            #EXTINF:215 tvg-id=\"one\" tvg-logo=\"logo.png\",Artist - Song\n\
+            #EXTALB:Album\n\
+            songs/01 Song.flac\n\
+            \n\
+            #EXTINF:-1,Radio\n\
+            http://radio.example.com/stream\n\
+            #EXTINF:100,Only Title\n\
+            #EXTART:Other Artist\n\
+            ..\\windows\\02 Other.mp3\n";
+        let (title, entries) = parse_m3u(text, Path::new("/music/lists"));
+
+        assert_eq!(title.as_deref(), Some("Road Trip"));
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].path.as_deref(), Some("/music/lists/songs/01 Song.flac"));
+        assert_eq!(entries[0].duration, Some(215.0));
+        assert_eq!(entries[0].artist, "Artist");
+        assert_eq!(entries[0].title, "Song");
+        assert_eq!(entries[0].album, "Album");
+
+        assert_eq!(entries[1].path, None);
+        assert_eq!(entries[1].location, "http://radio.example.com/stream");
+        assert_eq!(entries[1].duration, None);
+
+        assert_eq!(entries[2].path.as_deref(), Some("/music/windows/02 Other.mp3"));
+        assert_eq!(entries[2].title, "Only Title");
+        assert_eq!(entries[2].artist, "Other Artist");
+        assert_eq!(entries[2].album, "");
+    }
+
+    #[test]
+    fn test_parse_pls() {
+        let text = "[playlist]\n\
+            X-GNOME-Title=Evening\n\
+            File2=/music/b.ogg\n\
+            Title2=Second\n\
+            file10=/music/j.ogg\n\
+            Length2=-1\n\
+            File1=a.ogg\n\
+            Title1=First Artist - First\n\
+            Length1=180\n\
+            Title3=No File\n\
+            NumberOfEntries=3\n\
+            Version=2\n";
+        let (title, entries) = parse_pls(text, Path::new("/music/lists"));
+
+        assert_eq!(title.as_deref(), Some("Evening"));
+        let paths: Vec<Option<&str>> = entries.iter().map(|entry| entry.path.as_deref()).collect();
+        assert_eq!(paths, vec![Some("/music/lists/a.ogg"), Some("/music/b.ogg"), Some("/music/j.ogg")]);
+
+        assert_eq!(entries[0].artist, "First Artist");
+        assert_eq!(entries[0].title, "First");
+        assert_eq!(entries[0].duration, Some(180.0));
+        assert_eq!(entries[1].title, "Second");
+        assert_eq!(entries[1].duration, None);
+    }
+
+    #[test]
+    fn test_parse_xspf() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <playlist version="1" xmlns="http://xspf.org/ns/0/">
+              <title><![CDATA[Rock & Roll]]></title>
+              <trackList>
+                <track>
+                  <location>../other/My%20Song%20%231.ogg</location>
+                  <title><![CDATA[Song <Live>]]></title>
+                  <creator>Simon &amp; Garfunkel</creator>
+                  <album>Caf&#233;</album>
+                  <duration>1500</duration>
+                </track>
+                <track>
+                  <location>file:///music/a%20b.flac</location>
+                  <title>Second</title>
+                </track>
+                <track>
+                  <location>https://example.com/stream%20one</location>
+                </track>
+              </trackList>
+            </playlist>"#;
+        let (title, entries) = parse_xspf(text, Path::new("/music/lists"));
+
+        assert_eq!(title.as_deref(), Some("Rock & Roll"));
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].path.as_deref(), Some("/music/other/My Song #1.ogg"));
+        assert_eq!(entries[0].title, "Song <Live>");
+        assert_eq!(entries[0].artist, "Simon & Garfunkel");
+        assert_eq!(entries[0].album, "Café");
+        assert_eq!(entries[0].duration, Some(1.5));
+
+        assert_eq!(entries[1].path.as_deref(), Some("/music/a b.flac"));
+        assert_eq!(entries[1].duration, None);
+
+        assert_eq!(entries[2].path, None);
+        assert_eq!(entries[2].location, "https://example.com/stream%20one");
+    }
+
+    #[test]
+    fn test_resolve_location() {
+        let base = Path::new("/music/lists");
+        assert_eq!(resolve_location("file:///music/a%20b.flac", base).as_deref(), Some("/music/a b.flac"));
+        assert_eq!(resolve_location("file://localhost/music/a.flac", base).as_deref(), Some("/music/a.flac"));
+        assert_eq!(resolve_location("./a/../b.flac", base).as_deref(), Some("/music/lists/b.flac"));
+        assert_eq!(resolve_location("..\\c.flac", base).as_deref(), Some("/music/c.flac"));
+        assert_eq!(resolve_location("/elsewhere/d.flac", base).as_deref(), Some("/elsewhere/d.flac"));
+        assert_eq!(resolve_location("smb://server/e.flac", base), None);
+        assert_eq!(resolve_location("  ", base), None);
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("/music/./a/../b/c.flac")), PathBuf::from("/music/b/c.flac"));
+        assert_eq!(normalize(Path::new("/../a.flac")), PathBuf::from("/a.flac"));
+        assert_eq!(normalize(Path::new("a/b/../../c")), PathBuf::from("c"));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let dir = Path::new("/music/lists");
+        assert_eq!(relative_path(Path::new("/music/lists/a.flac"), dir), PathBuf::from("a.flac"));
+        assert_eq!(relative_path(Path::new("/music/albums/b.flac"), dir), PathBuf::from("../albums/b.flac"));
+        assert_eq!(relative_path(Path::new("/music/lists/sub/c.flac"), dir), PathBuf::from("sub/c.flac"));
+        //only the root in common
+        assert_eq!(relative_path(Path::new("/media/d.flac"), dir), PathBuf::from("/media/d.flac"));
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let dir = temp_dir("round-trip");
+        let music = dir.join("music");
+        let entries = vec![
+            entry(&music.join("Album/01 First & Last.flac").to_string_lossy(), "First & Last", "Artist", "Album", 181.0),
+            entry(&music.join("Other #2/Ça va.ogg").to_string_lossy(), "Ça va", "Other", "Other #2", 95.0),
+        ];
+
+        for format in PlaylistFormat::all() {
+            for relative in [true, false] {
+                let path = dir.join("lists").join(format!("{}-{}.{}", format.label(), relative, format.extension()));
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                write_entries(&path, format, "Mix & Match", &entries, relative).unwrap();
+
+                let text = fs::read_to_string(&path).unwrap();
+                assert_eq!(text.contains(&*music.to_string_lossy()), !relative, "{:?} relative {}", format, relative);
+
+                let playlist = PlaylistFile::read(&path).unwrap();
+                assert_eq!(playlist.title, "Mix & Match");
+                assert_eq!(playlist.entries.len(), entries.len());
+                for (read, written) in playlist.entries.iter().zip(entries.iter()) {
+                    assert_eq!(read.path, written.path, "{:?} relative {}", format, relative);
+                    assert_eq!(read.title, written.title);
+                    assert_eq!(read.artist, written.artist);
+                    assert_eq!(read.duration, written.duration);
+                }
+            }
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_track_matcher() {
+        let matcher = TrackMatcher::new(vec![
+            library_track(1, "/music/a.flac", "Song", "The Band", "Album", 200.0),
+            library_track(2, "/music/b.flac", "Song", "The Band", "Live Album", 260.0),
+            library_track(3, "/music/c.flac", "Other Song", "Someone", "Album", 120.0),
+        ]);
+
+        //the path wins over the tags
+        assert_eq!(matcher.find(&entry("/music/c.flac", "Song", "The Band", "", 200.0)), Some(3));
+        //moved file, found by its tags within the duration tolerance
+        assert_eq!(matcher.find(&entry("/old/a.flac", "song!", "the band", "", 202.5)), Some(1));
+        assert_eq!(matcher.find(&entry("/old/x.flac", "Song", "Band", "live album", 259.0)), Some(2));
+        assert_eq!(matcher.find(&entry("/old/x.flac", "Song", "The Band", "", 230.0)), None);
+        assert_eq!(matcher.find(&entry("/old/x.flac", "Song", "Nobody", "", 200.0)), None);
+
+        let unmatched = PlaylistFileEntry { location: "http://radio".to_string(), ..Default::default() };
+        let (track_ids, descriptions) = matcher.match_entries(&[
+            entry("/music/b.flac", "", "", "", 0.0),
+            unmatched,
+            entry("/old/z.flac", "Missing", "The Band", "", 10.0),
+        ]);
+        assert_eq!(track_ids, vec![2]);
+        assert_eq!(descriptions, vec!["http://radio".to_string(), "The Band - Missing".to_string()]);
+    }
+}
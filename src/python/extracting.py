@@ -98,12 +98,17 @@ def translate(filepath, mutagen_file) -> Optional[dict]:
     RETURN_MAP = dict()
 
     if filetype in {".ogg", ".flac", ".opus"}:
+        # vorbis comments may repeat a key, ie one ARTIST per artist
+        vorbis_tags = defaultdict(list)
         for _tup in mutagen_file.tags:
           # logger.debug(f"{_tup[0].lower()} : {_tup[1]} {type(_tup[1])}")
             #RETURN_MAP[_tup[0].lower()] = _tup[1]
             logger.debug(f"FLAC tag: {_tup[0].lower()} -> {_tup[1]}")
 
-            add_to_map(RETURN_MAP, _tup[0].lower(), [_tup[1]])
+            vorbis_tags[_tup[0].lower()].append(_tup[1])
+
+        for key, values in vorbis_tags.items():
+            add_to_map(RETURN_MAP, key, values)
     else:
         for tag in tag_mappings[filetype].keys():
             value = None
@@ -131,6 +136,15 @@ def translate(filepath, mutagen_file) -> Optional[dict]:
                 #RETURN_MAP[tag_mappings[filetype][tag]] = value
                 add_to_map(RETURN_MAP, tag_mappings[filetype][tag], value)
 
+        if filetype in ['.mp3', '.aif']:
+            for _key in mutagen_file.tags.keys():
+                if _key.startswith('TXXX:') and _key[5:] in musicbrainz_translate:
+                    value = [str(i) for i in mutagen_file.tags[_key].text]
+                    add_to_map(RETURN_MAP, musicbrainz_translate[_key[5:]], value)
+                if _key.startswith('TXXX:') and _key[5:].lower() in replaygain_translate:
+                    value = [str(i) for i in mutagen_file.tags[_key].text]
+                    add_to_map(RETURN_MAP, replaygain_translate[_key[5:].lower()], value)
+
         if filetype in ['.mp4', '.m4a']:
             for _key in mutagen_file.tags.keys():
                 value = None
@@ -145,6 +159,20 @@ def translate(filepath, mutagen_file) -> Optional[dict]:
                     discnumber = "{}/{}".format(_tup[0], _tup[1]) 
                     #RETURN_MAP['discnumber'] = discnumber 
                     add_to_map(RETURN_MAP, 'discnumber', discnumber)
+                if _tag == 'cpil':
+                    compilation = '1' if mutagen_file.tags[_key] else '0'
+                    add_to_map(RETURN_MAP, 'compilation', [compilation])
+                if _key.startswith('----:com.apple.iTunes:'):
+                    description = _key[len('----:com.apple.iTunes:'):]
+                    if description in musicbrainz_translate:
+                        ids = [bytes(i).decode('utf-8', 'replace') for i in mutagen_file.tags[_key]]
+                        add_to_map(RETURN_MAP, musicbrainz_translate[description], ids)
+                    if description.lower() in replaygain_translate:
+                        gains = [bytes(i).decode('utf-8', 'replace') for i in mutagen_file.tags[_key]]
+                        add_to_map(RETURN_MAP, replaygain_translate[description.lower()], gains)
+                if _tag == 'tmpo':
+                    bpm = [str(i) for i in mutagen_file.tags[_key]]
+                    add_to_map(RETURN_MAP, 'bpm', bpm)
                 if _tag == 'trkn':
                     _tup = mutagen_file.tags[_key][0]
                     if len(_tup) < 2:
@@ -43,7 +43,7 @@ class Importer():
                     song_uris.add(f)
         return self.get_coverart(song_uris)
 
-    def load_folder_both(self, directory) -> dict:
+    def load_folder_both(self, directory, progress=None) -> dict:
         logger.debug('\tload_folder')
         song_uris = set()
         for root, _, files in os.walk(directory): 
@@ -51,9 +51,18 @@ class Importer():
                 if self.is_song(filename) and not filename.startswith('.'):
                     f = os.path.join(root, filename) 
                     song_uris.add(f)
-        return self.get_both(song_uris)
+        return self.get_both(song_uris, progress)
 
-    def get_both(self, uris) -> dict:
+    def load_files_both(self, files, progress=None) -> dict:
+        logger.debug('\tload_files')
+        song_uris = set()
+        for f in files:
+            if self.is_song(f) and os.path.isfile(f):
+                song_uris.add(f)
+        return self.get_both(song_uris, progress)
+
+    # progress receives processed(uri) & failed(uri, reason) calls, processed returns True once the import is cancelled
+    def get_both(self, uris, progress=None) -> dict:
         logger.debug('\tget_metadata')
         TAGS = dict()
         COVER_ART = dict()
@@ -63,6 +72,9 @@ class Importer():
         logger.debug('\tget_metadata -> retrieving mutagen files')
         for song_uri in tqdm(uris):
             results.append(get_mutagen_file(song_uri))
+            if progress and progress.processed(song_uri):
+                logger.debug('\tget_metadata -> import cancelled')
+                break
         
         logger.debug('get_metadata -> getting coverart from mutagen files')
         for song_uri, mutagen_file in tqdm(results):
@@ -71,6 +83,8 @@ class Importer():
                     tags, cover_art = translate_and_cover_art(song_uri, mutagen_file)  
                     if tags:   
                         TAGS[song_uri] = tags
+                    elif progress:
+                        progress.failed(song_uri, "no tags")
 
                     if cover_art:
                         if type(cover_art) != bytes:
@@ -81,6 +95,10 @@ class Importer():
                     logger.error(f'exception on cover art extract: {song_uri}')
                     exc_type, exc_value, exc_tb = sys.exc_info()
                     logger.error(traceback.format_exception(exc_type, exc_value, exc_tb))
+                    if progress:
+                        progress.failed(song_uri, str(exc_value))
+            elif progress:
+                progress.failed(song_uri, "unreadable")
 
         return (TAGS, COVER_ART)
 
@@ -8,9 +8,14 @@ from loguru import logger
 logger.debug("in python main")
 importer = Importer()
 
-def tags_and_cover_art(directory_path):
+def tags_and_cover_art(directory_path, progress=None):
     logger.debug(f"retrieving tags & cover art from: {directory_path}")
-    BOTH_HASHMAP = importer.load_folder_both(directory_path)
+    BOTH_HASHMAP = importer.load_folder_both(directory_path, progress)
+    return BOTH_HASHMAP
+
+def tags_and_cover_art_files(file_paths, progress=None):
+    logger.debug(f"retrieving tags & cover art from {len(file_paths)} files")
+    BOTH_HASHMAP = importer.load_files_both(file_paths, progress)
     return BOTH_HASHMAP
 
 def tags(directory_path) -> dict:
@@ -92,7 +92,23 @@ asf_translate = {'WM/AlbumTitle': 'album',
                  'MusicBrainz/Work Id': 'musicbrainz_workid',
                  'MusicBrainz/Release Group Id': 'musicbrainz_releasegroupid',
                  'MusicBrainz/Original Album Id': 'musicbrainz_originalalbumid',
-                 'MusicBrainz/Original Artist Id': 'musicbrainz_originalartistid', 'MusicIP/PUID': 'musicip_puid', 'MusicBrainz/Album Status': 'releasestatus', 'MusicBrainz/Album Type': 'releasetype', 'MusicBrainz/Album Release Country': 'releasecountry', 'Acoustid/Id': 'acoustid_id', 'Acoustid/Fingerprint': 'acoustid_fingerprint', 'WM/IsCompilation': 'compilation', 'WM/Engineer': 'engineer', 'ASIN': 'asin', 'WM/DJMixer': 'djmixer', 'WM/Mixer': 'mixer', 'WM/ARTISTS': 'artists', 'WM/Director': 'director', 'WM/Work': 'work', 'WM/AuthorURL': 'website'}
+                 'MusicBrainz/Original Artist Id': 'musicbrainz_originalartistid', 'MusicIP/PUID': 'musicip_puid', 'MusicBrainz/Album Status': 'releasestatus', 'MusicBrainz/Album Type': 'releasetype', 'MusicBrainz/Album Release Country': 'releasecountry', 'Acoustid/Id': 'acoustid_id', 'Acoustid/Fingerprint': 'acoustid_fingerprint', 'WM/IsCompilation': 'compilation', 'WM/Engineer': 'engineer', 'ASIN': 'asin', 'WM/DJMixer': 'djmixer', 'WM/Mixer': 'mixer', 'WM/ARTISTS': 'artists', 'WM/Director': 'director', 'WM/Work': 'work', 'WM/AuthorURL': 'website', 'replaygain_track_gain': 'replaygain_track_gain', 'replaygain_track_peak': 'replaygain_track_peak', 'replaygain_album_gain': 'replaygain_album_gain', 'replaygain_album_peak': 'replaygain_album_peak'}
+# MusicBrainz ids, stored as TXXX frames in id3 & freeform atoms in mp4
+musicbrainz_translate = {
+    'MusicBrainz Album Id': 'musicbrainz_albumid',
+    'MusicBrainz Release Group Id': 'musicbrainz_releasegroupid',
+    'MusicBrainz Artist Id': 'musicbrainz_artistid',
+    'MusicBrainz Album Artist Id': 'musicbrainz_albumartistid',
+}
+
+# TXXX and freeform descriptions, matched lowercased as their case varies between taggers
+replaygain_translate = {
+    'replaygain_track_gain': 'replaygain_track_gain',
+    'replaygain_track_peak': 'replaygain_track_peak',
+    'replaygain_album_gain': 'replaygain_album_gain',
+    'replaygain_album_peak': 'replaygain_album_peak',
+}
+
 mp4_translate = {
     "\xa9ART": "artist",
     "\xa9nam": "title",
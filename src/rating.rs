@@ -0,0 +1,83 @@
+/* rating.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::{gio, glib::ToVariant};
+
+use crate::i18n::i18n;
+
+//Highest star rating
+pub const MAX_STARS: u8 = 5;
+
+//Favorite flag & star rating of a track or album.
+//Tracks are keyed on their file path & albums on their album key, so they survive rescans.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rating {
+    pub favorite: bool,
+    pub stars: u8,
+}
+
+impl Rating {
+    pub fn new(favorite: bool, stars: u8) -> Self {
+        Self {
+            favorite,
+            stars: stars.min(MAX_STARS),
+        }
+    }
+
+    //Nothing worth storing, the row is deleted instead
+    pub fn is_empty(&self) -> bool {
+        !self.favorite && self.stars == 0
+    }
+
+    pub fn with_stars(&self, stars: u8) -> Self {
+        Self::new(self.favorite, stars)
+    }
+
+    pub fn toggled_favorite(&self) -> Self {
+        Self::new(!self.favorite, self.stars)
+    }
+
+    //Most stars first, favorites first among the same stars
+    pub fn sort_key(&self) -> (u8, bool) {
+        (self.stars, self.favorite)
+    }
+
+    //0.0 to 1.0, as expected by xesam:userRating
+    pub fn user_rating(&self) -> f64 {
+        self.stars as f64 / MAX_STARS as f64
+    }
+}
+
+//Filled & empty stars, "★★★☆☆"
+pub fn stars_label(stars: u8) -> String {
+    let stars = stars.min(MAX_STARS) as usize;
+    format!("{}{}", "★".repeat(stars), "☆".repeat(MAX_STARS as usize - stars))
+}
+
+//Favorite toggle & stars submenu of a track or album, the rate action takes (id, stars)
+pub fn rating_menu(favorite_action: &str, rate_action: &str, id: i64, rating: Rating) -> gio::Menu {
+    let menu = gio::Menu::new();
+
+    let label = if rating.favorite {
+        i18n("Remove from Favorites")
+    } else {
+        i18n("Add to Favorites")
+    };
+    let menu_item = gio::MenuItem::new(Some(&label), None);
+    menu_item.set_action_and_target_value(Some(favorite_action), Some(&id.to_variant()));
+    menu.append_item(&menu_item);
+
+    let stars_menu = gio::Menu::new();
+    for stars in 0..=MAX_STARS {
+        let label = if stars == 0 { i18n("No Rating") } else { stars_label(stars) };
+        let menu_item = gio::MenuItem::new(Some(&label), None);
+        menu_item.set_action_and_target_value(Some(rate_action), Some(&(id, stars).to_variant()));
+        stars_menu.append_item(&menu_item);
+    }
+    menu.append_submenu(Some(&i18n("Rating")), &stars_menu);
+
+    menu
+}
@@ -62,7 +62,7 @@ mod imp {
     use std::cell::Cell;
 
     use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-    use gtk::glib::{self, ParamSpec, ParamSpecString, Value};
+    use gtk::glib::{self, ParamSpec, ParamSpecBoolean, ParamSpecString, Value};
     use once_cell::sync::Lazy;
 
     use crate::model::album::Album;
@@ -79,6 +79,8 @@ mod imp {
         pub search: RefCell<Option<String>>,
         pub type_: Cell<SearchSortObject>,
         pub method: Cell<SearchMethod>,
+        pub hide_compilations: Cell<bool>,
+        pub favorites_only: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -91,7 +93,11 @@ mod imp {
     impl ObjectImpl for FuzzyFilter {
         fn properties() -> &'static [ParamSpec] {
             static PROPERTIES: Lazy<Vec<ParamSpec>> =
-                Lazy::new(|| vec![ParamSpecString::builder("search").build()]);
+                Lazy::new(|| vec![
+                    ParamSpecString::builder("search").build(),
+                    ParamSpecBoolean::builder("hide-compilations").build(),
+                    ParamSpecBoolean::builder("favorites-only").build(),
+                ]);
             PROPERTIES.as_ref()
         }
 
@@ -103,6 +109,23 @@ mod imp {
                         .expect("Value must be a string");
                     self.obj().set_search(p);
                 }
+                "hide-compilations" => {
+                    let hide = value.get::<bool>().expect("Value must be a boolean");
+                    self.obj().set_hide_compilations(hide);
+                }
+                "favorites-only" => {
+                    let favorites_only = value.get::<bool>().expect("Value must be a boolean");
+                    self.obj().set_favorites_only(favorites_only);
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &ParamSpec) -> Value {
+            match pspec.name() {
+                "search" => self.search.borrow().to_value(),
+                "hide-compilations" => self.hide_compilations.get().to_value(),
+                "favorites-only" => self.favorites_only.get().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -119,6 +142,12 @@ mod imp {
             let search_key = match self.type_.get() {
                 SearchSortObject::Album => {
                     let album = search_obj.downcast_ref::<Album>().unwrap();
+                    if self.hide_compilations.get() && album.is_compilation() {
+                        return false;
+                    }
+                    if self.favorites_only.get() && !album.is_favorite() {
+                        return false;
+                    }
                     match self.method.get() {
                         SearchMethod::Full => album.search_string(),
                         SearchMethod::Track => album.title(),
@@ -130,6 +159,9 @@ mod imp {
                 },
                 SearchSortObject::Track => {
                     let track = search_obj.downcast_ref::<Track>().unwrap();
+                    if self.favorites_only.get() && !track.is_favorite() {
+                        return false;
+                    }
                     match self.method.get() {
                         SearchMethod::Full => track.search_string(),
                         SearchMethod::Track => track.title(),
@@ -153,6 +185,9 @@ mod imp {
                 },
                 SearchSortObject::Artist => {
                     let artist = search_obj.downcast_ref::<Artist>().unwrap();
+                    if self.hide_compilations.get() && artist.only_compilations() {
+                        return false;
+                    }
                     artist.name()
                 },
                 SearchSortObject::Genre => {
@@ -192,6 +227,31 @@ impl FuzzyFilter {
         self.imp().method.set(method)
     }
 
+    //Albums flagged as compilations, and artists only credited on them, are filtered out
+    pub fn set_hide_compilations(&self, hide: bool) {
+        let imp = self.imp();
+        if imp.hide_compilations.get() != hide {
+            imp.hide_compilations.set(hide);
+            self.changed(gtk::FilterChange::Different);
+        }
+    }
+
+    //Only the albums or tracks marked as favorite are kept
+    pub fn set_favorites_only(&self, favorites_only: bool) {
+        let imp = self.imp();
+        if imp.favorites_only.get() != favorites_only {
+            imp.favorites_only.set(favorites_only);
+            self.changed(gtk::FilterChange::Different);
+        }
+    }
+
+    //Favorites change without the items being replaced
+    pub fn refresh_ratings(&self) {
+        if self.imp().favorites_only.get() {
+            self.changed(gtk::FilterChange::Different);
+        }
+    }
+
     pub fn search(&self) -> Option<String> {
         self.imp().search.borrow().as_ref().map(ToString::to_string)
     }
@@ -0,0 +1,397 @@
+/* smart_playlist.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, rc::Rc};
+
+use crate::i18n::i18n;
+use crate::model::track::Track;
+use crate::util;
+
+//Condition a track has to meet to be part of a smart playlist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "field", content = "value", rename_all = "kebab-case")]
+pub enum SmartRule {
+    GenreIs(String),
+    YearBetween(i32, i32),
+    PlayCountAbove(u64),
+    NotPlayedInDays(u32),
+    RatingAtLeast(u8),
+    AddedInDays(u32),
+    FormatIs(String),
+}
+
+impl SmartRule {
+    //Rules in the order they are offered by the editor
+    pub fn fields() -> Vec<String> {
+        vec![
+            i18n("Genre is"),
+            i18n("Year is between"),
+            i18n("Play count is above"),
+            i18n("Not played in the last days"),
+            i18n("Rating is at least"),
+            i18n("Added in the last days"),
+            i18n("Format is"),
+        ]
+    }
+
+    pub fn field_index(&self) -> u32 {
+        match self {
+            Self::GenreIs(_) => 0,
+            Self::YearBetween(_, _) => 1,
+            Self::PlayCountAbove(_) => 2,
+            Self::NotPlayedInDays(_) => 3,
+            Self::RatingAtLeast(_) => 4,
+            Self::AddedInDays(_) => 5,
+            Self::FormatIs(_) => 6,
+        }
+    }
+
+    //Builds the rule of the editor field from its text & number values, unused values are ignored
+    pub fn from_field(index: u32, text: String, first: i64, second: i64) -> Self {
+        match index {
+            0 => Self::GenreIs(text),
+            1 => Self::YearBetween(first.min(second) as i32, first.max(second) as i32),
+            2 => Self::PlayCountAbove(first.max(0) as u64),
+            3 => Self::NotPlayedInDays(first.max(0) as u32),
+            4 => Self::RatingAtLeast(first.clamp(0, 5) as u8),
+            5 => Self::AddedInDays(first.max(0) as u32),
+            _ => Self::FormatIs(text),
+        }
+    }
+
+    pub fn matches(&self, track: &Track, now: i64) -> bool {
+        match self {
+            Self::GenreIs(genre) => {
+                let genre = genre.trim().to_lowercase();
+                track.genre_names()
+                    .iter()
+                    .any(|g| g.trim().to_lowercase() == genre)
+            },
+            Self::YearBetween(from, to) => match year(&track.date()) {
+                Some(year) => *from <= year && year <= *to,
+                None => false,
+            },
+            Self::PlayCountAbove(count) => track.play_count() > *count,
+            Self::NotPlayedInDays(days) => match track.last_played() {
+                Some(last_played) => last_played < now - Duration::days(*days as i64).num_seconds(),
+                None => true,
+            },
+            Self::RatingAtLeast(stars) => track.rating().stars >= *stars,
+            Self::AddedInDays(days) => track.added_time() >= now - Duration::days(*days as i64).num_seconds(),
+            Self::FormatIs(format) => {
+                normalize_format(&track.filetype()) == normalize_format(format)
+            },
+        }
+    }
+}
+
+//Order of the matching tracks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmartSort {
+    Title,
+    #[default]
+    Artist,
+    Album,
+    Year,
+    MostPlayed,
+    LeastPlayed,
+    LastPlayed,
+    RecentlyAdded,
+    Rating,
+}
+
+impl SmartSort {
+    pub fn labels() -> Vec<String> {
+        vec![
+            i18n("Title"),
+            i18n("Artist"),
+            i18n("Album"),
+            i18n("Year"),
+            i18n("Most Played"),
+            i18n("Least Played"),
+            i18n("Last Played"),
+            i18n("Recently Added"),
+            i18n("Rating"),
+        ]
+    }
+
+    pub fn new_from_index(index: u32) -> Self {
+        match index {
+            0 => Self::Title,
+            1 => Self::Artist,
+            2 => Self::Album,
+            3 => Self::Year,
+            4 => Self::MostPlayed,
+            5 => Self::LeastPlayed,
+            6 => Self::LastPlayed,
+            7 => Self::RecentlyAdded,
+            _ => Self::Rating,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        *self as u32
+    }
+
+    fn compare(&self, a: &Track, b: &Track) -> Ordering {
+        match self {
+            Self::Title => util::collate(&a.sort_title(), &b.sort_title()),
+            Self::Artist => util::collate(&a.sort_string(), &b.sort_string()),
+            Self::Album => util::collate(&a.sort_album(), &b.sort_album())
+                .then(a.disc_number().cmp(&b.disc_number()))
+                .then(a.track_number().cmp(&b.track_number())),
+            Self::Year => year(&a.date()).cmp(&year(&b.date()))
+                .then(util::collate(&a.sort_string(), &b.sort_string())),
+            Self::MostPlayed => b.play_count().cmp(&a.play_count()),
+            Self::LeastPlayed => a.play_count().cmp(&b.play_count()),
+            Self::LastPlayed => b.last_played().cmp(&a.last_played()),
+            Self::RecentlyAdded => b.added_time().cmp(&a.added_time()),
+            Self::Rating => b.rating().sort_key().cmp(&a.rating().sort_key()),
+        }
+    }
+}
+
+//Rules, match mode, order & size of a smart playlist, stored as json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartRules {
+    pub rules: Vec<SmartRule>,
+    pub match_all: bool,
+    pub sort: SmartSort,
+    //0 keeps every matching track
+    pub limit: u32,
+}
+
+impl Default for SmartRules {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            match_all: true,
+            sort: SmartSort::default(),
+            limit: 0,
+        }
+    }
+}
+
+impl SmartRules {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    //No rules matches the whole library
+    pub fn matches(&self, track: &Track, now: i64) -> bool {
+        if self.rules.is_empty() {
+            return true;
+        }
+        if self.match_all {
+            self.rules.iter().all(|rule| rule.matches(track, now))
+        } else {
+            self.rules.iter().any(|rule| rule.matches(track, now))
+        }
+    }
+
+    //Matching tracks in playlist order, cut to the limit
+    pub fn evaluate(&self, tracks: Vec<Rc<Track>>) -> Vec<Rc<Track>> {
+        let now = Utc::now().timestamp();
+        let mut tracks: Vec<Rc<Track>> = tracks.into_iter().filter(|track| self.matches(track, now)).collect();
+        tracks.sort_by(|a, b| self.sort.compare(a, b).then(a.id().cmp(&b.id())));
+        if self.limit > 0 {
+            tracks.truncate(self.limit as usize);
+        }
+        tracks
+    }
+}
+
+//Leading year of a date tag, "2004-05-01" or "2004"
+fn year(date: &str) -> Option<i32> {
+    let digits: String = date.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() == 4 {
+        digits.parse::<i32>().ok()
+    } else {
+        None
+    }
+}
+
+//".FLAC", "flac" & "FLAC" are the same format
+fn normalize_format(format: &str) -> String {
+    format.trim().trim_start_matches('.').to_lowercase()
+}
+
+//Albums kept in the recently added collection
+pub const RECENT_ALBUMS_LIMIT: usize = 10;
+
+//Tracks kept in the recently played collection
+pub const RECENT_TRACKS_LIMIT: usize = 50;
+
+//Collections kept up to date from the library & plays, they are never stored in the database
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutoPlaylist {
+    RecentlyAdded,
+    RecentlyPlayed,
+    NeverPlayed,
+}
+
+impl AutoPlaylist {
+    pub fn all() -> [AutoPlaylist; 3] {
+        [Self::RecentlyAdded, Self::RecentlyPlayed, Self::NeverPlayed]
+    }
+
+    //Playlist ids far below the negated smart playlist ids
+    pub fn id(&self) -> i64 {
+        i64::MIN + 1 + *self as i64
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Self::RecentlyAdded => i18n("Recently Added"),
+            Self::RecentlyPlayed => i18n("Recently Played"),
+            Self::NeverPlayed => i18n("Never Played"),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Self::RecentlyAdded => i18n("The albums most recently added to the library"),
+            Self::RecentlyPlayed => i18n("The tracks played last, most recent first"),
+            Self::NeverPlayed => i18n("Tracks in the library that were never played"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rating::Rating;
+    use crate::statistics::PlayStats;
+
+    const NOW: i64 = 1_700_000_000;
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn track(id: i64, title: &str, date: &str, filetype: &str) -> Rc<Track> {
+        util::set_sort_articles(&["the"]);
+        Rc::new(Track::new(
+            id, title.to_string(), "Album".to_string(), "Artist".to_string(), filetype.to_string(),
+            format!("/music/{}.flac", id), date.to_string(), "Rock".to_string(), 180.0, id, 1,
+        ))
+    }
+
+    #[test]
+    fn test_year() {
+        assert_eq!(year("2004-05-01"), Some(2004));
+        assert_eq!(year(" 1999 "), Some(1999));
+        assert_eq!(year("04"), None);
+        assert_eq!(year("20041"), None);
+        assert_eq!(year(""), None);
+    }
+
+    #[test]
+    fn test_normalize_format() {
+        assert_eq!(normalize_format(".FLAC"), "flac");
+        assert_eq!(normalize_format(" flac "), "flac");
+        assert_eq!(normalize_format("Mp3"), "mp3");
+    }
+
+    #[test]
+    fn test_rules_json() {
+        let rules = SmartRules {
+            rules: vec![
+                SmartRule::GenreIs("Jazz".to_string()),
+                SmartRule::YearBetween(1990, 1999),
+                SmartRule::NotPlayedInDays(30),
+            ],
+            match_all: false,
+            sort: SmartSort::MostPlayed,
+            limit: 25,
+        };
+
+        let json = rules.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"rules":[{"field":"genre-is","value":"Jazz"},{"field":"year-between","value":[1990,1999]},{"field":"not-played-in-days","value":30}],"match_all":false,"sort":"most-played","limit":25}"#
+        );
+        assert_eq!(SmartRules::from_json(&json).unwrap(), rules);
+        assert!(SmartRules::from_json(r#"{"rules":[{"field":"unknown","value":1}],"match_all":true,"sort":"title","limit":0}"#).is_err());
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let track = track(1, "Song", "1995-03-01", ".FLAC");
+        track.set_play_stats(PlayStats {
+            play_count: 3,
+            last_played: Some(NOW - 10 * DAY),
+            skip_count: 0,
+            added_time: NOW - 2 * DAY,
+        });
+        track.set_rating(Rating::new(false, 4));
+
+        assert!(SmartRule::YearBetween(1990, 1999).matches(&track, NOW));
+        assert!(!SmartRule::YearBetween(2000, 2010).matches(&track, NOW));
+        assert!(SmartRule::PlayCountAbove(2).matches(&track, NOW));
+        assert!(!SmartRule::PlayCountAbove(3).matches(&track, NOW));
+        assert!(SmartRule::NotPlayedInDays(5).matches(&track, NOW));
+        assert!(!SmartRule::NotPlayedInDays(30).matches(&track, NOW));
+        assert!(SmartRule::RatingAtLeast(4).matches(&track, NOW));
+        assert!(!SmartRule::RatingAtLeast(5).matches(&track, NOW));
+        assert!(SmartRule::AddedInDays(7).matches(&track, NOW));
+        assert!(!SmartRule::AddedInDays(1).matches(&track, NOW));
+        assert!(SmartRule::FormatIs("flac".to_string()).matches(&track, NOW));
+        assert!(!SmartRule::FormatIs("mp3".to_string()).matches(&track, NOW));
+
+        //never played tracks were not played in any number of days
+        let never_played = self::track(2, "Other", "", "mp3");
+        assert!(SmartRule::NotPlayedInDays(0).matches(&never_played, NOW));
+        assert!(!SmartRule::YearBetween(0, 3000).matches(&never_played, NOW));
+    }
+
+    #[test]
+    fn test_rules_matches() {
+        let track = track(1, "Song", "1995", "flac");
+        let rules = |match_all: bool, rules: Vec<SmartRule>| SmartRules { rules, match_all, ..SmartRules::default() };
+
+        //no rules matches the whole library
+        assert!(rules(true, vec![]).matches(&track, NOW));
+        assert!(rules(false, vec![]).matches(&track, NOW));
+
+        let mixed = vec![SmartRule::YearBetween(1990, 1999), SmartRule::FormatIs("mp3".to_string())];
+        assert!(!rules(true, mixed.clone()).matches(&track, NOW));
+        assert!(rules(false, mixed).matches(&track, NOW));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let tracks = vec![
+            track(3, "Beta", "2001", "flac"),
+            track(1, "Alpha", "2001", "flac"),
+            track(2, "Alpha", "2001", "flac"),
+            track(4, "The Alpha", "2001", "mp3"),
+        ];
+        let ids = |tracks: Vec<Rc<Track>>| tracks.iter().map(|track| track.id()).collect::<Vec<i64>>();
+
+        //same titles are ordered by id, the leading article is ignored
+        let rules = SmartRules { sort: SmartSort::Title, ..SmartRules::default() };
+        assert_eq!(ids(rules.evaluate(tracks.clone())), vec![1, 2, 4, 3]);
+
+        let rules = SmartRules {
+            rules: vec![SmartRule::FormatIs("flac".to_string())],
+            sort: SmartSort::Title,
+            limit: 2,
+            ..SmartRules::default()
+        };
+        assert_eq!(ids(rules.evaluate(tracks.clone())), vec![1, 2]);
+
+        tracks[0].add_play(NOW);
+        tracks[0].add_play(NOW);
+        tracks[2].add_play(NOW);
+        let rules = SmartRules { sort: SmartSort::MostPlayed, limit: 3, ..SmartRules::default() };
+        assert_eq!(ids(rules.evaluate(tracks)), vec![3, 2, 1]);
+    }
+}
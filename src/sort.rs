@@ -23,6 +23,7 @@
 use gtk::{glib, prelude::*, subclass::prelude::*};
 use log::debug;
 use super::search::SearchSortObject;
+use super::util;
 
 #[derive(Debug, Clone, Copy, PartialEq, glib::Enum)]
 #[enum_type(name = "SortMethod")]
@@ -37,6 +38,11 @@ pub enum SortMethod {
     TrackCount,
     AlbumCount,
     LastModified,
+    Bpm,
+    PlayCount,
+    LastPlayed,
+    RecentlyAdded,
+    Rating,
 }
 
 impl Default for SortMethod {
@@ -194,6 +200,128 @@ mod imp {
                         gtk::Ordering::Equal
                     }
                 },
+                SortMethod::Bpm => {
+                    let (item1_key, item2_key) = match self.type_.get() {
+                        SearchSortObject::Track => {
+                            let item1 = item1.downcast_ref::<Track>().unwrap();
+                            let item2 = item2.downcast_ref::<Track>().unwrap();
+
+                            (item1.bpm(), item2.bpm())
+                        },
+                        _ => unimplemented!("no sorting for")
+
+                    };
+
+                    //slowest first, tracks without a tempo last
+                    match (item1_key, item2_key) {
+                        (Some(bpm1), Some(bpm2)) => bpm1.total_cmp(&bpm2).into(),
+                        (Some(_), None) => gtk::Ordering::Smaller,
+                        (None, Some(_)) => gtk::Ordering::Larger,
+                        (None, None) => gtk::Ordering::Equal,
+                    }
+                },
+                SortMethod::PlayCount => {
+                    let (item1_key, item2_key) = match self.type_.get() {
+                        SearchSortObject::Album => {
+                            let item1 = item1.downcast_ref::<Album>().unwrap();
+                            let item2 = item2.downcast_ref::<Album>().unwrap();
+
+                            (item1.play_count(), item2.play_count())
+                        },
+                        SearchSortObject::Track => {
+                            let item1 = item1.downcast_ref::<Track>().unwrap();
+                            let item2 = item2.downcast_ref::<Track>().unwrap();
+
+                            (item1.play_count(), item2.play_count())
+                        },
+                        SearchSortObject::Playlist => {
+                            let item1 = item1.downcast_ref::<Playlist>().unwrap();
+                            let item2 = item2.downcast_ref::<Playlist>().unwrap();
+
+                            (item1.play_count(), item2.play_count())
+                        },
+                        _ => unimplemented!("no sorting for")
+
+                    };
+
+                    //most played first
+                    item2_key.cmp(&item1_key).into()
+                },
+                SortMethod::LastPlayed => {
+                    let (item1_key, item2_key) = match self.type_.get() {
+                        SearchSortObject::Album => {
+                            let item1 = item1.downcast_ref::<Album>().unwrap();
+                            let item2 = item2.downcast_ref::<Album>().unwrap();
+
+                            (item1.last_played(), item2.last_played())
+                        },
+                        SearchSortObject::Track => {
+                            let item1 = item1.downcast_ref::<Track>().unwrap();
+                            let item2 = item2.downcast_ref::<Track>().unwrap();
+
+                            (item1.last_played(), item2.last_played())
+                        },
+                        SearchSortObject::Playlist => {
+                            let item1 = item1.downcast_ref::<Playlist>().unwrap();
+                            let item2 = item2.downcast_ref::<Playlist>().unwrap();
+
+                            (item1.last_played(), item2.last_played())
+                        },
+                        _ => unimplemented!("no sorting for")
+
+                    };
+
+                    //most recent first, never played last
+                    item2_key.cmp(&item1_key).into()
+                },
+                SortMethod::RecentlyAdded => {
+                    let (item1_key, item2_key) = match self.type_.get() {
+                        SearchSortObject::Album => {
+                            let item1 = item1.downcast_ref::<Album>().unwrap();
+                            let item2 = item2.downcast_ref::<Album>().unwrap();
+
+                            (item1.added_time(), item2.added_time())
+                        },
+                        SearchSortObject::Track => {
+                            let item1 = item1.downcast_ref::<Track>().unwrap();
+                            let item2 = item2.downcast_ref::<Track>().unwrap();
+
+                            (item1.added_time(), item2.added_time())
+                        },
+                        SearchSortObject::Playlist => {
+                            let item1 = item1.downcast_ref::<Playlist>().unwrap();
+                            let item2 = item2.downcast_ref::<Playlist>().unwrap();
+
+                            (item1.creation_time().timestamp(), item2.creation_time().timestamp())
+                        },
+                        _ => unimplemented!("no sorting for")
+
+                    };
+
+                    //newest first
+                    item2_key.cmp(&item1_key).into()
+                },
+                SortMethod::Rating => {
+                    let (item1_key, item2_key) = match self.type_.get() {
+                        SearchSortObject::Album => {
+                            let item1 = item1.downcast_ref::<Album>().unwrap();
+                            let item2 = item2.downcast_ref::<Album>().unwrap();
+
+                            (item1.rating().sort_key(), item2.rating().sort_key())
+                        },
+                        SearchSortObject::Track => {
+                            let item1 = item1.downcast_ref::<Track>().unwrap();
+                            let item2 = item2.downcast_ref::<Track>().unwrap();
+
+                            (item1.rating().sort_key(), item2.rating().sort_key())
+                        },
+                        _ => unimplemented!("no sorting for")
+
+                    };
+
+                    //highest rated first, unrated last
+                    item2_key.cmp(&item1_key).into()
+                },
                 SortMethod::LastModified => {
                     let (item1_key, item2_key) = match self.type_.get() {
                         SearchSortObject::Playlist => {
@@ -285,13 +413,7 @@ mod imp {
                         }
                     } 
         
-                    if item1_key > item2_key {
-                        gtk::Ordering::Larger
-                    } else if item1_key < item2_key  {
-                        gtk::Ordering::Smaller
-                    } else {
-                        gtk::Ordering::Equal
-                    }
+                    util::collate(&item1_key, &item2_key).into()
                 }
             }
 
@@ -324,6 +446,20 @@ impl FuzzySorter {
         self.changed(gtk::SorterChange::Different);
     }
 
+    //Plays change without the items being replaced, re-sorts when the order depends on them
+    pub fn refresh_plays(&self) {
+        if matches!(self.imp().method.get(), SortMethod::PlayCount | SortMethod::LastPlayed) {
+            self.changed(gtk::SorterChange::Different);
+        }
+    }
+
+    //Ratings change without the items being replaced
+    pub fn refresh_ratings(&self) {
+        if self.imp().method.get() == SortMethod::Rating {
+            self.changed(gtk::SorterChange::Different);
+        }
+    }
+
     pub fn search(&self) -> Option<String> {
         self.imp().search.borrow().as_ref().map(ToString::to_string)
     }
@@ -0,0 +1,127 @@
+/* statistics.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use chrono::{Duration, Utc};
+
+use crate::i18n::i18n_k;
+
+//Entries shown in each of the top lists
+pub const TOP_LIMIT: usize = 10;
+
+//Periods shown in the plays per day, week or month list
+pub const PERIOD_LIMIT: usize = 12;
+
+//Time range the statistics are computed over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsRange {
+    LastWeek,
+    LastYear,
+    AllTime,
+}
+
+impl Default for StatsRange {
+    fn default() -> Self {
+        Self::LastWeek
+    }
+}
+
+impl StatsRange {
+    pub fn new_from_index(index: u32) -> Self {
+        match index {
+            0 => Self::LastWeek,
+            1 => Self::LastYear,
+            _ => Self::AllTime,
+        }
+    }
+
+    //Timestamp of the oldest play counted
+    pub fn since(&self) -> i64 {
+        match self {
+            Self::LastWeek => (Utc::now() - Duration::days(7)).timestamp(),
+            Self::LastYear => (Utc::now() - Duration::days(365)).timestamp(),
+            Self::AllTime => 0,
+        }
+    }
+}
+
+//Grouping of the plays over time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl Default for StatsPeriod {
+    fn default() -> Self {
+        Self::Day
+    }
+}
+
+impl StatsPeriod {
+    pub fn new_from_index(index: u32) -> Self {
+        match index {
+            0 => Self::Day,
+            1 => Self::Week,
+            _ => Self::Month,
+        }
+    }
+
+    //sqlite strftime format of the period keys, they sort in chronological order
+    pub fn format(&self) -> &'static str {
+        match self {
+            Self::Day => "%Y-%m-%d",
+            Self::Week => "%Y-%W",
+            Self::Month => "%Y-%m",
+        }
+    }
+
+    //Row title of a period key, week keys are numeric so the label can be translated
+    pub fn label(&self, key: &str) -> String {
+        match (self, key.split_once('-')) {
+            (Self::Week, Some((year, week))) => {
+                let week = week.parse::<u32>().map(|week| week.to_string()).unwrap_or_else(|_| week.to_string());
+                // Translators: do not replace {week} or {year}
+                i18n_k("Week {week}, {year}", &[("week", &week), ("year", year)])
+            },
+            _ => key.to_string(),
+        }
+    }
+}
+
+//Play counts of the range, each top list holds (id, plays) most played first
+#[derive(Debug, Clone, Default)]
+pub struct ListeningStats {
+    pub total_plays: u64,
+    pub total_seconds: f64,
+    pub top_tracks: Vec<(i64, u64)>,
+    pub top_albums: Vec<(i64, u64)>,
+    pub top_artists: Vec<(i64, u64)>,
+    pub top_genres: Vec<(i64, u64)>,
+    pub plays_per_period: Vec<(String, u64)>,
+}
+
+//Plays & skips of a single track, kept on the track for sorting
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlayStats {
+    pub play_count: u64,
+    pub last_played: Option<i64>,
+    pub skip_count: u64,
+    pub added_time: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_label() {
+        assert_eq!(StatsPeriod::Week.label("2023-07"), "Week 7, 2023");
+        assert_eq!(StatsPeriod::Week.label("2023-00"), "Week 0, 2023");
+        assert_eq!(StatsPeriod::Day.label("2023-02-14"), "2023-02-14");
+        assert_eq!(StatsPeriod::Month.label("2023-02"), "2023-02");
+    }
+}
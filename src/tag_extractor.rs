@@ -0,0 +1,376 @@
+/* tag_extractor.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::glib::translate::IntoGlib;
+use gst_pbutils::prelude::*;
+
+use std::cell::RefCell;
+use std::{collections::HashMap, error::Error, path::Path};
+use log::{debug, error, warn};
+use pyo3::{prelude::*, types::PyTuple};
+
+use super::import_progress::ProgressReporter;
+use super::importer::{MapVal, EXTENDED_TAGS};
+
+//Tag map of every file, keyed by uri then by value type ie "str_list" or "float", as built by extracting.translate
+pub type TagMap = HashMap<String, HashMap<String, HashMap<String, MapVal>>>;
+
+//Embedded cover art of every file, keyed by uri
+pub type CoverArtMap = HashMap<String, Vec<u8>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TagExtractorKind {
+    Mutagen,
+    GStreamer,
+}
+
+impl Default for TagExtractorKind {
+    fn default() -> Self {
+        Self::Mutagen
+    }
+}
+
+impl TagExtractorKind {
+    pub fn new_from_settings(value: i32) -> Self {
+        match value {
+            0 => Self::Mutagen,
+            1 => Self::GStreamer,
+            _ => Self::default(),
+        }
+    }
+
+    //Mutagen falls back on GStreamer when the python modules it needs are not installed
+    pub fn extractor(&self) -> Box<dyn TagExtractor> {
+        match self {
+            Self::Mutagen => {
+                if MutagenExtractor::available() {
+                    Box::new(MutagenExtractor)
+                } else {
+                    warn!("mutagen, tqdm or loguru python modules missing, extracting tags with GStreamer");
+                    Box::new(DiscovererExtractor)
+                }
+            },
+            Self::GStreamer => Box::new(DiscovererExtractor),
+        }
+    }
+}
+
+//Reads the tags & cover art of music files, into the map Importer builds the database from
+pub trait TagExtractor {
+    fn name(&self) -> &'static str;
+
+    //Stops early when the import is cancelled, returning the files read so far
+    fn extract_files(&self, files: Vec<String>, progress: &mut ProgressReporter) -> Result<(TagMap, CoverArtMap), Box<dyn Error>>;
+}
+
+#[pyclass]
+struct LoggingStdout;
+
+#[pymethods]
+impl LoggingStdout {
+    #[new]
+    fn new() -> Self {
+        Self
+    }
+
+    fn write(&self, data: &str) {
+        //println!("stdout from python: {:?}", data);
+        if data.contains("ERROR") || data.contains("Exception") {
+            error!("{}", data);
+        } else if data.contains("DEBUG") {
+            debug!("{}", data);
+        }
+    }
+}
+
+//Progress callbacks of the python importer
+#[pyclass]
+struct PyProgress {
+    reporter: RefCell<ProgressReporter>,
+}
+
+#[pymethods]
+impl PyProgress {
+    //Returns whether the import was cancelled
+    fn processed(&self, file: &str) -> bool {
+        let mut reporter = self.reporter.borrow_mut();
+        reporter.processed(file);
+        reporter.is_cancelled()
+    }
+
+    fn failed(&self, file: &str, reason: &str) {
+        self.reporter.borrow().file_failed(file, reason);
+    }
+}
+
+//Runs the mutagen scripts of src/python in the embedded interpreter
+pub struct MutagenExtractor;
+
+impl MutagenExtractor {
+    pub fn available() -> bool {
+        Python::with_gil(|py| {
+            ["mutagen", "tqdm", "loguru"].iter().all(|module| match py.import(*module) {
+                Ok(_) => true,
+                Err(e) => {
+                    debug!("python module {} unavailable: {}", module, e);
+                    false
+                },
+            })
+        })
+    }
+}
+
+impl TagExtractor for MutagenExtractor {
+    fn name(&self) -> &'static str {
+        "mutagen"
+    }
+
+    fn extract_files(&self, files: Vec<String>, progress: &mut ProgressReporter) -> Result<(TagMap, CoverArtMap), Box<dyn Error>> {
+        Python::with_gil(|py| {
+            let sys = py.import("sys")?;
+            sys.setattr("stderr", LoggingStdout::new().into_py(py))?;
+
+            let py_progress = Py::new(py, PyProgress { reporter: RefCell::new(progress.clone()) })?;
+            let result = extract_with_python(py, "tags_and_cover_art_files", (files, py_progress.clone_ref(py)));
+
+            //keep the processed count for the next batch
+            *progress = py_progress.borrow(py).reporter.borrow().clone();
+            result
+        })
+    }
+}
+
+//Runs one of the extraction functions of main.py, returning the tag map & cover art of every file
+fn extract_with_python<A: IntoPy<Py<PyTuple>>>(py: Python, function: &str, args: A) -> Result<(TagMap, CoverArtMap), Box<dyn Error>> {
+    let code_main = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/main.py"));
+    let code_extracting = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/extracting.py"));
+    let code_importer = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/importer.py"));
+    let code_translate_dicts = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/translate_dicts.py"));
+    let code_util = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/python/util.py"));
+
+    PyModule::from_code(py, code_util, "util", "util")?;
+    PyModule::from_code(py, code_translate_dicts,"translate_dicts", "translate_dicts")?;
+    PyModule::from_code(py, code_extracting, "extracting", "extracting")?;
+    PyModule::from_code(py, code_importer, "importer", "importer")?;
+
+    let module = PyModule::from_code(py, code_main, "main", "")?;
+    let object: Py<PyAny> = module
+        .getattr(function)?
+        .call1(args)
+        .map_err(|e| {
+            e.print_and_set_sys_last_vars(py);
+            e
+        })?
+        .into();
+
+    debug!("retrieving tags & cover art ... ");
+
+    let all: (TagMap, HashMap<String, &[u8]>) = object.extract(py)?;
+    let (tags, bytes_map) = all;
+
+    let mut vec_byte_map: CoverArtMap = HashMap::new();
+    for (uri, b) in bytes_map.iter() {
+        vec_byte_map.insert(uri.clone(), (*b).to_vec());
+    }
+
+    Ok((tags, vec_byte_map))
+}
+
+//Reads the tags GStreamer demuxers & parsers find, needs no python module
+pub struct DiscovererExtractor;
+
+impl TagExtractor for DiscovererExtractor {
+    fn name(&self) -> &'static str {
+        "gstreamer"
+    }
+
+    fn extract_files(&self, files: Vec<String>, progress: &mut ProgressReporter) -> Result<(TagMap, CoverArtMap), Box<dyn Error>> {
+        let discoverer = gst_pbutils::Discoverer::new(gst::ClockTime::from_seconds(10))?;
+
+        let mut tags = TagMap::new();
+        let mut cover_art = CoverArtMap::new();
+
+        for path in files {
+            if progress.is_cancelled() {
+                break;
+            }
+            progress.processed(&path);
+
+            let uri = match gtk::glib::filename_to_uri(&path, None) {
+                Ok(uri) => uri,
+                Err(e) => {
+                    error!("(URI) Could not parse: {}: {}", path, e);
+                    progress.file_failed(&path, &e.to_string());
+                    continue;
+                },
+            };
+
+            let info = match discoverer.discover_uri(&uri) {
+                Ok(info) => info,
+                Err(e) => {
+                    error!("(DISCOVERER) Could not parse: {}: {}", path, e);
+                    progress.file_failed(&path, &e.to_string());
+                    continue;
+                },
+            };
+
+            let tag_list = info.tags().or_else(|| {
+                info.audio_streams().first().and_then(|stream| stream.tags())
+            });
+
+            let duration = info.duration().map(|d| d.mseconds() as f32 / 1000.0);
+            tags.insert(path.clone(), translate(&path, tag_list.as_ref(), duration));
+
+            if let Some(image) = tag_list.as_ref().and_then(cover_art_bytes) {
+                cover_art.insert(path, image);
+            }
+        }
+
+        Ok((tags, cover_art))
+    }
+}
+
+//Musicbrainz tags have no typed gstreamer tag, they are read by name
+const MUSICBRAINZ_TAGS: [(&str, &str); 4] = [
+    ("musicbrainz-albumid", "musicbrainz_albumid"),
+    ("musicbrainz-releasegroupid", "musicbrainz_releasegroupid"),
+    ("musicbrainz-artistid", "musicbrainz_artistid"),
+    ("musicbrainz-albumartistid", "musicbrainz_albumartistid"),
+];
+
+//Builds the same map as extracting.translate, with the same fallbacks for the essential tags
+fn translate(path: &str, tag_list: Option<&gst::TagList>, duration: Option<f32>) -> HashMap<String, HashMap<String, MapVal>> {
+    let mut strings: HashMap<String, Vec<String>> = HashMap::new();
+
+    if let Some(tag_list) = tag_list {
+        add_strings::<gst::tags::Title>(&mut strings, "title", tag_list);
+        add_strings::<gst::tags::Artist>(&mut strings, "artist", tag_list);
+        add_strings::<gst::tags::AlbumArtist>(&mut strings, "albumartist", tag_list);
+        add_strings::<gst::tags::Album>(&mut strings, "album", tag_list);
+        add_strings::<gst::tags::Genre>(&mut strings, "genre", tag_list);
+        add_strings::<gst::tags::Composer>(&mut strings, "composer", tag_list);
+        add_strings::<gst::tags::Conductor>(&mut strings, "conductor", tag_list);
+        add_strings::<gst::tags::InterpretedBy>(&mut strings, "remixer", tag_list);
+        add_strings::<gst::tags::Publisher>(&mut strings, "label", tag_list);
+        add_strings::<gst::tags::LanguageCode>(&mut strings, "language", tag_list);
+        add_strings::<gst::tags::TitleSortname>(&mut strings, "titlesort", tag_list);
+        add_strings::<gst::tags::ArtistSortname>(&mut strings, "artistsort", tag_list);
+        add_strings::<gst::tags::AlbumSortname>(&mut strings, "albumsort", tag_list);
+        add_strings::<gst::tags::AlbumArtistSortname>(&mut strings, "albumartistsort", tag_list);
+        add_strings::<gst::tags::ComposerSortname>(&mut strings, "composersort", tag_list);
+
+        if let Some(date) = tag_list.get::<gst::tags::DateTime>().and_then(|d| d.get().to_iso8601_string().ok()) {
+            add_string(&mut strings, "date", date.to_string());
+        } else if let Some(date) = tag_list.get::<gst::tags::Date>() {
+            let date = date.get();
+            add_string(&mut strings, "date", format!("{:04}-{:02}-{:02}", date.year(), date.month().into_glib(), date.day()));
+        }
+
+        if let Some(number) = tag_list.get::<gst::tags::TrackNumber>() {
+            let count = tag_list.get::<gst::tags::TrackCount>().map(|c| c.get());
+            add_string(&mut strings, "tracknumber", number_string(number.get(), count));
+        }
+        if let Some(number) = tag_list.get::<gst::tags::AlbumVolumeNumber>() {
+            let count = tag_list.get::<gst::tags::AlbumVolumeCount>().map(|c| c.get());
+            add_string(&mut strings, "discnumber", number_string(number.get(), count));
+        }
+
+        if let Some(bpm) = tag_list.get::<gst::tags::BeatsPerMinute>() {
+            add_string(&mut strings, "bpm", format!("{}", bpm.get()));
+        }
+        if let Some(gain) = tag_list.get::<gst::tags::TrackGain>() {
+            add_string(&mut strings, "replaygain_track_gain", format!("{:.2} dB", gain.get()));
+        }
+        if let Some(peak) = tag_list.get::<gst::tags::TrackPeak>() {
+            add_string(&mut strings, "replaygain_track_peak", format!("{:.6}", peak.get()));
+        }
+        if let Some(gain) = tag_list.get::<gst::tags::AlbumGain>() {
+            add_string(&mut strings, "replaygain_album_gain", format!("{:.2} dB", gain.get()));
+        }
+        if let Some(peak) = tag_list.get::<gst::tags::AlbumPeak>() {
+            add_string(&mut strings, "replaygain_album_peak", format!("{:.6}", peak.get()));
+        }
+
+        for (tag_name, key) in MUSICBRAINZ_TAGS {
+            for value in tag_list.iter_tag_generic(tag_name) {
+                if let Ok(id) = value.get::<String>() {
+                    add_string(&mut strings, key, id);
+                }
+            }
+        }
+
+        //vorbis comments & ape tags gstreamer has no tag for are kept as "KEY=value"
+        for comment in tag_list.iter_tag::<gst::tags::ExtendedComment>() {
+            if let Some((key, value)) = comment.get().split_once('=') {
+                let key = key.split('[').next().unwrap_or(key).trim().to_lowercase();
+                if (key == "compilation" || EXTENDED_TAGS.contains(&key.as_str())) && !strings.contains_key(&key) {
+                    add_string(&mut strings, &key, value.to_string());
+                }
+            }
+        }
+    }
+
+    if !strings.contains_key("title") {
+        let file_name = Path::new(path).file_name().map_or(path.to_string(), |name| name.to_string_lossy().to_string());
+        add_string(&mut strings, "title", file_name);
+    }
+
+    if !strings.contains_key("albumartist") {
+        let artists = strings.get("artist").cloned().unwrap_or_else(|| vec!["Unknown Artist".to_string()]);
+        strings.insert("albumartist".to_string(), artists);
+    }
+
+    if !strings.contains_key("album") {
+        add_string(&mut strings, "album", "Unknown Album".to_string());
+    }
+
+    let mut map = HashMap::new();
+    map.insert(
+        "str_list".to_string(),
+        strings.into_iter().map(|(key, values)| (key, MapVal::List(values))).collect::<HashMap<String, MapVal>>(),
+    );
+
+    let filetype = Path::new(path).extension().map_or(String::new(), |ext| format!(".{}", ext.to_string_lossy()));
+    map.insert("str".to_string(), HashMap::from([("filetype_".to_string(), MapVal::Str(filetype))]));
+
+    if let Some(duration) = duration {
+        map.insert("float".to_string(), HashMap::from([("duration".to_string(), MapVal::Float(duration))]));
+    }
+
+    map
+}
+
+fn add_strings<'a, T: gst::tags::Tag<'a, TagType = &'a str>>(strings: &mut HashMap<String, Vec<String>>, key: &str, tag_list: &'a gst::TagList) {
+    for value in tag_list.iter_tag::<T>() {
+        add_string(strings, key, value.get().to_string());
+    }
+}
+
+fn add_string(strings: &mut HashMap<String, Vec<String>>, key: &str, value: String) {
+    let value = value.trim();
+    if !value.is_empty() {
+        strings.entry(key.to_string()).or_default().push(value.to_string());
+    }
+}
+
+//Same "number/total" form as the id3 TRCK & TPOS frames
+fn number_string(number: u32, count: Option<u32>) -> String {
+    match count {
+        Some(count) if count > 0 => format!("{}/{}", number, count),
+        _ => number.to_string(),
+    }
+}
+
+//First embedded image, the preview image when the file has no full size one
+fn cover_art_bytes(tag_list: &gst::TagList) -> Option<Vec<u8>> {
+    let sample = match tag_list.get::<gst::tags::Image>() {
+        Some(image) => image.get(),
+        None => tag_list.get::<gst::tags::PreviewImage>()?.get(),
+    };
+    let buffer = sample.buffer()?;
+    let map = buffer.map_readable().ok()?;
+    Some(map.as_slice().to_vec())
+}
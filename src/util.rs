@@ -8,7 +8,8 @@ use gtk::prelude::*;
 use gtk::gio;
 use gtk::{gdk, glib, gdk_pixbuf::Pixbuf};
 
-use std::rc::Rc;
+use std::{cell::RefCell, cmp::Ordering, fs, path::{Path, PathBuf}, rc::Rc};
+use log::error;
 
 use color_thief::{get_palette, ColorFormat};
 use scraper::{Html, Selector};
@@ -99,13 +100,92 @@ pub fn fetch_artist_image_discog(artist_name: &str) -> Option<(String, Vec<u8>)>
 
 
 pub fn load_cover_art_pixbuf(cover_art_id: i64, size: i32) -> Result<RoundedAlbumArt, String> {
-    let cover_art = model().cover_art(cover_art_id)?;
-    let pixbuf = cover_art.pixbuf()?;
+    model().cover_art(cover_art_id)?;
     let art = RoundedAlbumArt::new(size);
-    art.load(pixbuf);
+    art.load_cover_art(cover_art_id);
     Ok(art)
 }
 
+thread_local! {
+    //Articles of the "sort-articles" setting, read on first use
+    static SORT_ARTICLES: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+//Lowercase name used for sorting, without a leading article like "The" or "L'"
+pub fn sort_name(name: &str) -> String {
+    let lowercase_name = name.trim().to_lowercase();
+
+    SORT_ARTICLES.with(|articles| {
+        let mut articles = articles.borrow_mut();
+        let articles = articles.get_or_insert_with(|| {
+            settings_manager()
+                .string("sort-articles")
+                .split_whitespace()
+                .map(|article| article.to_lowercase())
+                .collect()
+        });
+
+        for article in articles.iter() {
+            if let Some(rest) = lowercase_name.strip_prefix(article.as_str()) {
+                //elided articles are glued to the name, the others are followed by a space
+                let elided = article.ends_with('\'') || article.ends_with('’');
+                if !rest.is_empty() && (elided || rest.starts_with(char::is_whitespace)) {
+                    return rest.trim_start().to_string();
+                }
+            }
+        }
+
+        lowercase_name.clone()
+    })
+}
+
+//Rereads the articles after the setting changed
+pub fn reset_sort_articles() {
+    SORT_ARTICLES.with(|articles| articles.replace(None));
+}
+
+//The tests have no installed settings schema to read the articles from
+#[cfg(test)]
+pub fn set_sort_articles(articles: &[&str]) {
+    SORT_ARTICLES.with(|cached| cached.replace(Some(articles.iter().map(|article| article.to_string()).collect())));
+}
+
+//Locale aware comparison of two sort names
+pub fn collate(a: &str, b: &str) -> Ordering {
+    glib::GString::from(a).collate(b)
+}
+
+//Directories & files beneath a folder, symlinked directories are not followed like os.walk, so a link loop is walked once
+pub fn walk_folder(root: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Unable to read {:?}: {}", dir, e);
+                continue;
+            },
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => pending.push(path),
+                //a link to a file is kept, a link to a directory is skipped
+                Ok(file_type) if file_type.is_symlink() && path.is_dir() => (),
+                Ok(_) => files.push(path),
+                Err(e) => error!("Unable to read {:?}: {}", path, e),
+            }
+        }
+        dirs.push(dir);
+    }
+
+    (dirs, files)
+}
+
 pub fn settings_manager() -> gio::Settings {
     // // We ship a single schema for both default and development profiles
     // let app_id = APPLICATION_ID.trim_end_matches(".Devel");
@@ -338,10 +338,9 @@ impl AlbumCard {
     }
 
     fn add_art(&self, cover_art_id: i64, size: i32) -> Result<RoundedAlbumArt, String> {
-        let cover_art = model().cover_art(cover_art_id)?;
-        let pixbuf = cover_art.pixbuf()?;
+        model().cover_art(cover_art_id)?;
         let art = RoundedAlbumArt::new(size);
-        art.load(pixbuf);
+        art.load_cover_art(cover_art_id);
         Ok(art)
     }
     
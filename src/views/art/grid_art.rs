@@ -5,15 +5,17 @@
  */
 
 use gtk::subclass::prelude::*;
-use gtk::{glib, prelude::*};
+use gtk::{glib, glib::clone, prelude::*};
 
 use gtk::{gdk_pixbuf::Pixbuf, gdk_pixbuf, gdk, gsk, graphene};
 
-use std::rc::Rc;
+use std::{cell::{Cell, RefCell}, rc::Rc};
 
 use log::debug;
 
-mod imp {use std::cell::{RefCell, Cell};
+use crate::util::model;
+
+mod imp {
     use super::*;
     use glib::subclass::Signal;
     use once_cell::sync::Lazy;
@@ -82,6 +84,42 @@ impl GridArt {
         album_art
     }
 
+    //Requests every art at the size of its tile, the grid is drawn once all of them are decoded
+    pub fn load_cover_arts(&self, cover_art_ids: Vec<i64>) {
+        let size = self.imp().size.get();
+        let cover_art_ids: Vec<i64> = cover_art_ids.into_iter().take(9).collect();
+        let tile_size = match cover_art_ids.len() {
+            0 | 1 => size,
+            2..=4 => size / 2,
+            _ => (size as f32 / 3.0).floor() as i32,
+        };
+
+        if cover_art_ids.is_empty() {
+            self.load(Vec::new());
+            return;
+        }
+
+        let results: Rc<RefCell<Vec<Option<Rc<Pixbuf>>>>> = Rc::new(RefCell::new(vec![None; cover_art_ids.len()]));
+        let remaining = Rc::new(Cell::new(cover_art_ids.len()));
+
+        for (pos, id) in cover_art_ids.into_iter().enumerate() {
+            let results = results.clone();
+            let remaining = remaining.clone();
+            model().cover_art_cache().request(id, tile_size, clone!(@weak self as this => move |result| {
+                match result {
+                    Ok(pixbuf) => results.borrow_mut()[pos] = Some(pixbuf),
+                    Err(msg) => debug!("Unable to load cover art {}: {}", id, msg),
+                }
+
+                remaining.set(remaining.get() - 1);
+                if remaining.get() == 0 {
+                    let pixbufs = results.take().into_iter().flatten().collect();
+                    this.load(pixbufs);
+                }
+            }));
+        }
+    }
+
     pub fn load(&self, pixbufs: Vec<Rc<Pixbuf>>) {
         if pixbufs.len() <= 1 {
             self.load_pixbuf(pixbufs);
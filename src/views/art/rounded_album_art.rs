@@ -5,11 +5,14 @@
  */
 
 use gtk::subclass::prelude::*;
-use gtk::{glib, prelude::*};
+use gtk::{glib, glib::clone, prelude::*};
 
 use gtk::{gdk, gdk_pixbuf, graphene, gsk};
 
 use std::{cell::Cell, cell::RefCell, rc::Rc};
+use log::debug;
+
+use crate::util::model;
 
 //simple gtk widget subclass that displays album art from bytes on a gdk pixbuf, with rounded corners
 
@@ -56,7 +59,10 @@ mod imp {
 
         fn snapshot(&self, snapshot: &gtk::Snapshot) {
             if let Some(texture) = self.texture.borrow_mut().as_ref() {
-                let rect = graphene::Rect::new(0.0, 0.0, texture.width() as f32, texture.height() as f32);
+                //art that is not square is centered in the widget
+                let offset_x = (self.size.get() - texture.width()).max(0) as f32 / 2.0;
+                let offset_y = (self.size.get() - texture.height()).max(0) as f32 / 2.0;
+                let rect = graphene::Rect::new(offset_x, offset_y, texture.width() as f32, texture.height() as f32);
                 let rounded_rect = gsk::RoundedRect::from_rect(rect, 10.0);
 
                 snapshot.push_rounded_clip(&rounded_rect);
@@ -93,6 +99,20 @@ impl RoundedAlbumArt {
         self.add_pixbuf(Some(new_pixbuf));
     }
 
+    //The art is decoded off the main thread at the widget size, the widget stays empty until then
+    pub fn load_cover_art(&self, cover_art_id: i64) {
+        let size = self.imp().size.get();
+        model().cover_art_cache().request(cover_art_id, size, clone!(@weak self as this => move |result| {
+            match result {
+                Ok(pixbuf) => this.add_pixbuf(Some((*pixbuf).clone())),
+                Err(msg) => {
+                    debug!("Unable to load cover art {}: {}", cover_art_id, msg);
+                    this.add_pixbuf(None);
+                },
+            }
+        }));
+    }
+
     fn add_pixbuf(&self, pixbuf: Option<gdk_pixbuf::Pixbuf>) {
         let imp = self.imp();
         match pixbuf {
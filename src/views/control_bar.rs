@@ -16,6 +16,7 @@ use crate::views::art::rounded_album_art::RoundedAlbumArt;
 use crate::player::queue::RepeatMode;
 use crate::model::track::Track;
 use crate::util::{player, model, seconds_to_string, settings_manager};
+use crate::rating;
 use crate::i18n::i18n;
 
 use super::window::WindowPage;
@@ -100,6 +101,12 @@ mod imp {
         #[template_child(id = "go_to_queue_button")]
         pub go_to_queue_button: TemplateChild<gtk::Button>,
 
+        #[template_child(id = "favorite_button")]
+        pub favorite_button: TemplateChild<gtk::Button>,
+
+        #[template_child(id = "rating_button")]
+        pub rating_button: TemplateChild<gtk::MenuButton>,
+
         #[template_child(id = "progress_scale")]
         pub progress_scale: TemplateChild<Scale>,
 
@@ -251,6 +258,15 @@ impl ControlBar {
             }),
         );
 
+        model().connect_local(
+            "refresh-ratings",
+            false,
+            clone!(@weak self as this => @default-return None, move |_| {
+                this.update_rating();
+                None
+            }),
+        );
+
         player.state().connect_local(
             "queue-repeat-mode",
             false,
@@ -334,6 +350,13 @@ impl ControlBar {
             })
         );
 
+        imp.favorite_button.connect_clicked(clone!(@strong self as this => @default-panic, move |_button| {
+                if let Some(track) = this.imp().track.borrow().as_ref() {
+                    _ = this.activate_action("win.toggle-favorite-track", Some(&track.id().to_variant()));
+                }
+            })
+        );
+
 
     }
 
@@ -359,6 +382,7 @@ impl ControlBar {
             imp.duration_label.set_label(&format!(" / {}", seconds_to_string(track.duration())));
             
             self.sync_prev_next();
+            self.update_rating();
             self.load_art(track.cover_art_option());
             self.set_revealed(true);
         } else {
@@ -371,6 +395,34 @@ impl ControlBar {
         }
     }
 
+    //Favorite & stars of the playing track, the rating menu is rebuilt to match
+    fn update_rating(&self) {
+        let imp = self.imp();
+        let (id, rating) = match imp.track.borrow().as_ref() {
+            Some(track) => (track.id(), track.rating()),
+            None => return,
+        };
+
+        if rating.favorite {
+            imp.favorite_button.add_css_class("accent");
+            imp.favorite_button.set_tooltip_text(Some(&i18n("Remove from Favorites")));
+        } else {
+            imp.favorite_button.remove_css_class("accent");
+            imp.favorite_button.set_tooltip_text(Some(&i18n("Add to Favorites")));
+        }
+
+        if rating.stars > 0 {
+            imp.rating_button.set_icon_name("starred-symbolic");
+            imp.rating_button.set_tooltip_text(Some(&rating::stars_label(rating.stars)));
+        } else {
+            imp.rating_button.set_icon_name("non-starred-symbolic");
+            imp.rating_button.set_tooltip_text(Some(&i18n("Rating")));
+        }
+
+        let menu = rating::rating_menu("win.toggle-favorite-track", "win.rate-track", id, rating);
+        imp.rating_button.set_menu_model(Some(&menu));
+    }
+
     fn load_art(&self, art: Option<i64>) {
         let imp = self.imp();
 
@@ -387,11 +439,10 @@ impl ControlBar {
     }
 
     fn load_image(&self, cover_art_id: i64) -> Result<RoundedAlbumArt, String> {
-        let cover_art = model().cover_art(cover_art_id)?;
-        let pixbuf = cover_art.pixbuf()?;
+        model().cover_art(cover_art_id)?;
 
         let art = RoundedAlbumArt::new(90);
-        art.load(pixbuf);
+        art.load_cover_art(cover_art_id);
         Ok(art)
     }
 
@@ -91,7 +91,8 @@ impl AddToPlaylistDialog {
 
         match model().playlists() {
             Some(map) => {
-                for (_i, playlist) in map {
+                //smart playlists hold the tracks matching their rules
+                for (_i, playlist) in map.into_iter().filter(|(_i, playlist)| !playlist.is_virtual()) {
                     let box_ = gtk::Box::new(gtk::Orientation::Horizontal, 0);
                     let label = gtk::Label::new(Some(&playlist.title()));
                     box_.append(&label);
@@ -0,0 +1,70 @@
+/* database_error_dialog.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use gtk::{glib, CompositeTemplate};
+
+use crate::i18n::i18n_k;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/io/github/nate_xyz/Resonance/database_error_dialog.ui")]
+    pub struct DatabaseErrorDialogPriv {
+        #[template_child(id = "error_label")]
+        pub error_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for DatabaseErrorDialogPriv {
+        const NAME: &'static str = "DatabaseErrorDialog";
+        type Type = super::DatabaseErrorDialog;
+        type ParentType = adw::MessageDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for DatabaseErrorDialogPriv {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().set_destroy_with_parent(true);
+        }
+    }
+
+    impl WidgetImpl for DatabaseErrorDialogPriv {}
+    impl WindowImpl for DatabaseErrorDialogPriv {}
+    impl MessageDialogImpl for DatabaseErrorDialogPriv {}
+    impl DatabaseErrorDialogPriv {}
+}
+
+glib::wrapper! {
+    pub struct DatabaseErrorDialog(ObjectSubclass<imp::DatabaseErrorDialogPriv>)
+    @extends gtk::Widget, gtk::Window, adw::MessageDialog,
+    @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl DatabaseErrorDialog {
+    pub fn new(version: u32, message: String) -> DatabaseErrorDialog {
+        let dialog: DatabaseErrorDialog = glib::Object::builder::<DatabaseErrorDialog>().build();
+        dialog.load(version, message);
+        dialog
+    }
+
+    fn load(&self, version: u32, message: String) {
+        // Translators: do not replace {version}
+        self.set_body(&i18n_k("Your library could not be upgraded to version {version} and has been left unchanged. Please report this issue along with the error below.", &[("version", &format!("{}", version))]));
+        self.imp().error_label.set_label(&message);
+    }
+}
@@ -5,4 +5,7 @@ pub mod duplicate_playlist_dialog;
 pub mod remove_directory_dialog;
 pub mod rename_playlist_dialog;
 pub mod confirm_rename_playlist_dialog;
-pub mod alpha_dialog;
\ No newline at end of file
+pub mod alpha_dialog;
+pub mod database_error_dialog;
+pub mod smart_playlist_dialog;
+pub mod playlist_import_report_dialog;
\ No newline at end of file
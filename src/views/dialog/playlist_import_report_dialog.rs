@@ -0,0 +1,81 @@
+/* playlist_import_report_dialog.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use gtk::{glib, CompositeTemplate};
+
+use crate::i18n::{i18n, i18n_k};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/io/github/nate_xyz/Resonance/playlist_import_report_dialog.ui")]
+    pub struct PlaylistImportReportDialogPriv {
+        #[template_child(id = "unmatched_label")]
+        pub unmatched_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PlaylistImportReportDialogPriv {
+        const NAME: &'static str = "PlaylistImportReportDialog";
+        type Type = super::PlaylistImportReportDialog;
+        type ParentType = adw::MessageDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PlaylistImportReportDialogPriv {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().set_destroy_with_parent(true);
+        }
+    }
+
+    impl WidgetImpl for PlaylistImportReportDialogPriv {}
+    impl WindowImpl for PlaylistImportReportDialogPriv {}
+    impl MessageDialogImpl for PlaylistImportReportDialogPriv {}
+    impl PlaylistImportReportDialogPriv {}
+}
+
+glib::wrapper! {
+    pub struct PlaylistImportReportDialog(ObjectSubclass<imp::PlaylistImportReportDialogPriv>)
+    @extends gtk::Widget, gtk::Window, adw::MessageDialog,
+    @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PlaylistImportReportDialog {
+    pub fn new(title: String, n_tracks: usize, unmatched: Vec<String>) -> PlaylistImportReportDialog {
+        let dialog: PlaylistImportReportDialog = glib::Object::builder::<PlaylistImportReportDialog>().build();
+        dialog.load(title, n_tracks, unmatched);
+        dialog
+    }
+
+    fn load(&self, title: String, n_tracks: usize, unmatched: Vec<String>) {
+        //no playlist is created when none of the entries were found
+        if n_tracks == 0 {
+            self.set_heading(Some(&i18n("Nothing Imported")));
+            // Translators: do not replace {playlist_title}
+            self.set_body(&i18n_k("None of the tracks of «{playlist_title}» were found in the library:", &[("playlist_title", &title)]));
+        } else {
+            // Translators: do not replace {playlist_title}, {number_of_tracks} or {number_of_unmatched}
+            self.set_body(&i18n_k("«{playlist_title}» has been created with {number_of_tracks} tracks, {number_of_unmatched} were not found in the library:", &[
+                ("playlist_title", &title),
+                ("number_of_tracks", &format!("{}", n_tracks)),
+                ("number_of_unmatched", &format!("{}", unmatched.len())),
+            ]));
+        }
+        self.imp().unmatched_label.set_label(&unmatched.join("\n"));
+    }
+}
@@ -0,0 +1,361 @@
+/* smart_playlist_dialog.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use gtk::{glib, glib::{clone, Sender}, CompositeTemplate};
+use gtk_macros::send;
+
+use std::{cell::{Cell, RefCell}, rc::Rc};
+use chrono::{Datelike, Utc};
+
+use crate::database::DatabaseAction;
+use crate::model::playlist::Playlist;
+use crate::smart_playlist::{SmartRule, SmartRules, SmartSort};
+use crate::i18n::{i18n, i18n_k};
+use crate::util::{database, model};
+
+//Editor widgets of a single rule, the values unused by the selected field are hidden
+#[derive(Debug, Clone)]
+struct RuleRow {
+    row: gtk::ListBoxRow,
+    field: gtk::DropDown,
+    text: gtk::Entry,
+    first: gtk::SpinButton,
+    second: gtk::SpinButton,
+}
+
+impl RuleRow {
+    fn new() -> Self {
+        let fields = SmartRule::fields();
+        let field = gtk::DropDown::from_strings(&fields.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
+        field.set_valign(gtk::Align::Center);
+
+        let text = gtk::Entry::new();
+        text.set_valign(gtk::Align::Center);
+        text.set_hexpand(true);
+
+        let first = gtk::SpinButton::with_range(0.0, 100000.0, 1.0);
+        first.set_valign(gtk::Align::Center);
+        let second = gtk::SpinButton::with_range(0.0, 100000.0, 1.0);
+        second.set_valign(gtk::Align::Center);
+
+        let content = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        content.set_margin_top(6);
+        content.set_margin_bottom(6);
+        content.set_margin_start(6);
+        content.set_margin_end(6);
+        content.append(&field);
+        content.append(&text);
+        content.append(&first);
+        content.append(&second);
+
+        let row = gtk::ListBoxRow::new();
+        row.set_activatable(false);
+        row.set_child(Some(&content));
+
+        Self { row, field, text, first, second }
+    }
+
+    fn load(&self, rule: &SmartRule) {
+        self.field.set_selected(rule.field_index());
+        self.set_defaults(rule.field_index());
+        match rule {
+            SmartRule::GenreIs(text) | SmartRule::FormatIs(text) => self.text.set_text(text),
+            SmartRule::YearBetween(from, to) => {
+                self.first.set_value(*from as f64);
+                self.second.set_value(*to as f64);
+            },
+            SmartRule::PlayCountAbove(count) => self.first.set_value(*count as f64),
+            SmartRule::NotPlayedInDays(days) | SmartRule::AddedInDays(days) => self.first.set_value(*days as f64),
+            SmartRule::RatingAtLeast(stars) => self.first.set_value(*stars as f64),
+        }
+    }
+
+    //Ranges & starting values of the selected field
+    fn set_defaults(&self, index: u32) {
+        let year = Utc::now().year() as f64;
+        let (text, range, first, second) = match index {
+            0 => (String::new(), (0.0, 0.0), 0.0, 0.0),
+            1 => (String::new(), (0.0, 9999.0), year - 10.0, year),
+            2 => (String::new(), (0.0, 100000.0), 10.0, 0.0),
+            3 => (String::new(), (1.0, 36500.0), 30.0, 0.0),
+            4 => (String::new(), (0.0, 5.0), 4.0, 0.0),
+            5 => (String::new(), (1.0, 36500.0), 7.0, 0.0),
+            _ => ("FLAC".to_string(), (0.0, 0.0), 0.0, 0.0),
+        };
+        self.text.set_text(&text);
+        self.first.set_range(range.0, range.1);
+        self.second.set_range(range.0, range.1);
+        self.first.set_value(first);
+        self.second.set_value(second);
+
+        self.text.set_visible(index == 0 || index == 6);
+        self.first.set_visible(index >= 1 && index <= 5);
+        self.second.set_visible(index == 1);
+    }
+
+    fn rule(&self) -> SmartRule {
+        SmartRule::from_field(
+            self.field.selected(),
+            self.text.text().to_string(),
+            self.first.value() as i64,
+            self.second.value() as i64,
+        )
+    }
+}
+
+mod imp {
+    use super::*;
+    use glib::subclass::Signal;
+    use once_cell::sync::Lazy;
+
+    #[derive(Debug, CompositeTemplate)]
+    #[template(resource = "/io/github/nate_xyz/Resonance/smart_playlist_dialog.ui")]
+    pub struct SmartPlaylistDialogPriv {
+        #[template_child(id = "title_adw_entry")]
+        pub title_adw_entry: TemplateChild<adw::EntryRow>,
+
+        #[template_child(id = "desc_adw_entry")]
+        pub desc_adw_entry: TemplateChild<adw::EntryRow>,
+
+        #[template_child(id = "add_rule_button")]
+        pub add_rule_button: TemplateChild<gtk::Button>,
+
+        #[template_child(id = "match_drop_down")]
+        pub match_drop_down: TemplateChild<gtk::DropDown>,
+
+        #[template_child(id = "rules_list_box")]
+        pub rules_list_box: TemplateChild<gtk::ListBox>,
+
+        #[template_child(id = "sort_drop_down")]
+        pub sort_drop_down: TemplateChild<gtk::DropDown>,
+
+        #[template_child(id = "limit_spin_button")]
+        pub limit_spin_button: TemplateChild<gtk::SpinButton>,
+
+        pub rule_rows: RefCell<Vec<RuleRow>>,
+        pub smart_id: Cell<Option<i64>>,
+
+        pub db_sender: Sender<DatabaseAction>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SmartPlaylistDialogPriv {
+        const NAME: &'static str = "SmartPlaylistDialog";
+        type Type = super::SmartPlaylistDialog;
+        type ParentType = adw::MessageDialog;
+
+        fn new() -> Self {
+            Self {
+                title_adw_entry: TemplateChild::default(),
+                desc_adw_entry: TemplateChild::default(),
+                add_rule_button: TemplateChild::default(),
+                match_drop_down: TemplateChild::default(),
+                rules_list_box: TemplateChild::default(),
+                sort_drop_down: TemplateChild::default(),
+                limit_spin_button: TemplateChild::default(),
+                rule_rows: RefCell::new(Vec::new()),
+                smart_id: Cell::new(None),
+                db_sender: database().sender(),
+            }
+        }
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SmartPlaylistDialogPriv {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().initialize();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![
+                    Signal::builder("done").build(),
+                ]
+            });
+
+            SIGNALS.as_ref()
+        }
+    }
+
+    impl WidgetImpl for SmartPlaylistDialogPriv {}
+    impl WindowImpl for SmartPlaylistDialogPriv {}
+    impl MessageDialogImpl for SmartPlaylistDialogPriv {}
+    impl SmartPlaylistDialogPriv {}
+}
+
+glib::wrapper! {
+    pub struct SmartPlaylistDialog(ObjectSubclass<imp::SmartPlaylistDialogPriv>)
+    @extends gtk::Widget, gtk::Window, adw::MessageDialog,
+    @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl SmartPlaylistDialog {
+    //Edits the rules of an existing smart playlist, or creates a new one
+    pub fn new(playlist: Option<Rc<Playlist>>) -> SmartPlaylistDialog {
+        let dialog: SmartPlaylistDialog = glib::Object::builder::<SmartPlaylistDialog>().build();
+        dialog.load(playlist);
+        dialog
+    }
+
+    fn initialize(&self) {
+        let imp = self.imp();
+        self.set_destroy_with_parent(true);
+
+        let modes = [i18n("All Rules"), i18n("Any Rule")];
+        imp.match_drop_down.set_model(Some(&gtk::StringList::new(&modes.iter().map(|s| s.as_str()).collect::<Vec<&str>>())));
+        imp.match_drop_down.connect_selected_notify(clone!(@weak self as this => move |_drop_down| {
+            this.update_preview();
+        }));
+
+        let sorts = SmartSort::labels();
+        imp.sort_drop_down.set_model(Some(&gtk::StringList::new(&sorts.iter().map(|s| s.as_str()).collect::<Vec<&str>>())));
+
+        imp.limit_spin_button.connect_value_changed(clone!(@weak self as this => move |_spin_button| {
+            this.update_preview();
+        }));
+
+        imp.add_rule_button.connect_clicked(clone!(@weak self as this => move |_button| {
+            this.add_rule(None);
+        }));
+
+        self.connect_response(
+            None,
+            clone!(@strong self as this => move |_dialog, response| {
+                this.dialog_response(response);
+            }),
+        );
+    }
+
+    fn load(&self, playlist: Option<Rc<Playlist>>) {
+        let imp = self.imp();
+        match playlist.as_ref().and_then(|playlist| playlist.smart_rules().map(|rules| (playlist, rules))) {
+            Some((playlist, rules)) => {
+                imp.smart_id.set(playlist.smart_id());
+                self.set_heading(Some(&i18n("Edit Smart Playlist")));
+                self.add_response("delete", &i18n("_Delete"));
+                self.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+                imp.title_adw_entry.set_text(&playlist.title());
+                imp.desc_adw_entry.set_text(&playlist.description());
+                imp.match_drop_down.set_selected(if rules.match_all { 0 } else { 1 });
+                imp.sort_drop_down.set_selected(rules.sort.index());
+                imp.limit_spin_button.set_value(rules.limit as f64);
+                for rule in rules.rules.iter() {
+                    self.add_rule(Some(rule));
+                }
+            },
+            None => {
+                imp.title_adw_entry.set_text(&i18n("Smart Playlist"));
+                imp.sort_drop_down.set_selected(SmartSort::default().index());
+                self.add_rule(None);
+            },
+        }
+        self.update_preview();
+    }
+
+    fn add_rule(&self, rule: Option<&SmartRule>) {
+        let imp = self.imp();
+        let rule_row = RuleRow::new();
+        match rule {
+            Some(rule) => rule_row.load(rule),
+            None => rule_row.set_defaults(0),
+        }
+
+        let remove_button = gtk::Button::from_icon_name("user-trash-symbolic");
+        remove_button.set_valign(gtk::Align::Center);
+        remove_button.set_tooltip_text(Some(&i18n("Remove Rule")));
+        remove_button.add_css_class("flat");
+        if let Some(content) = rule_row.row.child().and_then(|child| child.downcast::<gtk::Box>().ok()) {
+            content.append(&remove_button);
+        }
+
+        let row = rule_row.row.clone();
+        remove_button.connect_clicked(clone!(@weak self as this, @weak row => move |_button| {
+            let imp = this.imp();
+            imp.rules_list_box.remove(&row);
+            imp.rule_rows.borrow_mut().retain(|rule_row| rule_row.row != row);
+            this.update_preview();
+        }));
+
+        rule_row.field.connect_selected_notify(clone!(@weak self as this => move |drop_down| {
+            if let Some(rule_row) = this.imp().rule_rows.borrow().iter().find(|rule_row| &rule_row.field == drop_down) {
+                rule_row.set_defaults(drop_down.selected());
+            }
+            this.update_preview();
+        }));
+        rule_row.text.connect_changed(clone!(@weak self as this => move |_entry| {
+            this.update_preview();
+        }));
+        for spin_button in [&rule_row.first, &rule_row.second] {
+            spin_button.connect_value_changed(clone!(@weak self as this => move |_spin_button| {
+                this.update_preview();
+            }));
+        }
+
+        imp.rules_list_box.append(&rule_row.row);
+        imp.rule_rows.borrow_mut().push(rule_row);
+        self.update_preview();
+    }
+
+    fn rules(&self) -> SmartRules {
+        let imp = self.imp();
+        SmartRules {
+            rules: imp.rule_rows.borrow().iter().map(|rule_row| rule_row.rule()).collect(),
+            match_all: imp.match_drop_down.selected() == 0,
+            sort: SmartSort::new_from_index(imp.sort_drop_down.selected()),
+            limit: imp.limit_spin_button.value() as u32,
+        }
+    }
+
+    //Number of library tracks matching the rules as they are edited
+    fn update_preview(&self) {
+        let tracks = match model().tracks() {
+            Some(map) => map.into_values().filter(|track| !model().is_hidden(track.id())).collect(),
+            None => Vec::new(),
+        };
+        let n_tracks = self.rules().evaluate(tracks).len();
+        // Translators: do not replace {number_of_tracks}
+        self.set_body(&i18n_k("{number_of_tracks} tracks match", &[("number_of_tracks", &format!("{}", n_tracks))]));
+    }
+
+    fn dialog_response(&self, response: &str) {
+        let imp = self.imp();
+        match response {
+            "save" => {
+                let mut title = imp.title_adw_entry.text().to_string();
+                if title.is_empty() {
+                    title = i18n("Smart Playlist");
+                }
+                let description = imp.desc_adw_entry.text().to_string();
+                let rules = self.rules();
+
+                match imp.smart_id.get() {
+                    Some(smart_id) => send!(imp.db_sender, DatabaseAction::UpdateSmartPlaylist((smart_id, title, description, rules))),
+                    None => send!(imp.db_sender, DatabaseAction::CreateSmartPlaylist((title, description, rules))),
+                }
+                self.emit_by_name::<()>("done", &[]);
+            },
+            "delete" => {
+                if let Some(smart_id) = imp.smart_id.get() {
+                    send!(imp.db_sender, DatabaseAction::DeleteSmartPlaylist(smart_id));
+                }
+                self.emit_by_name::<()>("done", &[]);
+            },
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,201 @@
+/* duplicates_page.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use gtk::{glib, glib::clone};
+use gtk_macros::send;
+
+use std::collections::HashMap;
+use log::error;
+
+use crate::database::DatabaseAction;
+use crate::duplicates::{DuplicateGroup, FormatPolicy};
+use crate::util::{database, model};
+use crate::i18n::{i18n, i18n_k};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct DuplicatesPagePriv {
+        pub window_title: adw::WindowTitle,
+        pub stack: gtk::Stack,
+        pub list_box: gtk::ListBox,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for DuplicatesPagePriv {
+        const NAME: &'static str = "DuplicatesPage";
+        type Type = super::DuplicatesPage;
+        type ParentType = gtk::Box;
+    }
+
+    impl ObjectImpl for DuplicatesPagePriv {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().initialize();
+        }
+    }
+
+    impl WidgetImpl for DuplicatesPagePriv {}
+    impl BoxImpl for DuplicatesPagePriv {}
+}
+
+glib::wrapper! {
+    pub struct DuplicatesPage(ObjectSubclass<imp::DuplicatesPagePriv>)
+    @extends gtk::Box, gtk::Widget;
+}
+
+//Subpage of the preferences listing the duplicated tracks by album, with the preferred format of each album
+impl DuplicatesPage {
+    pub fn new() -> DuplicatesPage {
+        glib::Object::builder::<DuplicatesPage>().build()
+    }
+
+    fn initialize(&self) {
+        let imp = self.imp();
+        self.set_orientation(gtk::Orientation::Vertical);
+
+        let back_button = gtk::Button::from_icon_name("go-previous-symbolic");
+        back_button.set_tooltip_text(Some(&i18n("Back")));
+        back_button.connect_clicked(clone!(@weak self as this => move |_button| {
+            if let Some(window) = this.root().and_downcast::<adw::PreferencesWindow>() {
+                window.close_subpage();
+            }
+        }));
+
+        imp.window_title.set_title(&i18n("Duplicate Tracks"));
+        let header_bar = adw::HeaderBar::new();
+        header_bar.pack_start(&back_button);
+        header_bar.set_title_widget(Some(&imp.window_title));
+        self.append(&header_bar);
+
+        let status_page = adw::StatusPage::new();
+        status_page.set_icon_name(Some("emblem-ok-symbolic"));
+        status_page.set_title(&i18n("No Duplicates Found"));
+        status_page.set_description(Some(&i18n("Every track is in the library once.")));
+        status_page.set_vexpand(true);
+
+        imp.list_box.set_selection_mode(gtk::SelectionMode::None);
+        imp.list_box.add_css_class("boxed-list");
+        imp.list_box.set_valign(gtk::Align::Start);
+        imp.list_box.set_margin_top(24);
+        imp.list_box.set_margin_bottom(24);
+        imp.list_box.set_margin_start(12);
+        imp.list_box.set_margin_end(12);
+
+        let clamp = adw::Clamp::new();
+        clamp.set_child(Some(&imp.list_box));
+
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_hscrollbar_policy(gtk::PolicyType::Never);
+        scrolled_window.set_vexpand(true);
+        scrolled_window.set_child(Some(&clamp));
+
+        imp.stack.add_named(&status_page, Some("empty"));
+        imp.stack.add_named(&scrolled_window, Some("duplicates"));
+        self.append(&imp.stack);
+
+        for signal in ["populated", "refresh-tracks"] {
+            model().connect_local(
+                signal,
+                false,
+                clone!(@weak self as this => @default-return None, move |_value| {
+                    this.update();
+                    None
+                }),
+            );
+        }
+
+        self.update();
+    }
+
+    fn update(&self) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.list_box.first_child() {
+            imp.list_box.remove(&child);
+        }
+
+        let album_policies = match database().query_album_format_policies() {
+            Ok(album_policies) => album_policies,
+            Err(e) => {
+                error!("Unable to query album format policies: {}", e);
+                HashMap::new()
+            },
+        };
+
+        let mut albums: Vec<(String, Vec<DuplicateGroup>)> = Vec::new();
+        for group in model().duplicates() {
+            match albums.iter_mut().find(|(album_key, _)| *album_key == group.album_key) {
+                Some((_, groups)) => groups.push(group),
+                None => albums.push((group.album_key.clone(), vec![group])),
+            }
+        }
+        albums.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (album_key, groups) in albums.iter() {
+            imp.list_box.append(&album_row(album_key, groups, album_policies.get(album_key).copied()));
+        }
+
+        let n_tracks: usize = albums.iter().map(|(_, groups)| groups.len()).sum();
+        // Translators: do not replace {number_of_tracks}
+        imp.window_title.set_subtitle(&i18n_k("{number_of_tracks} tracks", &[("number_of_tracks", &format!("{}", n_tracks))]));
+        imp.stack.set_visible_child_name(if albums.is_empty() { "empty" } else { "duplicates" });
+    }
+}
+
+fn album_row(album_key: &str, groups: &[DuplicateGroup], policy: Option<FormatPolicy>) -> adw::ExpanderRow {
+    let first = &groups[0].tracks[0];
+
+    let row = adw::ExpanderRow::new();
+    row.set_use_markup(false);
+    row.set_title(&first.album());
+    // Translators: do not replace {album_artist} or {number_of_tracks}
+    row.set_subtitle(&i18n_k("{album_artist} · {number_of_tracks} duplicated tracks", &[
+        ("album_artist", &first.album_artist()),
+        ("number_of_tracks", &format!("{}", groups.len())),
+    ]));
+    row.add_css_class("darken-mas-mas");
+
+    let policies = [i18n("Library Default"), i18n("Show All Copies"), i18n("Prefer Lossless"), i18n("Prefer Lossy")];
+    let drop_down = gtk::DropDown::from_strings(&policies.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
+    drop_down.set_valign(gtk::Align::Center);
+    drop_down.set_tooltip_text(Some(&i18n("Preferred Format")));
+    drop_down.set_selected(policy.map_or(0, |policy| policy.value() as u32 + 1));
+
+    let album_key = album_key.to_string();
+    drop_down.connect_selected_notify(move |drop_down| {
+        let policy = match drop_down.selected() {
+            0 => None,
+            selected => Some(FormatPolicy::new_from_settings(selected as i32 - 1)),
+        };
+        send!(database().sender(), DatabaseAction::SetAlbumFormatPolicy((album_key.clone(), policy)));
+    });
+    row.add_action(&drop_down);
+
+    for group in groups {
+        for track in group.tracks.iter() {
+            let track_row = adw::ActionRow::new();
+            track_row.set_use_markup(false);
+            track_row.set_title(&track.title());
+            track_row.set_subtitle(&format!("{} · {}", track.filetype().trim_start_matches('.').to_uppercase(), track.uri()));
+            track_row.set_tooltip_text(Some(&track.uri()));
+
+            if model().is_hidden(track.id()) {
+                let label = gtk::Label::new(Some(&i18n("Hidden")));
+                label.add_css_class("dim-label");
+                track_row.add_suffix(&label);
+            }
+
+            row.add_row(&track_row);
+        }
+    }
+
+    row
+}
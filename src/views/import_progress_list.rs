@@ -0,0 +1,177 @@
+/* import_progress_list.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use gtk::{glib, glib::clone};
+use gtk_macros::send;
+
+use std::{cell::RefCell, collections::HashMap, time::Duration};
+
+use crate::database::DatabaseAction;
+use crate::import_progress::{ImportState, ImportStatus};
+use crate::util::database;
+use crate::i18n::{i18n, i18n_k};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct ImportProgressListPriv {
+        pub list_box: gtk::ListBox,
+        pub rows: RefCell<HashMap<String, (adw::ActionRow, gtk::ProgressBar, gtk::Button)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ImportProgressListPriv {
+        const NAME: &'static str = "ImportProgressList";
+        type Type = super::ImportProgressList;
+        type ParentType = gtk::Box;
+    }
+
+    impl ObjectImpl for ImportProgressListPriv {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().initialize();
+        }
+    }
+
+    impl WidgetImpl for ImportProgressListPriv {}
+    impl BoxImpl for ImportProgressListPriv {}
+}
+
+glib::wrapper! {
+    pub struct ImportProgressList(ObjectSubclass<imp::ImportProgressListPriv>)
+    @extends gtk::Box, gtk::Widget;
+}
+
+//One row per queued or running folder import, hidden when nothing is imported
+impl ImportProgressList {
+    fn initialize(&self) {
+        let imp = self.imp();
+
+        imp.list_box.set_selection_mode(gtk::SelectionMode::None);
+        imp.list_box.add_css_class("boxed-list");
+        self.append(&imp.list_box);
+
+        database().connect_local(
+            "import-progress",
+            false,
+            clone!(@weak self as this => @default-return None, move |_value| {
+                this.update();
+                None
+            }),
+        );
+
+        //the eta keeps counting down between progress updates
+        glib::timeout_add_seconds_local(1, clone!(@weak self as this => @default-return glib::Continue(false), move || {
+            if !this.imp().rows.borrow().is_empty() {
+                this.update();
+            }
+            glib::Continue(true)
+        }));
+
+        self.update();
+    }
+
+    fn update(&self) {
+        let imp = self.imp();
+        let imports = database().imports();
+
+        let mut rows = imp.rows.borrow_mut();
+        rows.retain(|folder, (row, _, _)| {
+            let keep = imports.iter().any(|status| &status.folder == folder);
+            if !keep {
+                imp.list_box.remove(row);
+            }
+            keep
+        });
+
+        for status in imports.iter() {
+            let (row, progress_bar, cancel_button) = rows.entry(status.folder.clone()).or_insert_with(|| {
+                let row = adw::ActionRow::new();
+                row.set_title(&status.folder);
+                row.add_css_class("darken-mas-mas");
+
+                let progress_bar = gtk::ProgressBar::new();
+                progress_bar.set_valign(gtk::Align::Center);
+                progress_bar.set_width_request(120);
+                row.add_suffix(&progress_bar);
+
+                let cancel_button = gtk::Button::from_icon_name("cross-filled-symbolic");
+                cancel_button.set_tooltip_text(Some(&i18n("Cancel Import")));
+                cancel_button.add_css_class("flat");
+                cancel_button.add_css_class("circular");
+                cancel_button.set_valign(gtk::Align::Center);
+
+                let folder = status.folder.clone();
+                cancel_button.connect_clicked(move |_button| {
+                    send!(database().sender(), DatabaseAction::CancelImport(folder.clone()));
+                });
+                row.add_suffix(&cancel_button);
+
+                imp.list_box.append(&row);
+                (row, progress_bar, cancel_button)
+            });
+
+            cancel_button.set_sensitive(status.state != ImportState::Cancelling);
+
+            row.set_subtitle(&status_message(status));
+            if status.current_file.is_empty() {
+                row.set_tooltip_text(None);
+            } else {
+                row.set_tooltip_text(Some(&status.current_file));
+            }
+
+            if status.state == ImportState::Extracting && status.total == 0 {
+                progress_bar.pulse();
+            } else {
+                progress_bar.set_fraction(status.fraction());
+            }
+        }
+
+        self.set_visible(!rows.is_empty());
+    }
+}
+
+fn status_message(status: &ImportStatus) -> String {
+    let mut message = match status.state {
+        ImportState::Queued => i18n("Waiting for other imports"),
+        ImportState::Extracting if status.total == 0 => i18n("Looking for music files…"),
+        // Translators: do not replace {processed} or {total}
+        ImportState::Extracting => i18n_k("{processed} of {total} files", &[
+            ("processed", &format!("{}", status.processed)),
+            ("total", &format!("{}", status.total)),
+        ]),
+        ImportState::Cancelling => i18n("Cancelling…"),
+        ImportState::Done | ImportState::Failed => String::new(),
+    };
+
+    if let Some(eta) = status.eta() {
+        message.push_str(" · ");
+        message.push_str(&eta_message(eta));
+    }
+
+    if status.errors > 0 {
+        message.push_str(" · ");
+        // Translators: do not replace {number_of_files}
+        message.push_str(&i18n_k("{number_of_files} unreadable", &[("number_of_files", &format!("{}", status.errors))]));
+    }
+
+    message
+}
+
+fn eta_message(eta: Duration) -> String {
+    let seconds = eta.as_secs();
+    if seconds < 60 {
+        // Translators: do not replace {seconds}
+        i18n_k("{seconds} s left", &[("seconds", &format!("{}", seconds.max(1)))])
+    } else {
+        // Translators: do not replace {minutes}
+        i18n_k("about {minutes} min left", &[("minutes", &format!("{}", (seconds + 59) / 60))])
+    }
+}
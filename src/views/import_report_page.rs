@@ -0,0 +1,175 @@
+/* import_report_page.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use gtk::{gdk, gio, glib, glib::clone};
+
+use std::path::Path;
+use log::error;
+
+use crate::import_progress::ImportProblem;
+use crate::util::database;
+use crate::i18n::{i18n, i18n_k};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct ImportReportPagePriv {
+        pub window_title: adw::WindowTitle,
+        pub stack: gtk::Stack,
+        pub list_box: gtk::ListBox,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ImportReportPagePriv {
+        const NAME: &'static str = "ImportReportPage";
+        type Type = super::ImportReportPage;
+        type ParentType = gtk::Box;
+    }
+
+    impl ObjectImpl for ImportReportPagePriv {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().initialize();
+        }
+    }
+
+    impl WidgetImpl for ImportReportPagePriv {}
+    impl BoxImpl for ImportReportPagePriv {}
+}
+
+glib::wrapper! {
+    pub struct ImportReportPage(ObjectSubclass<imp::ImportReportPagePriv>)
+    @extends gtk::Box, gtk::Widget;
+}
+
+//Subpage of the preferences listing the files skipped or imported with missing tags, one row per file
+impl ImportReportPage {
+    pub fn new() -> ImportReportPage {
+        glib::Object::builder::<ImportReportPage>().build()
+    }
+
+    fn initialize(&self) {
+        let imp = self.imp();
+        self.set_orientation(gtk::Orientation::Vertical);
+
+        let back_button = gtk::Button::from_icon_name("go-previous-symbolic");
+        back_button.set_tooltip_text(Some(&i18n("Back")));
+        back_button.connect_clicked(clone!(@weak self as this => move |_button| {
+            if let Some(window) = this.root().and_downcast::<adw::PreferencesWindow>() {
+                window.close_subpage();
+            }
+        }));
+
+        imp.window_title.set_title(&i18n("Import Report"));
+        let header_bar = adw::HeaderBar::new();
+        header_bar.pack_start(&back_button);
+        header_bar.set_title_widget(Some(&imp.window_title));
+        self.append(&header_bar);
+
+        let status_page = adw::StatusPage::new();
+        status_page.set_icon_name(Some("emblem-ok-symbolic"));
+        status_page.set_title(&i18n("No Problems Found"));
+        status_page.set_description(Some(&i18n("Every music file was imported with its tags.")));
+        status_page.set_vexpand(true);
+
+        imp.list_box.set_selection_mode(gtk::SelectionMode::None);
+        imp.list_box.add_css_class("boxed-list");
+        imp.list_box.set_valign(gtk::Align::Start);
+        imp.list_box.set_margin_top(24);
+        imp.list_box.set_margin_bottom(24);
+        imp.list_box.set_margin_start(12);
+        imp.list_box.set_margin_end(12);
+
+        let clamp = adw::Clamp::new();
+        clamp.set_child(Some(&imp.list_box));
+
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_hscrollbar_policy(gtk::PolicyType::Never);
+        scrolled_window.set_vexpand(true);
+        scrolled_window.set_child(Some(&clamp));
+
+        imp.stack.add_named(&status_page, Some("empty"));
+        imp.stack.add_named(&scrolled_window, Some("report"));
+        self.append(&imp.stack);
+
+        database().connect_local(
+            "import-report-changed",
+            false,
+            clone!(@weak self as this => @default-return None, move |_value| {
+                this.update();
+                None
+            }),
+        );
+
+        self.update();
+    }
+
+    fn update(&self) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.list_box.first_child() {
+            imp.list_box.remove(&child);
+        }
+
+        let problems = match database().query_import_report() {
+            Ok(problems) => problems,
+            Err(e) => {
+                error!("Unable to query import report: {}", e);
+                Vec::new()
+            },
+        };
+
+        //the report is ordered by file, problems of the same file share a row
+        let mut files: Vec<(String, Vec<String>)> = Vec::new();
+        for ImportProblem { uri, reason, .. } in problems {
+            match files.last_mut() {
+                Some((last_uri, reasons)) if *last_uri == uri => reasons.push(reason),
+                _ => files.push((uri, vec![reason])),
+            }
+        }
+
+        for (uri, reasons) in files.iter() {
+            imp.list_box.append(&problem_row(uri, reasons));
+        }
+
+        // Translators: do not replace {number_of_files}
+        imp.window_title.set_subtitle(&i18n_k("{number_of_files} files", &[("number_of_files", &format!("{}", files.len()))]));
+        imp.stack.set_visible_child_name(if files.is_empty() { "empty" } else { "report" });
+    }
+}
+
+fn problem_row(uri: &str, reasons: &[String]) -> adw::ActionRow {
+    let path = Path::new(uri);
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| uri.to_string());
+
+    let row = adw::ActionRow::new();
+    row.set_use_markup(false);
+    row.set_title(&file_name);
+    row.set_subtitle(&reasons.join(", "));
+    row.set_tooltip_text(Some(uri));
+    row.add_css_class("darken-mas-mas");
+
+    let button = gtk::Button::from_icon_name("folder-open-symbolic");
+    button.set_tooltip_text(Some(&i18n("Open Containing Folder")));
+    button.add_css_class("flat");
+    button.add_css_class("circular");
+    button.set_valign(gtk::Align::Center);
+
+    let uri = uri.to_string();
+    button.connect_clicked(move |button| {
+        if let Some(folder) = gio::File::for_path(&uri).parent() {
+            let window = button.root().and_downcast::<gtk::Window>();
+            gtk::show_uri(window.as_ref(), &folder.uri(), gdk::CURRENT_TIME);
+        }
+    });
+
+    row.add_suffix(&button);
+    row
+}
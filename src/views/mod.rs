@@ -1,12 +1,16 @@
 pub mod window;
 pub mod preferences_window;
+pub mod statistics_window;
 pub mod art;
 pub mod pages;
 mod dialog;
 mod album_card;
 mod control_bar;
 mod disc_button;
+mod duplicates_page;
 mod generic_flowbox_child;
+mod import_progress_list;
+mod import_report_page;
 mod scale;
 mod track_entry;
 mod volume_scale;
@@ -66,6 +66,12 @@ mod imp {
         #[template_child(id = "date_label")]
         pub date_label: TemplateChild<gtk::Label>,
 
+        #[template_child(id = "original_date_label")]
+        pub original_date_label: TemplateChild<gtk::Label>,
+
+        #[template_child(id = "record_label_label")]
+        pub record_label_label: TemplateChild<gtk::Label>,
+
         #[template_child(id = "overlay")]
         pub overlay: TemplateChild<gtk::Overlay>,
 
@@ -272,6 +278,14 @@ impl AlbumDetailPage {
                         // add_button.connect_clicked(clone!(@weak self.on_disc_add_button));
                         box_.append(&disc_button);
 
+                        let disc_subtitle = disc.values().find_map(|track| track.tag("discsubtitle"));
+                        if let Some(disc_subtitle) = disc_subtitle {
+                            let label = gtk::Label::new(Some(&disc_subtitle));
+                            label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+                            label.set_margin_start(10);
+                            label.add_css_class("dim-label");
+                            box_.append(&label);
+                        }
 
                         track_box.append(&box_);
                     }
@@ -300,6 +314,22 @@ impl AlbumDetailPage {
                     imp.date_label.set_label(&date)
                 }
 
+                match album.tag("originaldate") {
+                    Some(original_date) if original_date != date => {
+                        imp.original_date_label.set_label(&i18n_k("Originally released {original_date}", &[("original_date", &original_date)]));
+                        imp.original_date_label.set_visible(true);
+                    },
+                    _ => imp.original_date_label.set_visible(false),
+                }
+
+                match album.tag("label") {
+                    Some(label) => {
+                        imp.record_label_label.set_label(&label);
+                        imp.record_label_label.set_visible(true);
+                    },
+                    None => imp.record_label_label.set_visible(false),
+                }
+
                 let n_tracks = album.n_tracks();
                 if n_tracks <= 1 {
                     imp.track_amount_label.set_label(&i18n("1 track"));
@@ -230,6 +230,9 @@ impl AlbumGridChild {
                 let imp = this.imp();
                 debug!("unpaired release");
                 if button == gdk::BUTTON_SECONDARY {
+                    if let Some(album) = imp.album.borrow().as_ref() {
+                        imp.popover.set_menu_model(Some(album.menu_model()));
+                    }
                     imp.popover.popup();
                 }
             })
@@ -287,8 +290,6 @@ impl AlbumGridChild {
                 // } else if album.cover_art_id.is_none() {
                 //     self.on_populated(PlaceHolderArt(album.title.clone(), album.album_artist.clone()));
                 // }
-
-                //imp.popover.set_menu_model(Some(album.menu_model()))
             }
             None => {
                 imp.title_label.set_label("");
@@ -303,19 +304,10 @@ impl AlbumGridChild {
     }
 
     fn load_image(&self, cover_art_id: i64) -> Result<RoundedAlbumArt, String> {
+        model().cover_art(cover_art_id)?;
         let art = RoundedAlbumArt::new(200);
-        match model().cover_art(cover_art_id) {
-            Ok(cover_art) => {
-                match cover_art.pixbuf() {
-                    Ok(pixbuf) => {
-                        art.load(pixbuf);
-                        return Ok(art);
-                    }
-                    Err(msg) => return Err(msg),
-                };
-            }
-            Err(msg) => return Err(msg),
-        };
+        art.load_cover_art(cover_art_id);
+        Ok(art)
     }
 
     fn album(&self ) -> Rc<Album> {
@@ -14,7 +14,7 @@ use log::debug;
 use crate::model::album::Album;
 use crate::search::{FuzzyFilter, SearchSortObject, SearchMethod};
 use crate::sort::{FuzzySorter, SortMethod};
-use crate::util::model;
+use crate::util::{model, settings_manager};
 
 use super::album_sidebar::AlbumFlap;
 use super::album_grid_child::AlbumGridChild;
@@ -181,6 +181,14 @@ impl AlbumGridPage {
         filter_model.set_model(Some(&list_store));
         filter_model.set_filter(Some(&filter));
 
+        settings_manager().bind("show-compilations", &filter, "hide-compilations")
+            .flags(gio::SettingsBindFlags::GET | gio::SettingsBindFlags::INVERT_BOOLEAN)
+            .build();
+
+        settings_manager().bind("album-grid-favorites-only", &filter, "favorites-only")
+            .flags(gio::SettingsBindFlags::GET)
+            .build();
+
         let sorter = FuzzySorter::new(SearchSortObject::Album);
         let sorter_model = gtk::SortListModel::new(None::<gio::ListStore>, None::<FuzzySorter>);
         sorter_model.set_model(Some(&filter_model));
@@ -305,6 +313,28 @@ impl AlbumGridPage {
             })
         );
 
+        model().connect_local("refresh-plays", false, 
+        clone!(@weak self as this => @default-return None, move |_args| {
+                if let Some(sorter) = this.imp().sorter.borrow().as_ref() {
+                    sorter.refresh_plays();
+                }
+                None
+            })
+        );
+
+        model().connect_local("refresh-ratings", false, 
+        clone!(@weak self as this => @default-return None, move |_args| {
+                let imp = this.imp();
+                if let Some(filter) = imp.filter.borrow().as_ref() {
+                    filter.refresh_ratings();
+                }
+                if let Some(sorter) = imp.sorter.borrow().as_ref() {
+                    sorter.refresh_ratings();
+                }
+                None
+            })
+        );
+
         imp.album_sidebar.connect_local("back", false, 
         clone!(@strong self as this => @default-return None, move |_| {
                 this.imp().adwflap.set_reveal_flap(false);
@@ -56,6 +56,12 @@ mod imp {
         
         #[template_child(id = "grid_view")]
         pub grid_view: TemplateChild<gtk::GridView>,
+
+        #[template_child(id = "appears_on_label")]
+        pub appears_on_label: TemplateChild<gtk::Label>,
+
+        #[template_child(id = "appears_on_grid_view")]
+        pub appears_on_grid_view: TemplateChild<gtk::GridView>,
         
         #[template_child(id = "sort-menu")]
         pub sort_menu: TemplateChild<gio::Menu>,
@@ -159,6 +165,7 @@ impl ArtistDetailPage {
             .build();
         
         imp.grid_view.remove_css_class("view");
+        imp.appears_on_grid_view.remove_css_class("view");
 
         imp.play_button.connect_clicked(
             clone!(@strong self as this => move |_button| {
@@ -210,6 +217,7 @@ impl ArtistDetailPage {
         );
 
         imp.grid_view.set_factory(Some(&list_item_factory));
+        imp.appears_on_grid_view.set_factory(Some(&list_item_factory));
 
         imp.scrolled_window.vadjustment().connect_notify_local(
             Some("value"),
@@ -242,13 +250,28 @@ impl ArtistDetailPage {
         let imp = self.imp();
         let artist = model().artist(artist_id);
         let list_store = gio::ListStore::new(Album::static_type());
+        let appears_on_store = gio::ListStore::new(Album::static_type());
         
         match artist {
             Ok(artist) => {
-                for album in artist.albums().unwrap().iter() {
+                let albums = artist.albums().unwrap_or_default();
+                for album in albums.iter() {
                     list_store.append(album.as_ref());
                 }
-                imp.albums.replace(artist.albums());
+
+                let appears_on = artist.appears_on();
+                for album in appears_on.iter() {
+                    appears_on_store.append(album.as_ref());
+                }
+                imp.appears_on_label.set_visible(!appears_on.is_empty());
+                imp.appears_on_grid_view.set_visible(!appears_on.is_empty());
+
+                //an artist only featured on other albums plays those
+                if albums.is_empty() {
+                    imp.albums.replace(Some(appears_on));
+                } else {
+                    imp.albums.replace(Some(albums));
+                }
                 imp.artist.replace(Some(artist));
                 self.update_view();
             }
@@ -272,6 +295,11 @@ impl ArtistDetailPage {
         let selection_model = gtk::NoSelection::new(Some(sorter_model));
         imp.grid_view.set_model(Some(&selection_model));
 
+        //appearances share the filter & sorter of the artist's own albums
+        let filter_model = gtk::FilterListModel::new(Some(appears_on_store), Some(filter.clone()));
+        let sorter_model = gtk::SortListModel::new(Some(filter_model), Some(sorter.clone()));
+        imp.appears_on_grid_view.set_model(Some(&gtk::NoSelection::new(Some(sorter_model))));
+
         // imp.search_entry.bind_property("text", &filter, "search")
         //     .flags(glib::BindingFlags::SYNC_CREATE)
         //     .build();
@@ -335,7 +363,7 @@ impl ArtistDetailPage {
     }
 
     pub fn albums(&self) -> Vec<Rc<Album>> {
-        self.imp().albums.borrow().as_ref().unwrap().clone()
+        self.imp().albums.borrow().clone().unwrap_or_default()
     }
 
 
@@ -17,7 +17,7 @@ use crate::model::artist::Artist;
 use crate::views::generic_flowbox_child::{GenericFlowboxChild, GenericChild};
 use crate::search::{FuzzyFilter, SearchSortObject};
 use crate::sort::{FuzzySorter, SortMethod};
-use crate::util::model;
+use crate::util::{model, settings_manager};
 
 mod imp {
     use super::*;
@@ -155,7 +155,10 @@ impl ArtistGridPage {
         let filter_model = gtk::FilterListModel::new(None::<gio::ListStore>, None::<FuzzyFilter>);
         filter_model.set_model(Some(&list_store));
         filter_model.set_filter(Some(&filter));
-        
+
+        settings_manager().bind("show-compilations", &filter, "hide-compilations")
+            .flags(gio::SettingsBindFlags::GET | gio::SettingsBindFlags::INVERT_BOOLEAN)
+            .build();
 
         let sorter = FuzzySorter::new(SearchSortObject::Artist);
         let sorter_model = gtk::SortListModel::new(None::<gio::ListStore>, None::<FuzzySorter>);
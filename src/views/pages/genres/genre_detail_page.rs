@@ -243,7 +243,7 @@ impl GenreDetailPage {
         let list_store = gio::ListStore::new(Album::static_type());
         match genre {
             Ok(genre) => {
-                for album in genre.albums().unwrap().iter() {
+                for album in genre.albums().unwrap_or_default().iter() {
                     list_store.append(album.as_ref());
                 }
                 imp.albums.replace(genre.albums());
@@ -332,7 +332,7 @@ impl GenreDetailPage {
     }
 
     pub fn albums(&self) -> Vec<Rc<Album>> {
-        self.imp().albums.borrow().as_ref().unwrap().clone()
+        self.imp().albums.borrow().clone().unwrap_or_default()
     }
 
 }
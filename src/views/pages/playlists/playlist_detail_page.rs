@@ -16,6 +16,7 @@ use crate::views::dialog::{
     delete_playlist_dialog::DeletePlaylistDialog,
     duplicate_playlist_dialog::DuplicatePlaylistDialog,
     confirm_rename_playlist_dialog::ConfirmRenamePlaylistDialog,
+    smart_playlist_dialog::SmartPlaylistDialog,
 };
 use crate::model::{
     playlist::Playlist,
@@ -124,6 +125,9 @@ mod imp {
         #[template_child(id = "duplicate_button")]
         pub duplicate_button: TemplateChild<gtk::Button>,
 
+        #[template_child(id = "export_button")]
+        pub export_button: TemplateChild<gtk::Button>,
+
         #[template_child(id = "popover")]
         pub popover: TemplateChild<gtk::PopoverMenu>,
 
@@ -250,6 +254,19 @@ impl PlaylistDetailPage {
             }),
         );
 
+        model().connect_local(
+            "refresh-smart-playlists",
+            false,
+            clone!(@strong self as this => @default-return None, move |_| {
+                let virtual_id = this.imp().playlist.borrow().as_ref().filter(|playlist| playlist.is_virtual()).map(|playlist| playlist.id());
+                if let Some(id) = virtual_id {
+                    debug!("refreshing smart playlist detail page.");
+                    this.load_playlist(id);
+                }
+                None
+            }),
+        );
+
 
         let list_item_factory = gtk::SignalListItemFactory::new();
         list_item_factory.connect_setup(
@@ -302,6 +319,9 @@ impl PlaylistDetailPage {
                 clone!(@strong self as this => @default-panic, move |_button| {
                     let playlist = this.playlist();
                     let tracks = playlist.tracks();
+                    if tracks.is_empty() {
+                        return;
+                    }
                     player().clear_play_album(tracks, Some(playlist.title()));
                 }),
             );
@@ -312,6 +332,9 @@ impl PlaylistDetailPage {
             add.connect_clicked(
                 clone!(@strong self as this => @default-panic, move |_button| {
                     let tracks = this.playlist().tracks();
+                    if tracks.is_empty() {
+                        return;
+                    }
                     player().add_album(tracks);
                 }),
             );
@@ -320,10 +343,28 @@ impl PlaylistDetailPage {
         let edit_buttons = [&imp.edit_button];
         for edit in edit_buttons {
             edit.connect_toggled(
-                clone!(@strong self as this => move |_button| {
+                clone!(@strong self as this => move |button| {
                     let imp = this.imp();
                     let edit_mode = imp.edit_mode.get();
 
+                    //the tracks of a smart playlist follow its rules, those are edited instead
+                    let playlist = this.playlist();
+                    if playlist.is_smart() {
+                        if button.is_active() {
+                            button.set_active(false);
+                            let dialog = SmartPlaylistDialog::new(Some(playlist));
+                            dialog.set_transient_for(Some(&win(this.upcast_ref())));
+                            dialog.show();
+                        }
+                        return;
+                    }
+
+                    //collections are filled from the library & plays, they have nothing to edit
+                    if playlist.is_auto() {
+                        button.set_active(false);
+                        return;
+                    }
+
                     if edit_mode {
                         let playlist = this.playlist();
                         let new_title = imp.adw_entry.text().to_string();
@@ -622,30 +663,32 @@ impl PlaylistDetailPage {
             imp.placeholder_art.replace(Some(art));
         }
 
+        imp.export_button.set_action_target_value(Some(&playlist.id().to_variant()));
+
+        //smart playlists & collections are saved as a regular playlist with the duplicate button
+        imp.edit_button.set_visible(!playlist.is_auto());
+        if playlist.is_virtual() {
+            imp.edit_button.set_tooltip_markup(Some(&i18n("Edit Smart Playlist Rules")));
+            imp.duplicate_button.set_tooltip_markup(Some(&i18n("Save as Playlist")));
+            imp.duplicate_button.show();
+        } else {
+            imp.edit_button.set_tooltip_markup(Some(&i18n("Edit Playlist")));
+            imp.duplicate_button.set_tooltip_markup(Some(&i18n("Duplicate Playlist")));
+            imp.duplicate_button.set_visible(imp.edit_mode.get());
+        }
+
         //imp.popover.set_menu_model(Some(playlist.menu_model()));
     }
 
 
     fn load_image(&self, cover_art_id: Vec<i64>) -> Result<GridArt, String> {
-        let art = GridArt::new(500);
-
-        let mut cover_art_pixbufs = Vec::new();
-        for id in cover_art_id {
-            match model().cover_art(id) {
-                Ok(cover_art) => {
-                    match cover_art.pixbuf() {
-                        Ok(pixbuf) => {
-                            cover_art_pixbufs.push(pixbuf);
-                        }
-                        Err(msg) => return Err(msg),
-                    };
-                }
-                Err(msg) => return Err(msg),
-            };
+        for id in cover_art_id.iter() {
+            model().cover_art(*id)?;
         }
 
-        art.load(cover_art_pixbufs);
-        return Ok(art);
+        let art = GridArt::new(500);
+        art.load_cover_arts(cover_art_id);
+        Ok(art)
     }
 
 
@@ -408,19 +408,10 @@ impl PlaylistDetailRow {
     }
 
     fn load_image(&self, cover_art_id: i64) -> Result<RoundedAlbumArt, String> {
+        model().cover_art(cover_art_id)?;
         let art = RoundedAlbumArt::new(60);
-        match model().cover_art(cover_art_id) {
-            Ok(cover_art) => {
-                match cover_art.pixbuf() {
-                    Ok(pixbuf) => {
-                        art.load(pixbuf);
-                        return Ok(art);
-                    }
-                    Err(msg) => return Err(msg),
-                };
-            }
-            Err(msg) => return Err(msg),
-        };
+        art.load_cover_art(cover_art_id);
+        Ok(art)
     }
 
     pub fn set_edit_mode(&self, edit_mode: bool) {
@@ -45,6 +45,9 @@ mod imp {
         #[template_child(id = "art_bin")]
         pub art_bin: TemplateChild<adw::Bin>,
 
+        #[template_child(id = "smart_badge")]
+        pub smart_badge: TemplateChild<gtk::Label>,
+
         #[template_child(id = "title_label")]
         pub title_label: TemplateChild<gtk::Label>,
 
@@ -176,6 +179,9 @@ impl PlaylistGridChild {
             clone!(@strong self as this => @default-panic, move |_button| {
                 let playlist = this.playlist();
                 let tracks = playlist.tracks();
+                if tracks.is_empty() {
+                    return;
+                }
                 player().clear_play_album(tracks, Some(playlist.title()));
             })
         );
@@ -183,6 +189,9 @@ impl PlaylistGridChild {
         imp.add_button.connect_clicked(
             clone!(@strong self as this => @default-panic, move |_button| {
                 let tracks = this.playlist().tracks();
+                if tracks.is_empty() {
+                    return;
+                }
                 player().add_album(tracks);
             })
         );
@@ -231,6 +240,7 @@ impl PlaylistGridChild {
                 imp.main_button.set_tooltip_text(Some(&playlist.title()));
                 
                 imp.title_label.set_label(&playlist.title());
+                imp.smart_badge.set_visible(playlist.is_smart());
                 imp.modify_time_label.set_label(&format!("{}", playlist.modify_time()));
                 imp.track_count_label.set_label(&i18n_k("{number_of_tracks} tracks", &[("number_of_tracks", &format!("{}", playlist.n_tracks()))]));
                 imp.duration_label.set_label(&seconds_to_string_longform(playlist.duration()));
@@ -265,6 +275,7 @@ impl PlaylistGridChild {
                 imp.modify_time_label.set_label("");
                 imp.track_count_label.set_label("");
                 imp.duration_label.set_label("");
+                imp.smart_badge.hide();
                 imp.art_bin.set_child(gtk::Widget::NONE);
             }
         }
@@ -272,25 +283,13 @@ impl PlaylistGridChild {
 
     fn load_image(&self, cover_art_id: Vec<i64>) -> Result<GridArt, String> {
         debug!("loading image, playlist grid child");
-        let art = GridArt::new(200);
-
-        let mut cover_art_pixbufs = Vec::new();
-        for id in cover_art_id {
-            match model().cover_art(id) {
-                Ok(cover_art) => {
-                    match cover_art.pixbuf() {
-                        Ok(pixbuf) => {
-                            cover_art_pixbufs.push(pixbuf);
-                        }
-                        Err(msg) => return Err(msg),
-                    };
-                }
-                Err(msg) => return Err(msg),
-            };
+        for id in cover_art_id.iter() {
+            model().cover_art(*id)?;
         }
 
-        art.load(cover_art_pixbufs);
-        return Ok(art);
+        let art = GridArt::new(200);
+        art.load_cover_arts(cover_art_id);
+        Ok(art)
     }
 
 
@@ -39,6 +39,15 @@ mod imp {
         #[template_child(id = "flow_box")]
         pub flow_box: TemplateChild<gtk::FlowBox>,
 
+        #[template_child(id = "collections_label")]
+        pub collections_label: TemplateChild<gtk::Label>,
+
+        #[template_child(id = "collections_flow_box")]
+        pub collections_flow_box: TemplateChild<gtk::FlowBox>,
+
+        #[template_child(id = "playlists_label")]
+        pub playlists_label: TemplateChild<gtk::Label>,
+
         #[template_child(id = "search_bar")]
         pub search_bar: TemplateChild<gtk::SearchBar>,
 
@@ -52,6 +61,7 @@ mod imp {
         pub sort_menu: TemplateChild<gio::Menu>,
 
         pub list_store: RefCell<Option<Rc<ListStore>>>,
+        pub collections_list_store: RefCell<Option<Rc<ListStore>>>,
         pub hidden: Cell<bool>,
         pub sort_method: Cell<SortMethod>,
         pub search_string: RefCell<Option<String>>,
@@ -152,6 +162,22 @@ impl PlaylistGridPage {
             })
         );
 
+        model().connect_local("refresh-smart-playlists", false, 
+            clone!(@weak self as this => @default-return None, move |_args| {
+                this.update_view();
+                None
+            })
+        );
+
+        model().connect_local("refresh-plays", false, 
+            clone!(@weak self as this => @default-return None, move |_args| {
+                if let Some(sorter) = this.imp().sorter.borrow().as_ref() {
+                    sorter.refresh_plays();
+                }
+                None
+            })
+        );
+
         let list_store = gio::ListStore::new(Playlist::static_type());
         
         let filter = FuzzyFilter::new(SearchSortObject::Playlist);
@@ -165,34 +191,16 @@ impl PlaylistGridPage {
         sorter_model.set_sorter(Some(&sorter));
 
         let selection = gtk::NoSelection::new(Some(sorter_model));
-        
-        imp.flow_box.bind_model(Some(&selection), 
-        clone!(@strong self as this => @default-panic, move |obj| {
-            let playlist = obj.clone().downcast::<Playlist>().expect("Album is of wrong type");
-            let playlist_grid_child = PlaylistGridChild::new();
-            playlist_grid_child.connect_local("clicked", false, clone!(@strong this => @default-return None, move |value| {
-                let int = value.get(1);
-                match int {
-                    Some(int) => {
-                        let int = int.get::<i64>().ok().unwrap();
-                        this.on_playlist_click_with_id(int);
-                    }, 
-                    None => (),
-                }
-                None
-            }));
-
-            playlist_grid_child.load_playlist(Rc::new(playlist));
-
-            this.bind_property("sort-method", &playlist_grid_child, "sort-method")
-                .flags(glib::BindingFlags::SYNC_CREATE)
-                .build();
+        self.bind_flow_box(&imp.flow_box, &selection);
 
-            return playlist_grid_child.upcast::<gtk::Widget>();
-            })
-        );
+        //collections keep their order, the search still applies to them
+        let collections_list_store = gio::ListStore::new(Playlist::static_type());
+        let collections_filter_model = gtk::FilterListModel::new(Some(collections_list_store.clone()), Some(filter.clone()));
+        let collections_selection = gtk::NoSelection::new(Some(collections_filter_model));
+        self.bind_flow_box(&imp.collections_flow_box, &collections_selection);
 
         imp.list_store.replace(Some(Rc::new(list_store)));
+        imp.collections_list_store.replace(Some(Rc::new(collections_list_store)));
 
 
         // imp.search_entry.bind_property("text", &filter, "search")
@@ -259,15 +267,62 @@ impl PlaylistGridPage {
         );
     }
 
+    fn bind_flow_box(&self, flow_box: &gtk::FlowBox, selection: &gtk::NoSelection) {
+        flow_box.bind_model(Some(selection),
+        clone!(@strong self as this => @default-panic, move |obj| {
+            let playlist = obj.clone().downcast::<Playlist>().expect("Album is of wrong type");
+            let playlist_grid_child = PlaylistGridChild::new();
+            playlist_grid_child.connect_local("clicked", false, clone!(@strong this => @default-return None, move |value| {
+                let int = value.get(1);
+                match int {
+                    Some(int) => {
+                        let int = int.get::<i64>().ok().unwrap();
+                        this.on_playlist_click_with_id(int);
+                    }, 
+                    None => (),
+                }
+                None
+            }));
+
+            playlist_grid_child.load_playlist(Rc::new(playlist));
+
+            this.bind_property("sort-method", &playlist_grid_child, "sort-method")
+                .flags(glib::BindingFlags::SYNC_CREATE)
+                .build();
+
+            return playlist_grid_child.upcast::<gtk::Widget>();
+            })
+        );
+    }
+
     pub fn update_view(&self) {
+        let imp = self.imp();
         let list_store = self.list_store();
         list_store.remove_all();
+        let collections_list_store = imp.collections_list_store.borrow().clone().unwrap();
+        collections_list_store.remove_all();
 
         if let Some(map) = model().playlists() {
-            if !map.is_empty() {
-                for (_id, playlist) in map.iter() {
-                    list_store.append(playlist.as_ref());
-                }
+            //empty collections are left out, so an empty library still shows the placeholder
+            let mut collections: Vec<&Rc<Playlist>> = map.values()
+                .filter(|playlist| playlist.is_auto() && !playlist.tracks().is_empty())
+                .collect();
+            collections.sort_by_key(|playlist| playlist.id());
+            for playlist in collections.iter() {
+                collections_list_store.append(playlist.as_ref());
+            }
+
+            for (_id, playlist) in map.iter().filter(|(_id, playlist)| !playlist.is_auto()) {
+                list_store.append(playlist.as_ref());
+            }
+
+            let has_collections = collections_list_store.n_items() > 0;
+            let has_playlists = list_store.n_items() > 0;
+            imp.collections_label.set_visible(has_collections);
+            imp.collections_flow_box.set_visible(has_collections);
+            imp.playlists_label.set_visible(has_collections && has_playlists);
+
+            if has_collections || has_playlists {
                 self.set_property("hidden", false.to_value());
                 return;
             }
@@ -343,16 +343,15 @@ impl QueuePage {
     }
 
     fn add_art(&self, cover_art_id: i64, _size: i32) -> Result<gtk::Picture, String> {
-        let cover_art = model().cover_art(cover_art_id)?;
-        let pixbuf = cover_art.pixbuf()?;
+        model().cover_art(cover_art_id)?;
 
-        let picture = if pixbuf.height() < 668 {
-            let scaled_up = pixbuf.scale_simple(668, 668, gtk::gdk_pixbuf::InterpType::Bilinear)
-            .unwrap();
-            gtk::Picture::for_pixbuf(&scaled_up)
-        } else {
-            gtk::Picture::for_pixbuf(&pixbuf)
-        };
+        let picture = gtk::Picture::new();
+        model().cover_art_cache().request(cover_art_id, 668, clone!(@weak picture => move |result| {
+            match result {
+                Ok(pixbuf) => picture.set_pixbuf(Some(&*pixbuf)),
+                Err(msg) => debug!("Unable to load cover art {}: {}", cover_art_id, msg),
+            }
+        }));
 
         // picture.set_can_shrink(true);
         picture.set_css_classes(&[&"card"]);
@@ -417,6 +416,10 @@ impl QueuePage {
         menu_item.set_action_and_target_value(Some("win.end-queue"), None);
         menu.append_item(&menu_item);
 
+        let menu_item = gio::MenuItem::new(Some(&i18n("Export Queue")), None);
+        menu_item.set_action_and_target_value(Some("win.export-queue"), None);
+        menu.append_item(&menu_item);
+
         imp.popover.set_menu_model(Some(&menu));
     }
 
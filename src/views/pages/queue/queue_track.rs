@@ -300,19 +300,10 @@ impl QueueTrack {
     }
 
     fn load_image(&self, cover_art_id: i64) -> Result<RoundedAlbumArt, String> {
+        model().cover_art(cover_art_id)?;
         let art = RoundedAlbumArt::new(50);
-        match model().cover_art(cover_art_id) {
-            Ok(cover_art) => {
-                match cover_art.pixbuf() {
-                    Ok(pixbuf) => {
-                        art.load(pixbuf);
-                        return Ok(art);
-                    }
-                    Err(msg) => return Err(msg),
-                };
-            }
-            Err(msg) => return Err(msg),
-        };
+        art.load_cover_art(cover_art_id);
+        Ok(art)
     }
 
 
@@ -367,7 +358,7 @@ impl QueueTrack {
         menu_item.set_action_and_target_value(Some("win.go-to-album-detail"), Some(&track.album_id().to_variant()));
         menu.append_item(&menu_item);
     
-        let menu_item = gio::MenuItem::new(Some(&format!("Go to Artist {} Detail", track.artist())), None);
+        let menu_item = gio::MenuItem::new(Some(&format!("Go to Artist {} Detail", track.album_artist())), None);
         menu_item.set_action_and_target_value(Some("win.go-to-artist-detail"), Some(&track.artist_id().to_variant()));
         menu.append_item(&menu_item);
     
@@ -17,7 +17,7 @@ use crate::model::track::Track;
 use crate::search::{FuzzyFilter, SearchSortObject, SearchMethod};
 use crate::sort::{FuzzySorter, SortMethod};
 use crate::player::queue::RepeatMode;
-use crate::util::{player, model};
+use crate::util::{player, model, settings_manager};
 
 use super::track_page_row::TrackPageRow;
 
@@ -179,6 +179,32 @@ impl TrackPage {
             })
         );
 
+        model().connect_local(
+            "refresh-plays", 
+            false, 
+        clone!(@weak self as this => @default-return None, move |_args| {
+                if let Some(sorter) = this.imp().sorter.borrow().as_ref() {
+                    sorter.refresh_plays();
+                }
+                None
+            })
+        );
+
+        model().connect_local(
+            "refresh-ratings", 
+            false, 
+        clone!(@weak self as this => @default-return None, move |_args| {
+                let imp = this.imp();
+                if let Some(filter) = imp.filter.borrow().as_ref() {
+                    filter.refresh_ratings();
+                }
+                if let Some(sorter) = imp.sorter.borrow().as_ref() {
+                    sorter.refresh_ratings();
+                }
+                None
+            })
+        );
+
         let imp = self.imp();
 
         imp.play_all_button.connect_clicked(
@@ -281,6 +307,10 @@ impl TrackPage {
         filter_model.set_model(Some(&*list_store));
         filter_model.set_filter(Some(&filter));
 
+        settings_manager().bind("track-page-favorites-only", &filter, "favorites-only")
+            .flags(gio::SettingsBindFlags::GET)
+            .build();
+
         let sorter = FuzzySorter::new(SearchSortObject::Track);
         sorter.set_method(imp.sort_method.get());
         let sorter_model = gtk::SortListModel::new(None::<gio::ListStore>, None::<FuzzySorter>);
@@ -377,8 +407,10 @@ impl TrackPage {
         let list_store = self.list_store();
         if let Some(map) = model().tracks() {
             if !map.is_empty() {
-                for (_id, track) in map.iter() {
-                    list_store.append(track.as_ref());
+                for (id, track) in map.iter() {
+                    if !model().is_hidden(*id) {
+                        list_store.append(track.as_ref());
+                    }
                 }
                 self.set_property("hidden", false.to_value());
                 return;
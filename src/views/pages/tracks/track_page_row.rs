@@ -6,7 +6,7 @@
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
-use gtk::{glib, glib::clone, CompositeTemplate};
+use gtk::{gdk, glib, glib::clone, CompositeTemplate};
 
 use std::{cell::RefCell, cell::Cell, rc::Rc};
 use log::error;
@@ -19,6 +19,7 @@ use crate::views::{
 use crate::search::SearchMethod;
 use crate::sort::SortMethod;
 use crate::util::{player, model, seconds_to_string};
+use crate::i18n::i18n_k;
 
 mod imp {
     use super::*;
@@ -54,6 +55,9 @@ mod imp {
         #[template_child(id = "date_label")]
         pub date_label: TemplateChild<gtk::Label>,
 
+        #[template_child(id = "bpm_label")]
+        pub bpm_label: TemplateChild<gtk::Label>,
+
         #[template_child(id = "add_button")]
         pub add_button: TemplateChild<gtk::Button>,
 
@@ -109,6 +113,7 @@ mod imp {
                 "display-labels-default" => {
                     let display_labels_default = value.get().expect("The value needs to be of type `bool`.");
                     self.display_labels_default.replace(display_labels_default);
+                    self.obj().update_bpm_label_visibility();
                 },
                 "sort-method" => {
                     let sort_method = value.get().expect("The value needs to be of type `enum`.");
@@ -174,6 +179,7 @@ impl TrackPageRow {
                 imp.duration_label.hide();
                 imp.date_label.hide();
                 imp.genre_label.hide();
+                imp.bpm_label.hide();
                 
                 imp.add_button.show();
                 
@@ -201,6 +207,7 @@ impl TrackPageRow {
                     imp.date_label.show();
                 }
 
+                this.update_bpm_label_visibility();
                 imp.duration_label.show();
                 imp.add_button.hide();
                 
@@ -216,16 +223,19 @@ impl TrackPageRow {
         );
         self.add_controller(ctrl);
 
-        // let ctrl = gtk::GestureClick::new();
-        // ctrl.connect_unpaired_release(
-        //     clone!(@strong self as this => move |_gesture_click, _x, _y, button, _sequence| {
-        //         let imp = this.imp();
-        //         if button == gdk::BUTTON_SECONDARY {
-        //             imp.popover.popup();
-        //         }
-        //     })
-        // );
-        // self.add_controller(ctrl);
+        let ctrl = gtk::GestureClick::new();
+        ctrl.connect_unpaired_release(
+            clone!(@strong self as this => move |_gesture_click, _x, _y, button, _sequence| {
+                let imp = this.imp();
+                if button == gdk::BUTTON_SECONDARY {
+                    if let Some(track) = imp.track.borrow().as_ref() {
+                        imp.popover.set_menu_model(Some(track.menu_model()));
+                        imp.popover.popup();
+                    }
+                }
+            })
+        );
+        self.add_controller(ctrl);
     }
 
 
@@ -243,6 +253,11 @@ impl TrackPageRow {
                 imp.album_artist_label.set_label(&track.artist());
                 imp.date_label.set_label(&track.date());
                 imp.genre_label.set_label(&track.genre());
+                match track.bpm() {
+                    Some(bpm) => imp.bpm_label.set_label(&i18n_k("{bpm} BPM", &[("bpm", &format!("{:.0}", bpm))])),
+                    None => imp.bpm_label.set_label(""),
+                }
+                self.update_bpm_label_visibility();
 
                 let duration = track.duration();
                 if duration > 0.0 {
@@ -266,8 +281,6 @@ impl TrackPageRow {
                         imp.art.replace(None);
                     }
                 }
-
-                //imp.popover.set_menu_model(Some(track.menu_model()));
             }
             None => {
                 imp.track_title_label.set_label("");
@@ -275,6 +288,7 @@ impl TrackPageRow {
                 imp.album_artist_label.set_label("");
                 imp.date_label.set_label("");
                 imp.genre_label.set_label("");
+                imp.bpm_label.set_label("");
                 imp.duration_label.set_label("");
                 imp.art_bin.set_child(gtk::Widget::NONE);
                 imp.art.replace(None);
@@ -283,19 +297,10 @@ impl TrackPageRow {
     }
 
     fn load_image(&self, cover_art_id: i64) -> Result<RoundedAlbumArt, String> {
+        model().cover_art(cover_art_id)?;
         let art = RoundedAlbumArt::new(60);
-        match model().cover_art(cover_art_id) {
-            Ok(cover_art) => {
-                match cover_art.pixbuf() {
-                    Ok(pixbuf) => {
-                        art.load(pixbuf);
-                        return Ok(art);
-                    }
-                    Err(msg) => return Err(msg),
-                };
-            }
-            Err(msg) => return Err(msg),
-        };
+        art.load_cover_art(cover_art_id);
+        Ok(art)
     }
 
 
@@ -336,8 +341,16 @@ impl TrackPageRow {
 
     }
 
+    //The tempo is shown when sorting by it, or with the additional labels
+    fn update_bpm_label_visibility(&self) {
+        let imp = self.imp();
+        let visible = imp.sort_method.get() == SortMethod::Bpm || imp.display_labels_default.get();
+        imp.bpm_label.set_visible(visible && !imp.bpm_label.label().is_empty());
+    }
+
     fn update_sort_ui(&self) {
         let imp = self.imp();
+        self.update_bpm_label_visibility();
         match imp.sort_method.get() {
             SortMethod::Genre => {
                 if !imp.display_labels_default.get() {
@@ -11,12 +11,17 @@ use gtk_macros::send;
 
 use std::cell::RefCell;
 use std::error::Error;
+use std::path::Path;
 use log::{debug, error};
 
 use crate::i18n::i18n;
 use crate::util::{self, database};
 use crate::database::DatabaseAction;
+use crate::backup;
 use crate::views::dialog::remove_directory_dialog::RemoveDirectoryDialog;
+use super::import_progress_list::ImportProgressList;
+use super::import_report_page::ImportReportPage;
+use super::duplicates_page::DuplicatesPage;
 
 mod imp {
     use super::*;
@@ -81,12 +86,56 @@ mod imp {
         #[template_child(id = "spin_volume_default")]
         pub spin_volume_default: TemplateChild<gtk::SpinButton>,
 
+        #[template_child(id = "replaygain_mode")]
+        pub replaygain_mode: TemplateChild<gtk::ComboBoxText>,
+
+        #[template_child(id = "replaygain_preamp_adjustment")]
+        pub replaygain_preamp_adjustment: TemplateChild<gtk::Adjustment>,
+
         #[template_child(id = "switch_loop_shuffle")]
         pub switch_loop_shuffle: TemplateChild<gtk::Switch>,
 
         #[template_child(id = "add_folder_button")]
         pub add_folder_button: TemplateChild<gtk::Button>,
 
+        #[template_child(id = "rescan_library_button")]
+        pub rescan_library_button: TemplateChild<gtk::Button>,
+
+        #[template_child(id = "import_report_button")]
+        pub import_report_button: TemplateChild<gtk::Button>,
+
+        #[template_child(id = "duplicate_policy")]
+        pub duplicate_policy: TemplateChild<gtk::ComboBoxText>,
+
+        #[template_child(id = "duplicates_button")]
+        pub duplicates_button: TemplateChild<gtk::Button>,
+
+        #[template_child(id = "switch_watch_folders")]
+        pub switch_watch_folders: TemplateChild<gtk::Switch>,
+
+        #[template_child(id = "switch_import_playlist_files")]
+        pub switch_import_playlist_files: TemplateChild<gtk::Switch>,
+
+        #[template_child(id = "switch_analyze_library")]
+        pub switch_analyze_library: TemplateChild<gtk::Switch>,
+
+        #[template_child(id = "analysis_jobs_adjustment")]
+        pub analysis_jobs_adjustment: TemplateChild<gtk::Adjustment>,
+
+        #[template_child(id = "import_progress_list")]
+        pub import_progress_list: TemplateChild<ImportProgressList>,
+
+        #[template_child(id = "tag_extractor")]
+        pub tag_extractor: TemplateChild<gtk::ComboBoxText>,
+        #[template_child(id = "switch_split_artist_credits")]
+        pub switch_split_artist_credits: TemplateChild<gtk::Switch>,
+
+        #[template_child(id = "genre_separators_entry")]
+        pub genre_separators_entry: TemplateChild<adw::EntryRow>,
+
+        #[template_child(id = "various_artists_entry")]
+        pub various_artists_entry: TemplateChild<adw::EntryRow>,
+
         #[template_child(id = "dir-list")]
         pub dir_list: TemplateChild<adw::PreferencesGroup>,
 
@@ -96,6 +145,15 @@ mod imp {
         #[template_child(id = "volume_adjustment")]
         pub volume_adjustment: TemplateChild<gtk::Adjustment>,
 
+        #[template_child(id = "backup_library_button")]
+        pub backup_library_button: TemplateChild<gtk::Button>,
+
+        #[template_child(id = "restore_library_button")]
+        pub restore_library_button: TemplateChild<gtk::Button>,
+
+        #[template_child(id = "sort_articles_entry")]
+        pub sort_articles_entry: TemplateChild<adw::EntryRow>,
+
         #[template_child(id = "reset_default_all")]
         pub reset_default_all: TemplateChild<gtk::Button>,
 
@@ -124,6 +182,8 @@ mod imp {
         pub reset_default_discord: TemplateChild<gtk::Button>,
 
         pub folder_dialog: RefCell<Option<gtk::FileChooserNative>>,
+        pub backup_dialog: RefCell<Option<gtk::FileChooserNative>>,
+        pub relocate_dialog: RefCell<Option<gtk::FileChooserNative>>,
         pub dir_rows: RefCell<Option<Vec<adw::ActionRow>>>,
         pub settings: gio::Settings,
         pub db_sender: Sender<DatabaseAction>
@@ -164,11 +224,29 @@ mod imp {
                 artist_detail_sort: TemplateChild::default(),
                 genre_detail_sort: TemplateChild::default(),
                 spin_volume_default: TemplateChild::default(),
+                replaygain_mode: TemplateChild::default(),
+                replaygain_preamp_adjustment: TemplateChild::default(),
                 switch_loop_shuffle: TemplateChild::default(),
                 add_folder_button: TemplateChild::default(),
+                rescan_library_button: TemplateChild::default(),
+                import_report_button: TemplateChild::default(),
+                duplicate_policy: TemplateChild::default(),
+                duplicates_button: TemplateChild::default(),
+                switch_watch_folders: TemplateChild::default(),
+                switch_import_playlist_files: TemplateChild::default(),
+                switch_analyze_library: TemplateChild::default(),
+                analysis_jobs_adjustment: TemplateChild::default(),
+                import_progress_list: TemplateChild::default(),
+                tag_extractor: TemplateChild::default(),
+                switch_split_artist_credits: TemplateChild::default(),
+                genre_separators_entry: TemplateChild::default(),
+                various_artists_entry: TemplateChild::default(),
                 dir_list: TemplateChild::default(),
                 play_threshold_adjustment: TemplateChild::default(),
                 volume_adjustment: TemplateChild::default(),
+                backup_library_button: TemplateChild::default(),
+                restore_library_button: TemplateChild::default(),
+                sort_articles_entry: TemplateChild::default(),
                 reset_default_all: TemplateChild::default(),
                 reset_default_queue: TemplateChild::default(),
                 reset_default_album: TemplateChild::default(),
@@ -180,6 +258,8 @@ mod imp {
                 reset_default_discord: TemplateChild::default(),
                 dir_rows: RefCell::new(None),
                 folder_dialog: RefCell::new(None),
+                backup_dialog: RefCell::new(None),
+                relocate_dialog: RefCell::new(None),
                 settings: util::settings_manager(),
                 db_sender: database().sender(),
             }
@@ -365,11 +445,71 @@ impl PreferencesWindow {
             .flags(SettingsBindFlags::DEFAULT)
             .build();
 
+        imp.settings
+            .bind("replaygain-mode", &*imp.replaygain_mode, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("replaygain-preamp", &*imp.replaygain_preamp_adjustment, "value")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
         imp.settings
             .bind("shuffle-mode-loop", &*imp.switch_loop_shuffle, "active")
             .flags(SettingsBindFlags::DEFAULT)
             .build();
 
+        imp.settings
+            .bind("watch-music-folders", &*imp.switch_watch_folders, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("import-playlist-files", &*imp.switch_import_playlist_files, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("analyze-library", &*imp.switch_analyze_library, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("analysis-jobs", &*imp.analysis_jobs_adjustment, "value")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("tag-extractor", &*imp.tag_extractor, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("duplicate-policy", &*imp.duplicate_policy, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("split-artist-credits", &*imp.switch_split_artist_credits, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("genre-separators", &*imp.genre_separators_entry, "text")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("various-artists-name", &*imp.various_artists_entry, "text")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        imp.settings
+            .bind("sort-articles", &*imp.sort_articles_entry, "text")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
         Ok(())
     }
 
@@ -389,7 +529,20 @@ impl PreferencesWindow {
             let row = adw::ActionRow::new();
             row.set_title(&folder_name.clone());
             row.add_css_class("darken-mas-mas");
-            
+
+            if !Path::new(&folder_name).is_dir() {
+                row.set_subtitle(&i18n("Folder not found, relocate it if it moved"));
+            }
+
+            let relocate_button = gtk::Button::from_icon_name("folder-open-symbolic");
+            relocate_button.set_tooltip_text(Some(&i18n("Relocate Folder")));
+            relocate_button.add_css_class("flat");
+            relocate_button.add_css_class("circular");
+            relocate_button.set_valign(gtk::Align::Center);
+            relocate_button.connect_clicked(clone!(@weak self as this, @strong folder_name => move |_button| {
+                this.show_relocate_dialog(folder_name.clone());
+            }));
+            row.add_suffix(&relocate_button);
 
             let button = gtk::Button::new();
             button.set_icon_name("cross-filled-symbolic");
@@ -467,9 +620,116 @@ impl PreferencesWindow {
         );
     }
 
+    //Moves a folder to where its files are now, instead of removing & re-adding it
+    fn show_relocate_dialog(&self, folder: String) {
+        let imp = self.imp();
+
+        let dialog = gtk::FileChooserNative::builder()
+            .accept_label(&i18n("_Relocate"))
+            .cancel_label(&i18n("_Cancel"))
+            .modal(true)
+            .title(&i18n("Select New Location"))
+            .action(gtk::FileChooserAction::SelectFolder)
+            .select_multiple(false)
+            .transient_for(self)
+            .build();
+
+        dialog.connect_response(clone!(@weak self as this => move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    debug!("DIALOG RELOCATE RECEIVED: {:?} -> {:?}", folder, path);
+                    send!(this.imp().db_sender, DatabaseAction::RelocateFolder((folder.clone(), path)));
+                }
+            } else {
+                debug!("No folder selected.");
+            }
+        }));
+
+        dialog.show();
+        imp.relocate_dialog.replace(Some(dialog));
+    }
+
+    fn show_backup_dialog(&self, restore: bool) {
+        let imp = self.imp();
+
+        let builder = gtk::FileChooserNative::builder()
+            .cancel_label(&i18n("_Cancel"))
+            .modal(true)
+            .select_multiple(false)
+            .transient_for(self);
+
+        let dialog = if restore {
+            builder
+                .accept_label(&i18n("_Restore"))
+                .title(&i18n("Select Backup"))
+                .action(gtk::FileChooserAction::Open)
+                .build()
+        } else {
+            builder
+                .accept_label(&i18n("_Save"))
+                .title(&i18n("Save Backup"))
+                .action(gtk::FileChooserAction::Save)
+                .build()
+        };
+
+        let filter = gtk::FileFilter::new();
+        gtk::FileFilter::set_name(&filter, Some(&i18n("Backup files")));
+        filter.add_pattern("*.json");
+        dialog.add_filter(&filter);
+
+        if restore {
+            _ = dialog.set_current_folder(Some(&gio::File::for_path(backup::backup_dir())));
+        } else {
+            dialog.set_current_name("resonance-backup.json");
+        }
+
+        dialog.connect_response(clone!(@weak self as this => move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    debug!("DIALOG BACKUP RECEIVED: {:?}", path);
+                    if restore {
+                        send!(this.imp().db_sender, DatabaseAction::RestoreLibrary(path));
+                    } else {
+                        send!(this.imp().db_sender, DatabaseAction::BackupLibrary(path));
+                    }
+                }
+            } else {
+                debug!("No backup file selected.");
+            }
+        }));
+
+        dialog.show();
+        imp.backup_dialog.replace(Some(dialog));
+    }
+
     fn setup_button_connections(&self) {
         let imp = self.imp();
 
+        imp.backup_library_button.connect_clicked(clone!(@strong self as this => @default-panic, move |_button| {
+                this.show_backup_dialog(false);
+            })
+        );
+
+        imp.restore_library_button.connect_clicked(clone!(@strong self as this => @default-panic, move |_button| {
+                this.show_backup_dialog(true);
+            })
+        );
+
+        imp.rescan_library_button.connect_clicked(clone!(@strong self as this => @default-panic, move |_button| {
+                send!(this.imp().db_sender, DatabaseAction::RescanLibrary);
+            })
+        );
+
+        imp.import_report_button.connect_clicked(clone!(@strong self as this => @default-panic, move |_button| {
+                this.present_subpage(&ImportReportPage::new());
+            })
+        );
+
+        imp.duplicates_button.connect_clicked(clone!(@strong self as this => @default-panic, move |_button| {
+                this.present_subpage(&DuplicatesPage::new());
+            })
+        );
+
         imp.reset_default_all.connect_clicked(clone!(@strong self as this => @default-panic, move |_button| {
                 let imp = this.imp();
                 if let Some(variant) = gio::Settings::default_value(&imp.settings, "full-page-back-button") {
@@ -477,6 +737,7 @@ impl PreferencesWindow {
                         _ = imp.settings.set_boolean("full-page-back-button", value);
                     }
                 }
+                imp.settings.reset("sort-articles");
             })
         );
 
@@ -582,6 +843,8 @@ impl PreferencesWindow {
                         _ = imp.settings.set_boolean("shuffle-mode-loop", value);
                     }
                 }
+                imp.settings.reset("replaygain-mode");
+                imp.settings.reset("replaygain-preamp");
             })
         );
 
@@ -0,0 +1,321 @@
+/* statistics_window.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use gtk::{glib, glib::clone};
+
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use log::error;
+
+use crate::model::track::Track;
+use crate::statistics::{ListeningStats, StatsPeriod, StatsRange};
+use crate::util::{database, model, player, seconds_to_string_longform};
+use crate::i18n::{i18n, i18n_k};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct StatisticsWindowPriv {
+        pub window_title: adw::WindowTitle,
+        pub stack: gtk::Stack,
+        pub range_drop_down: RefCell<Option<gtk::DropDown>>,
+        pub period_drop_down: RefCell<Option<gtk::DropDown>>,
+        pub top_tracks: gtk::ListBox,
+        pub top_albums: gtk::ListBox,
+        pub top_artists: gtk::ListBox,
+        pub top_genres: gtk::ListBox,
+        pub plays_per_period: gtk::ListBox,
+        pub stats: RefCell<ListeningStats>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for StatisticsWindowPriv {
+        const NAME: &'static str = "StatisticsWindow";
+        type Type = super::StatisticsWindow;
+        type ParentType = adw::Window;
+    }
+
+    impl ObjectImpl for StatisticsWindowPriv {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().initialize();
+        }
+    }
+
+    impl WidgetImpl for StatisticsWindowPriv {}
+    impl WindowImpl for StatisticsWindowPriv {}
+    impl AdwWindowImpl for StatisticsWindowPriv {}
+}
+
+glib::wrapper! {
+    pub struct StatisticsWindow(ObjectSubclass<imp::StatisticsWindowPriv>)
+    @extends gtk::Widget, gtk::Window, adw::Window,
+    @implements gtk::Accessible;
+}
+
+//Top tracks, albums, artists & genres of the Plays table, with the listening time & plays over time
+impl StatisticsWindow {
+    pub fn new() -> StatisticsWindow {
+        glib::Object::builder::<StatisticsWindow>().build()
+    }
+
+    fn initialize(&self) {
+        let imp = self.imp();
+        self.set_default_size(560, 720);
+        self.set_modal(true);
+
+        let ranges = [i18n("Last 7 Days"), i18n("Last Year"), i18n("All Time")];
+        let range_drop_down = gtk::DropDown::from_strings(&ranges.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
+        range_drop_down.set_tooltip_text(Some(&i18n("Time Range")));
+        range_drop_down.connect_selected_notify(clone!(@weak self as this => move |_drop_down| {
+            this.update();
+        }));
+
+        imp.window_title.set_title(&i18n("Listening Statistics"));
+        let header_bar = adw::HeaderBar::new();
+        header_bar.pack_end(&range_drop_down);
+        header_bar.set_title_widget(Some(&imp.window_title));
+
+        let status_page = adw::StatusPage::new();
+        status_page.set_icon_name(Some("media-playback-start-symbolic"));
+        status_page.set_title(&i18n("No Plays Yet"));
+        status_page.set_description(Some(&i18n("Tracks played in this time range will show up here.")));
+        status_page.set_vexpand(true);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 24);
+        content.set_margin_top(24);
+        content.set_margin_bottom(24);
+        content.set_margin_start(12);
+        content.set_margin_end(12);
+
+        content.append(&self.top_group(&i18n("Top Tracks"), &imp.top_tracks, |this| this.top_tracks()));
+        content.append(&self.top_group(&i18n("Top Albums"), &imp.top_albums, |this| this.top_album_tracks()));
+        content.append(&self.top_group(&i18n("Top Artists"), &imp.top_artists, |this| this.top_artist_tracks()));
+        content.append(&self.top_group(&i18n("Top Genres"), &imp.top_genres, |this| this.top_genre_tracks()));
+
+        let periods = [i18n("Per Day"), i18n("Per Week"), i18n("Per Month")];
+        let period_drop_down = gtk::DropDown::from_strings(&periods.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
+        period_drop_down.set_valign(gtk::Align::Center);
+        period_drop_down.connect_selected_notify(clone!(@weak self as this => move |_drop_down| {
+            this.update();
+        }));
+
+        let period_group = adw::PreferencesGroup::new();
+        period_group.set_title(&i18n("Plays Over Time"));
+        period_group.set_header_suffix(Some(&period_drop_down));
+        imp.plays_per_period.set_selection_mode(gtk::SelectionMode::None);
+        imp.plays_per_period.add_css_class("boxed-list");
+        period_group.add(&imp.plays_per_period);
+        content.append(&period_group);
+
+        let clamp = adw::Clamp::new();
+        clamp.set_child(Some(&content));
+
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_hscrollbar_policy(gtk::PolicyType::Never);
+        scrolled_window.set_vexpand(true);
+        scrolled_window.set_child(Some(&clamp));
+
+        imp.stack.add_named(&status_page, Some("empty"));
+        imp.stack.add_named(&scrolled_window, Some("statistics"));
+
+        let window_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        window_box.append(&header_bar);
+        window_box.append(&imp.stack);
+        self.set_content(Some(&window_box));
+
+        imp.range_drop_down.replace(Some(range_drop_down));
+        imp.period_drop_down.replace(Some(period_drop_down));
+
+        for signal in ["populated", "refresh-plays"] {
+            model().connect_local(
+                signal,
+                false,
+                clone!(@weak self as this => @default-return None, move |_value| {
+                    this.update();
+                    None
+                }),
+            );
+        }
+
+        self.update();
+    }
+
+    //A titled list with a button queueing the tracks of the list
+    fn top_group(&self, title: &str, list_box: &gtk::ListBox, tracks: fn(&StatisticsWindow) -> Vec<Rc<Track>>) -> adw::PreferencesGroup {
+        let button = gtk::Button::from_icon_name("media-playback-start-symbolic");
+        button.set_tooltip_text(Some(&i18n("Play These")));
+        button.add_css_class("flat");
+        button.set_valign(gtk::Align::Center);
+
+        let queue_title = title.to_string();
+        button.connect_clicked(clone!(@weak self as this => move |_button| {
+            let tracks = tracks(&this);
+            if !tracks.is_empty() {
+                player().clear_play_album(tracks, Some(queue_title.clone()));
+            }
+        }));
+
+        let group = adw::PreferencesGroup::new();
+        group.set_title(title);
+        group.set_header_suffix(Some(&button));
+
+        list_box.set_selection_mode(gtk::SelectionMode::None);
+        list_box.add_css_class("boxed-list");
+        group.add(list_box);
+        group
+    }
+
+    fn update(&self) {
+        let imp = self.imp();
+
+        let range = StatsRange::new_from_index(imp.range_drop_down.borrow().as_ref().map_or(0, |d| d.selected()));
+        let period = StatsPeriod::new_from_index(imp.period_drop_down.borrow().as_ref().map_or(0, |d| d.selected()));
+
+        let stats = match database().query_listening_stats(range, period) {
+            Ok(stats) => stats,
+            Err(e) => {
+                error!("Unable to query listening statistics: {}", e);
+                ListeningStats::default()
+            },
+        };
+
+        let model = model();
+
+        fill(&imp.top_tracks, stats.top_tracks.iter().filter_map(|(id, plays)| {
+            model.track(*id).ok().map(|track| (track.title(), Some(track.artist()), *plays))
+        }));
+
+        fill(&imp.top_albums, stats.top_albums.iter().filter_map(|(id, plays)| {
+            model.album(*id).ok().map(|album| (album.title(), Some(album.artist()), *plays))
+        }));
+
+        fill(&imp.top_artists, stats.top_artists.iter().filter_map(|(id, plays)| {
+            model.artist(*id).ok().map(|artist| (artist.name(), None, *plays))
+        }));
+
+        fill(&imp.top_genres, stats.top_genres.iter().filter_map(|(id, plays)| {
+            model.genre(*id).ok().map(|genre| (genre.name(), None, *plays))
+        }));
+
+        while let Some(child) = imp.plays_per_period.first_child() {
+            imp.plays_per_period.remove(&child);
+        }
+
+        let max_plays = stats.plays_per_period.iter().map(|(_, plays)| *plays).max().unwrap_or(1).max(1);
+        for (key, plays) in stats.plays_per_period.iter() {
+            let row = adw::ActionRow::new();
+            row.set_use_markup(false);
+            row.set_title(&period.label(key));
+
+            let level_bar = gtk::LevelBar::new();
+            level_bar.set_max_value(max_plays as f64);
+            level_bar.set_value(*plays as f64);
+            level_bar.set_width_request(160);
+            level_bar.set_valign(gtk::Align::Center);
+            row.add_suffix(&level_bar);
+            row.add_suffix(&plays_label(*plays));
+
+            imp.plays_per_period.append(&row);
+        }
+
+        // Translators: do not replace {number_of_plays} or {listening_time}
+        imp.window_title.set_subtitle(&i18n_k("{number_of_plays} plays · {listening_time}", &[
+            ("number_of_plays", &format!("{}", stats.total_plays)),
+            ("listening_time", &seconds_to_string_longform(stats.total_seconds)),
+        ]));
+        imp.stack.set_visible_child_name(if stats.total_plays == 0 { "empty" } else { "statistics" });
+        imp.stats.replace(stats);
+    }
+
+    fn top_tracks(&self) -> Vec<Rc<Track>> {
+        let model = model();
+        let tracks = self.imp().stats.borrow().top_tracks.iter()
+            .filter_map(|(id, _)| model.track(*id).ok())
+            .collect::<Vec<Rc<Track>>>();
+        playable(tracks)
+    }
+
+    fn top_album_tracks(&self) -> Vec<Rc<Track>> {
+        let model = model();
+        let tracks = self.imp().stats.borrow().top_albums.iter()
+            .filter_map(|(id, _)| model.album(*id).ok())
+            .flat_map(|album| album.tracks())
+            .collect::<Vec<Rc<Track>>>();
+        playable(tracks)
+    }
+
+    fn top_artist_tracks(&self) -> Vec<Rc<Track>> {
+        let model = model();
+        let mut tracks = Vec::new();
+        for (id, _) in self.imp().stats.borrow().top_artists.iter() {
+            if let Some(albums) = model.artist(*id).ok().and_then(|artist| artist.albums()) {
+                tracks.extend(albums.iter()
+                    .flat_map(|album| album.tracks())
+                    .filter(|track| track.artist_ids().contains(id)));
+            }
+        }
+        playable(tracks)
+    }
+
+    fn top_genre_tracks(&self) -> Vec<Rc<Track>> {
+        let model = model();
+        let mut tracks = Vec::new();
+        for (id, _) in self.imp().stats.borrow().top_genres.iter() {
+            if let Some(albums) = model.genre(*id).ok().and_then(|genre| genre.albums()) {
+                tracks.extend(albums.iter()
+                    .flat_map(|album| album.tracks())
+                    .filter(|track| track.genre_ids().contains(id)));
+            }
+        }
+        playable(tracks)
+    }
+}
+
+//Rows of a top list, as (title, subtitle, plays)
+fn fill(list_box: &gtk::ListBox, entries: impl Iterator<Item = (String, Option<String>, u64)>) {
+    while let Some(child) = list_box.first_child() {
+        list_box.remove(&child);
+    }
+
+    for (position, (title, subtitle, plays)) in entries.enumerate() {
+        let row = adw::ActionRow::new();
+        row.set_use_markup(false);
+        row.set_title(&title);
+        if let Some(subtitle) = subtitle {
+            row.set_subtitle(&subtitle);
+        }
+
+        let position_label = gtk::Label::new(Some(&format!("{}", position + 1)));
+        position_label.add_css_class("dim-label");
+        position_label.add_css_class("numeric");
+        position_label.set_width_chars(2);
+        row.add_prefix(&position_label);
+        row.add_suffix(&plays_label(plays));
+
+        list_box.append(&row);
+    }
+}
+
+fn plays_label(plays: u64) -> gtk::Label {
+    // Translators: do not replace {number_of_plays}
+    let label = gtk::Label::new(Some(&i18n_k("{number_of_plays} plays", &[("number_of_plays", &format!("{}", plays))])));
+    label.add_css_class("dim-label");
+    label.add_css_class("numeric");
+    label
+}
+
+//Each track once, without the duplicate copies hidden from the library
+fn playable(tracks: Vec<Rc<Track>>) -> Vec<Rc<Track>> {
+    let model = model();
+    let mut seen = HashSet::new();
+    tracks.into_iter()
+        .filter(|track| !model.is_hidden(track.id()) && seen.insert(track.id()))
+        .collect()
+}
@@ -16,7 +16,7 @@ use crate::model::{
     album::Album,
 };
 use crate::util::{player, seconds_to_string, settings_manager};
-use crate::i18n::i18n_k;
+use crate::i18n::{i18n, i18n_k};
 
 mod imp {
     use super::*;
@@ -152,6 +152,10 @@ impl TrackEntry {
         imp.add_button.set_tooltip_text(Some(&i18n_k("Add {track_title} to Playlist", &[("track_title", &track.title())])));
         
         imp.track_name_label.set_label(&track.title());
+        let credits = track_credits(&track);
+        if !credits.is_empty() {
+            imp.track_name_label.set_tooltip_text(Some(&credits));
+        }
         imp.time_label.set_label(&seconds_to_string(track.duration()));
         
         if disc_n <= 1 {
@@ -220,4 +224,24 @@ impl TrackEntry {
         v
     }
 }
-    
\ No newline at end of file
+
+//Credits & musical details of the track, one per line
+fn track_credits(track: &Track) -> String {
+    let fields = [
+        ("composer", i18n("Composer")),
+        ("conductor", i18n("Conductor")),
+        ("remixer", i18n("Remixer")),
+        ("lyricist", i18n("Lyricist")),
+        ("bpm", i18n("BPM")),
+        ("key", i18n("Key")),
+        ("language", i18n("Language")),
+    ];
+
+    let mut lines = Vec::new();
+    for (key, name) in fields {
+        if let Some(value) = track.tag(key) {
+            lines.push(format!("{}: {}", name, value));
+        }
+    }
+    lines.join("\n")
+}
@@ -8,23 +8,28 @@ use adw::{subclass::prelude::*, Squeezer, ViewSwitcher, ViewSwitcherTitle};
 use gtk::{prelude::*, gdk, gio, glib, glib::{clone, Sender}, CompositeTemplate};
 use gtk_macros::send;
 
-use std::cell::{Cell, RefCell};
+use std::{cell::{Cell, RefCell}, rc::Rc};
 use rand::{thread_rng, Rng};
 use log::{debug, error};
 
 use crate::app::App;
 use crate::database::DatabaseAction;
 use crate::web::{discord::DiscordAction, last_fm::LastFmAction};
+use crate::player::gst_backend::ReplayGainMode;
 use crate::sort::SortMethod;
-use crate::toasts::add_error_toast;
-use crate::util::{model, player, database, get_child_by_index, settings_manager};
-use crate::i18n::i18n;
+use crate::rating::Rating;
+use crate::toasts::{add_error_toast, add_success_toast};
+use crate::util::{self, model, player, database, get_child_by_index, settings_manager};
+use crate::i18n::{i18n, i18n_k};
+use crate::model::track::Track;
+use crate::playlist_file::{self, PlaylistFormat, PLAYLIST_EXTENSIONS};
 
 use super::dialog::{
     save_playlist_dialog::SavePlaylistDialog, 
     add_tracks_to_playlist_dialog::AddToPlaylistDialog,
     delete_playlist_dialog::DeletePlaylistDialog,
     duplicate_playlist_dialog::DuplicatePlaylistDialog,
+    smart_playlist_dialog::SmartPlaylistDialog,
     alpha_dialog::AlphaDialog,
 };
 use super::pages::{
@@ -41,6 +46,7 @@ use super::pages::{
     tracks::track_page::TrackPage,
 };
 use super::control_bar::ControlBar;
+use super::import_progress_list::ImportProgressList;
 
 #[derive(Debug, Clone, Copy, PartialEq, glib::Enum)]
 #[enum_type(name = "WindowPage")]
@@ -65,11 +71,8 @@ mod imp {
     #[derive(Debug, CompositeTemplate)]
     #[template(resource = "/io/github/nate_xyz/Resonance/window.ui")]
     pub struct WindowPriv {
-        #[template_child(id = "welcome_info_label")]
-        pub welcome_info_label: TemplateChild<gtk::Label>,
-
-        #[template_child(id = "welcome_percentage_label")]
-        pub welcome_percentage_label: TemplateChild<gtk::Label>,
+        #[template_child(id = "welcome_imports")]
+        pub welcome_imports: TemplateChild<ImportProgressList>,
 
         #[template_child(id = "switcher-bar")]
         pub view_switcher_bar: TemplateChild<adw::ViewSwitcherBar>,
@@ -170,6 +173,7 @@ mod imp {
         pub window_page: Cell<WindowPage>,
         pub provider: gtk::CssProvider,
         pub folder_dialog: RefCell<Option<gtk::FileChooserNative>>,
+        pub playlist_file_dialog: RefCell<Option<gtk::FileChooserNative>>,
         pub settings: gio::Settings,
         pub db_sender: RefCell<Option<Sender<DatabaseAction>>>,
     }
@@ -190,8 +194,7 @@ mod imp {
 
         fn new() -> Self {
             Self {
-                welcome_info_label: TemplateChild::default(),
-                welcome_percentage_label: TemplateChild::default(),
+                welcome_imports: TemplateChild::default(),
                 view_switcher_bar: TemplateChild::default(),
                 welcome_status: TemplateChild::default(),
                 spinner: TemplateChild::default(),
@@ -230,6 +233,7 @@ mod imp {
                 window_page: Cell::new(WindowPage::default()),
                 provider: gtk::CssProvider::new(),
                 folder_dialog: RefCell::new(None),
+                playlist_file_dialog: RefCell::new(None),
                 settings: settings_manager(),
                 current_css_cover_art_id: Cell::new(-1),
             }
@@ -362,12 +366,7 @@ impl Window {
                 debug!("Notified database loaded in window.");
                 let imp = this.imp();
                 
-                imp.spinner.hide();
-                imp.spinner.stop();
-                imp.add_library_button.show();
-
-                imp.welcome_status.set_title(&i18n("Welcome to Resonance"));
-                imp.welcome_status.set_description(Some(&i18n("Add your music library to get started!")));
+                this.reset_welcome_page();
 
                 if database.imp().loaded.get() {
                     imp.meta_stack.set_visible_child_full(
@@ -384,6 +383,29 @@ impl Window {
 
             })
         );
+
+        database.connect_local(
+            "import-progress",
+            false,
+            clone!(@strong self as this => @default-return None, move |_value| {
+                let database = util::database();
+                if database.imports().is_empty() && !database.imp().loaded.get() {
+                    this.reset_welcome_page();
+                }
+                None
+            }),
+        );
+    }
+
+    //Back to the welcome message once a failed import leaves the library empty
+    fn reset_welcome_page(&self) {
+        let imp = self.imp();
+        imp.spinner.hide();
+        imp.spinner.stop();
+        imp.add_library_button.show();
+
+        imp.welcome_status.set_title(&i18n("Welcome to Resonance"));
+        imp.welcome_status.set_description(Some(&i18n("Add your music library to get started!")));
     }
 
     // Bind the PlayerState to the UI
@@ -421,30 +443,6 @@ impl Window {
         );
     }
 
-    pub fn set_import_message(&self, message: &str) {
-        let imp = self.imp();
-        if let Some(name) = imp.meta_stack.visible_child_name() {
-            if "welcome-stack-page" == name.as_str() {
-                if !imp.welcome_info_label.is_visible() {
-                    imp.welcome_info_label.show()
-                }
-                imp.welcome_info_label.set_label(message);
-            }
-        }
-    }
-
-    pub fn set_import_percentage(&self, message: &str) {
-        let imp = self.imp();
-        if let Some(name) = imp.meta_stack.visible_child_name() {
-            if "welcome-stack-page" == name.as_str() {
-                if !imp.welcome_percentage_label.is_visible() {
-                    imp.welcome_percentage_label.show()
-                }
-                imp.welcome_percentage_label.set_label(message);
-            }
-        }
-    }
-
     fn bind_signals(&self) {
         debug!("bind signals");
 
@@ -462,11 +460,6 @@ impl Window {
 
         imp.meta_stack.connect_notify_local(Some("visible-child"),         
         clone!(@strong self as this => move |stack, _pspec| {
-                let imp = this.imp();
-                if imp.welcome_info_label.is_visible() || imp.welcome_percentage_label.is_visible(){
-                    imp.welcome_info_label.hide();
-                    imp.welcome_percentage_label.hide();
-                }
                 if let Some(name) = stack.visible_child_name() {
                     if name.as_str() == "main-stack-page" {
                         this.show_alpha_message();
@@ -982,14 +975,22 @@ impl Window {
                 return;
             }
 
-            if let Ok(cover_art) = model.cover_art(cover_art_id)  {
-                if let Ok(palette) = cover_art.palette() {
-                    if let Some(palette) = palette {
-                        self.update_color_rgb(palette);
-                        imp.current_css_cover_art_id.set(cover_art_id);
+            if model.cover_art(cover_art_id).is_ok() {
+                imp.current_css_cover_art_id.set(cover_art_id);
+                model.cover_art_cache().palette(cover_art_id, clone!(@weak self as this => move |palette| {
+                    //the cover may have changed while the palette was loading
+                    if this.imp().current_css_cover_art_id.get() != cover_art_id {
                         return;
                     }
-                }
+                    match palette {
+                        Some(palette) => this.update_color_rgb(palette),
+                        None => {
+                            this.imp().current_css_cover_art_id.set(-1);
+                            this.update_color_hex();
+                        },
+                    }
+                }));
+                return;
             }
         }
 
@@ -1272,6 +1273,9 @@ impl Window {
                         1 => SortMethod::LastModified,
                         2 => SortMethod::Duration,
                         3 => SortMethod::TrackCount,
+                        4 => SortMethod::PlayCount,
+                        5 => SortMethod::LastPlayed,
+                        6 => SortMethod::RecentlyAdded,
                         _ => SortMethod::LastModified,
                     };
                     this.set_property("sort-method", sort_method.to_value());
@@ -1289,6 +1293,11 @@ impl Window {
                         3 => SortMethod::Genre,
                         4 => SortMethod::ReleaseDate,
                         5 => SortMethod::Duration,
+                        6 => SortMethod::Bpm,
+                        7 => SortMethod::PlayCount,
+                        8 => SortMethod::LastPlayed,
+                        9 => SortMethod::RecentlyAdded,
+                        10 => SortMethod::Rating,
                         _ => SortMethod::Track,
                     };
                     this.set_property("sort-method", sort_method.to_value());
@@ -1306,6 +1315,10 @@ impl Window {
                         3 => SortMethod::ReleaseDate,
                         4 => SortMethod::Duration,
                         5 => SortMethod::TrackCount,
+                        6 => SortMethod::PlayCount,
+                        7 => SortMethod::LastPlayed,
+                        8 => SortMethod::RecentlyAdded,
+                        9 => SortMethod::Rating,
                         _ => SortMethod::Album,
                     };
                     this.set_property("sort-method", sort_method.to_value());
@@ -1313,6 +1326,15 @@ impl Window {
             })
         );
 
+        let show_compilations = imp.settings.create_action("show-compilations");
+        self.add_action(&show_compilations);
+
+        let album_grid_favorites_only = imp.settings.create_action("album-grid-favorites-only");
+        self.add_action(&album_grid_favorites_only);
+
+        let track_page_favorites_only = imp.settings.create_action("track-page-favorites-only");
+        self.add_action(&track_page_favorites_only);
+
         self.add_simple_action("play-album", Some(glib::VariantTy::INT64), 
         move |_, album_id| {
             if let Some(id) = album_id.and_then(|u| u.get::<i64>()) {
@@ -1445,6 +1467,48 @@ impl Window {
             })
         );
 
+        self.add_simple_action("toggle-favorite-track", Some(glib::VariantTy::INT64), 
+            clone!(@strong self as this => @default-panic, move |_, track_id| {
+                if let Some(id) = track_id.and_then(|u| u.get::<i64>()) {
+                    if let Ok(track) = model().track(id) {
+                        this.rate_track(id, track.rating().toggled_favorite());
+                    }
+                }
+            })
+        );
+
+        //target is (track id, stars)
+        self.add_simple_action("rate-track", Some(glib::VariantTy::new("(xy)").unwrap()), 
+            clone!(@strong self as this => @default-panic, move |_, target| {
+                if let Some((id, stars)) = target.and_then(|u| u.get::<(i64, u8)>()) {
+                    if let Ok(track) = model().track(id) {
+                        this.rate_track(id, track.rating().with_stars(stars));
+                    }
+                }
+            })
+        );
+
+        self.add_simple_action("toggle-favorite-album", Some(glib::VariantTy::INT64), 
+            clone!(@strong self as this => @default-panic, move |_, album_id| {
+                if let Some(id) = album_id.and_then(|u| u.get::<i64>()) {
+                    if let Ok(album) = model().album(id) {
+                        this.rate_album(id, album.rating().toggled_favorite());
+                    }
+                }
+            })
+        );
+
+        //target is (album id, stars)
+        self.add_simple_action("rate-album", Some(glib::VariantTy::new("(xy)").unwrap()), 
+            clone!(@strong self as this => @default-panic, move |_, target| {
+                if let Some((id, stars)) = target.and_then(|u| u.get::<(i64, u8)>()) {
+                    if let Ok(album) = model().album(id) {
+                        this.rate_album(id, album.rating().with_stars(stars));
+                    }
+                }
+            })
+        );
+
 
 
 
@@ -1501,6 +1565,48 @@ impl Window {
         );
 
 
+        self.add_simple_action("new-smart-playlist", None,
+            clone!(@strong self as this => @default-panic, move |_, _| {
+                let dialog = SmartPlaylistDialog::new(None);
+                dialog.set_transient_for(Some(&this));
+                dialog.show();
+            })
+        );
+
+        self.add_simple_action("edit-smart-playlist", Some(glib::VariantTy::INT64),
+        clone!(@strong self as this => @default-panic, move |_, playlist_id| {
+                if let Some(id) = playlist_id.and_then(|u| u.get::<i64>()) {
+                    if let Ok(playlist) = model().playlist(id) {
+                        let dialog = SmartPlaylistDialog::new(Some(playlist));
+                        dialog.set_transient_for(Some(&this));
+                        dialog.show();
+                    }
+                }
+            })
+        );
+
+        self.add_simple_action("import-playlist", None,
+            clone!(@strong self as this => @default-panic, move |_, _| {
+                this.show_import_playlist_dialog();
+            })
+        );
+
+        self.add_simple_action("export-playlist", Some(glib::VariantTy::INT64),
+        clone!(@strong self as this => @default-panic, move |_, playlist_id| {
+                if let Some(id) = playlist_id.and_then(|u| u.get::<i64>()) {
+                    if let Ok(playlist) = model().playlist(id) {
+                        this.show_export_playlist_dialog(playlist.title(), playlist.tracks());
+                    }
+                }
+            })
+        );
+
+        self.add_simple_action("export-queue", None,
+            clone!(@strong self as this => @default-panic, move |_, _| {
+                this.show_export_playlist_dialog(i18n("Queue"), player().queue().tracks());
+            })
+        );
+
         // self.create_action_array('play-playlist-from-track', self.action_play_playlist_from_track)
         self.add_simple_action("play-playlist-from-track", Some(glib::VariantType::new_array(glib::VariantTy::INT64).as_ref()), 
             move |_, array| {
@@ -1576,6 +1682,101 @@ impl Window {
     }
 
 
+    fn show_import_playlist_dialog(&self) {
+        let dialog = gtk::FileChooserNative::builder()
+            .accept_label(&i18n("_Import"))
+            .cancel_label(&i18n("_Cancel"))
+            .modal(true)
+            .title(&i18n("Import Playlist"))
+            .action(gtk::FileChooserAction::Open)
+            .select_multiple(false)
+            .transient_for(self)
+            .build();
+
+        let filter = gtk::FileFilter::new();
+        gtk::FileFilter::set_name(&filter, Some(&i18n("Playlist files")));
+        for extension in PLAYLIST_EXTENSIONS {
+            filter.add_suffix(extension);
+        }
+        dialog.add_filter(&filter);
+
+        dialog.connect_response(clone!(@weak self as this => move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    debug!("DIALOG PLAYLIST RECEIVED: {:?}", path);
+                    if let Some(sender) = this.imp().db_sender.borrow().as_ref() {
+                        send!(sender, DatabaseAction::ImportPlaylistFile(path));
+                    }
+                }
+            } else {
+                debug!("No playlist file selected.");
+            }
+        }));
+
+        dialog.show();
+        self.imp().playlist_file_dialog.replace(Some(dialog));
+    }
+
+    //The format & whether paths are relative to the playlist file are picked in the file chooser
+    fn show_export_playlist_dialog(&self, title: String, tracks: Vec<Rc<Track>>) {
+        if tracks.is_empty() {
+            add_error_toast(i18n("Nothing to export, the playlist is empty."));
+            return;
+        }
+
+        let dialog = gtk::FileChooserNative::builder()
+            .accept_label(&i18n("_Export"))
+            .cancel_label(&i18n("_Cancel"))
+            .modal(true)
+            .title(&i18n("Export Playlist"))
+            .action(gtk::FileChooserAction::Save)
+            .select_multiple(false)
+            .transient_for(self)
+            .build();
+
+        let formats: Vec<(&str, &str)> = PlaylistFormat::all().iter()
+            .map(|format| (format.extension(), format.label()))
+            .collect();
+        dialog.add_choice("format", &i18n("Format"), &formats);
+        dialog.set_choice("format", PlaylistFormat::M3u8.extension());
+        dialog.add_choice("relative", &i18n("Relative paths"), &[]);
+        dialog.set_choice("relative", "true");
+        dialog.set_current_name(&format!("{}.{}", title.replace('/', "-"), PlaylistFormat::M3u8.extension()));
+
+        dialog.connect_response(move |dialog, response| {
+            if response != gtk::ResponseType::Accept {
+                debug!("No export file selected.");
+                return;
+            }
+
+            let path = match dialog.file().and_then(|file| file.path()) {
+                Some(path) => path,
+                None => return,
+            };
+
+            let format = dialog.choice("format")
+                .and_then(|id| PlaylistFormat::from_extension(&id))
+                .unwrap_or(PlaylistFormat::M3u8);
+            let relative = matches!(dialog.choice("relative"), Some(choice) if choice == "true");
+            //the extension follows the picked format
+            let path = path.with_extension(format.extension());
+
+            match playlist_file::write_playlist(&path, format, &title, &tracks, relative) {
+                Ok(_) => {
+                    // Translators: do not replace {playlist_title} or {file}
+                    add_success_toast(&i18n("Exported:"), &i18n_k("«{playlist_title}» saved to {file}.", &[("playlist_title", &title), ("file", &format!("{}", path.display()))]))
+                },
+                Err(e) => {
+                    error!("Unable to export playlist to {:?}: {}", path, e);
+                    add_error_toast(i18n("Unable to export playlist."));
+                },
+            }
+        });
+
+        dialog.show();
+        self.imp().playlist_file_dialog.replace(Some(dialog));
+    }
+
     pub fn add_simple_action<F>(&self, name: &str, param: Option<&glib::VariantTy>, f: F) 
     where
     F: Fn(&gio::SimpleAction, Option<&glib::Variant>) + 'static, {
@@ -1594,6 +1795,14 @@ impl Window {
 
         self.set_window_size();
 
+        imp.settings.connect_changed(
+            Some("sort-articles"),
+            move |_settings, _name| {
+                util::reset_sort_articles();
+                model().update_sort_keys();
+            },
+        );
+
         imp.settings.connect_changed(
             Some("album-grid-sort"),
             clone!(@strong imp.album_grid_page as this => move |settings, _name| {
@@ -1605,6 +1814,10 @@ impl Window {
                     3 => SortMethod::ReleaseDate,
                     4 => SortMethod::Duration,
                     5 => SortMethod::TrackCount,
+                    6 => SortMethod::PlayCount,
+                    7 => SortMethod::LastPlayed,
+                    8 => SortMethod::RecentlyAdded,
+                    9 => SortMethod::Rating,
                     _ => SortMethod::Album,
                 };
                 this.set_property("sort-method", sort_method.to_value());
@@ -1619,6 +1832,10 @@ impl Window {
             3 => SortMethod::ReleaseDate,
             4 => SortMethod::Duration,
             5 => SortMethod::TrackCount,
+            6 => SortMethod::PlayCount,
+            7 => SortMethod::LastPlayed,
+            8 => SortMethod::RecentlyAdded,
+            9 => SortMethod::Rating,
             _ => SortMethod::Album,
         };
         imp.album_grid_page.set_property("sort-method", sort_method.to_value());
@@ -1643,6 +1860,11 @@ impl Window {
                     3 => SortMethod::Genre,
                     4 => SortMethod::ReleaseDate,
                     5 => SortMethod::Duration,
+                    6 => SortMethod::Bpm,
+                    7 => SortMethod::PlayCount,
+                    8 => SortMethod::LastPlayed,
+                    9 => SortMethod::RecentlyAdded,
+                    10 => SortMethod::Rating,
                     _ => SortMethod::Track,
                 };
                 this.set_property("sort-method", sort_method.to_value());
@@ -1657,6 +1879,11 @@ impl Window {
             3 => SortMethod::Genre,
             4 => SortMethod::ReleaseDate,
             5 => SortMethod::Duration,
+            6 => SortMethod::Bpm,
+            7 => SortMethod::PlayCount,
+            8 => SortMethod::LastPlayed,
+            9 => SortMethod::RecentlyAdded,
+            10 => SortMethod::Rating,
             _ => SortMethod::Track,
         };
         imp.track_page.set_property("sort-method", sort_method.to_value());
@@ -1680,6 +1907,9 @@ impl Window {
                     1 => SortMethod::LastModified,
                     2 => SortMethod::Duration,
                     3 => SortMethod::TrackCount,
+                    4 => SortMethod::PlayCount,
+                    5 => SortMethod::LastPlayed,
+                    6 => SortMethod::RecentlyAdded,
                     _ => SortMethod::LastModified,
                 };
                 this.set_property("sort-method", sort_method.to_value());
@@ -1692,6 +1922,9 @@ impl Window {
             1 => SortMethod::LastModified,
             2 => SortMethod::Duration,
             3 => SortMethod::TrackCount,
+            4 => SortMethod::PlayCount,
+            5 => SortMethod::LastPlayed,
+            6 => SortMethod::RecentlyAdded,
             _ => SortMethod::LastModified,
         };
         imp.playlist_grid_page.set_property("sort-method", sort_method.to_value());
@@ -1875,6 +2108,40 @@ impl Window {
                 player.commit_threshold.set(threshold);         
             },
         );
+
+        imp.settings.connect_changed(
+            Some("replaygain-mode"),
+            move |settings, _name| {
+                let mode = ReplayGainMode::new_from_settings(settings.int("replaygain-mode"));
+                player().replaygain_mode.set(mode);
+            },
+        );
+
+        imp.settings.connect_changed(
+            Some("replaygain-preamp"),
+            move |settings, _name| {
+                let preamp = settings.double("replaygain-preamp");
+                player().replaygain_preamp.set(preamp);
+            },
+        );
+    }
+
+    //The playing track is published again over MPRIS with its new rating
+    fn rate_track(&self, track_id: i64, rating: Rating) {
+        match model().set_track_rating(track_id, rating) {
+            Ok(_) => player().mpris().refresh_rating(track_id),
+            Err(e) => {
+                error!("Unable to rate track {}: {}", track_id, e);
+                add_error_toast(i18n("Unable to save the rating."));
+            },
+        }
+    }
+
+    fn rate_album(&self, album_id: i64, rating: Rating) {
+        if let Err(e) = model().set_album_rating(album_id, rating) {
+            error!("Unable to rate album {}: {}", album_id, e);
+            add_error_toast(i18n("Unable to save the rating."));
+        }
     }
 
     fn set_window_size(&self) {
@@ -0,0 +1,149 @@
+/* watcher.rs
+ *
+ * SPDX-FileCopyrightText: 2023 nate-xyz
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::{gio, glib, glib::{Sender, SourceId}, prelude::*};
+use gtk_macros::send;
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::Path,
+    rc::Rc,
+    time::Duration,
+};
+use log::{debug, error};
+
+use super::database::DatabaseAction;
+use super::util;
+
+//Time to wait after the last file event before the queued changes are applied
+const DEBOUNCE_SECONDS: u64 = 3;
+
+//Watches every directory of the music folders, queuing changed paths until the folder settles
+#[derive(Debug)]
+pub struct LibraryWatcher {
+    monitors: RefCell<HashMap<String, gio::FileMonitor>>,
+    pending: RefCell<HashMap<String, HashSet<String>>>,
+    timeout: Rc<RefCell<Option<SourceId>>>,
+    sender: Sender<DatabaseAction>,
+}
+
+impl LibraryWatcher {
+    pub fn new(sender: Sender<DatabaseAction>) -> Self {
+        Self {
+            monitors: RefCell::new(HashMap::new()),
+            pending: RefCell::new(HashMap::new()),
+            timeout: Rc::new(RefCell::new(None)),
+            sender,
+        }
+    }
+
+    pub fn watch_folder(&self, folder: &str) {
+        self.watch_tree(folder, Path::new(folder));
+    }
+
+    //Watches a directory and every directory beneath it, the changes are queued for the music folder
+    pub fn watch_tree(&self, folder: &str, root: &Path) {
+        let (dirs, _files) = util::walk_folder(root);
+        for dir in dirs {
+            self.watch_directory(folder, &dir);
+        }
+    }
+
+    fn watch_directory(&self, folder: &str, dir: &Path) {
+        let dir_str = match dir.to_str() {
+            Some(s) => s.to_string(),
+            None => return,
+        };
+
+        if self.monitors.borrow().contains_key(&dir_str) {
+            return;
+        }
+
+        let monitor = match gio::File::for_path(dir).monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+            Ok(monitor) => monitor,
+            Err(e) => {
+                error!("Unable to watch {}: {}", dir_str, e);
+                return;
+            },
+        };
+
+        let folder = folder.to_string();
+        let sender = self.sender.clone();
+        monitor.connect_changed(move |_monitor, file, other_file, event| {
+            let mut changed = Vec::new();
+            match event {
+                gio::FileMonitorEvent::Created
+                | gio::FileMonitorEvent::Deleted
+                | gio::FileMonitorEvent::ChangesDoneHint
+                | gio::FileMonitorEvent::MovedIn
+                | gio::FileMonitorEvent::MovedOut => {
+                    changed.push(file.path());
+                },
+                gio::FileMonitorEvent::Renamed => {
+                    changed.push(file.path());
+                    changed.push(other_file.and_then(|f| f.path()));
+                },
+                _ => (),
+            }
+
+            for path in changed.into_iter().flatten() {
+                if let Some(path) = path.to_str() {
+                    send!(sender, DatabaseAction::QueueFileChange((folder.clone(), path.to_string())));
+                }
+            }
+        });
+
+        debug!("watching {}", dir_str);
+        self.monitors.borrow_mut().insert(dir_str, monitor);
+    }
+
+    //Stops watching a directory and every directory beneath it
+    pub fn unwatch(&self, path: &str) {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        self.monitors.borrow_mut().retain(|dir, monitor| {
+            if dir == path || dir.starts_with(&prefix) {
+                monitor.cancel();
+                false
+            } else {
+                true
+            }
+        });
+        self.pending.borrow_mut().remove(path);
+    }
+
+    pub fn unwatch_all(&self) {
+        for (_dir, monitor) in self.monitors.borrow_mut().drain() {
+            monitor.cancel();
+        }
+        self.pending.borrow_mut().clear();
+        if let Some(source) = self.timeout.take() {
+            source.remove();
+        }
+    }
+
+    //Restarts the debounce timer on every change, so a large copy is applied once it finishes
+    pub fn queue_change(&self, folder: String, path: String) {
+        self.pending.borrow_mut().entry(folder).or_default().insert(path);
+
+        if let Some(source) = self.timeout.take() {
+            source.remove();
+        }
+
+        let sender = self.sender.clone();
+        let timeout = self.timeout.clone();
+        let source = glib::timeout_add_local_once(Duration::from_secs(DEBOUNCE_SECONDS), move || {
+            //the source is destroyed once it fires, it must not be removed again
+            _ = timeout.take();
+            send!(sender, DatabaseAction::ApplyFileChanges);
+        });
+        self.timeout.replace(Some(source));
+    }
+
+    pub fn take_pending(&self) -> HashMap<String, HashSet<String>> {
+        self.pending.take()
+    }
+}
@@ -11,12 +11,13 @@
 use gtk_macros::send;
 
 use std::{cell::RefCell, thread};
-use std::{collections::{HashSet, HashMap}, rc::Rc};
-use log::error;
+use std::{collections::HashMap, rc::Rc};
+use log::{debug, error};
 use reqwest;
 use serde::{Deserialize, Serialize};
 
 use crate::model::track::Track;
+use crate::util::database;
 
 
 #[derive(Clone, Debug)]
@@ -26,8 +27,7 @@ pub enum MusicBrainzAction {
 
 #[derive(Clone, Debug)]
 pub enum ThreadedMusicBrainzAction {
-    MusicBrainzId((bool, i64, Option<Vec<String>>)),
-    ValidArtUrl((bool, u32, i64, Vec<String>, Option<String>)),
+    ArtPath((i64, Result<Option<String>, String>)),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -42,14 +42,15 @@ struct Release {
 
 #[derive(Debug)]
 pub struct ResonanceMusicBrainz {
-    id_cache: RefCell<HashMap<i64, Vec<String>>>,
-    url_cache: RefCell<HashMap<(Vec<String>, u32), String>>,
+    //Cover Art Archive path of each album, ie "release/<mbid>", None if it has no art
+    art_cache: RefCell<HashMap<i64, Option<String>>>,
     mb_receiver: RefCell<Option<Receiver<MusicBrainzAction>>>,
     tx: Sender<ThreadedMusicBrainzAction>,
     id_receiver: RefCell<Option<Receiver<ThreadedMusicBrainzAction>>>,
     sender_mb_mpris: Sender<(i64, String)>,
     sender_mb_discord: Sender<(i64, Option<String>)>,
-    thread_spawned_already: RefCell<HashSet<i64>>,
+    //Albums being looked up, with whether each waiting request came from mpris
+    pending: RefCell<HashMap<i64, Vec<bool>>>,
 }
 
 impl ResonanceMusicBrainz {
@@ -59,18 +60,15 @@ impl ResonanceMusicBrainz {
         sender_mb_discord: Sender<(i64, Option<String>)>) -> Rc<Self> {
         let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_LOW);
         let mbz = Self {
-            id_cache: RefCell::new(HashMap::new()),
-            url_cache: RefCell::new(HashMap::new()),
+            art_cache: RefCell::new(HashMap::new()),
             mb_receiver: RefCell::new(Some(receiver)),
             tx,
             id_receiver: RefCell::new(Some(rx)),
             sender_mb_mpris,
             sender_mb_discord,
-            thread_spawned_already: RefCell::new(HashSet::new()),
+            pending: RefCell::new(HashMap::new()),
         };
 
-        // mbz.receiver.replace(Some(receiver));
-
         let mb = Rc::new(mbz);
         mb.clone().setup_channels();
         mb
@@ -102,191 +100,161 @@ impl ResonanceMusicBrainz {
 
     fn process_internal_mb_action(&self, action: ThreadedMusicBrainzAction) -> glib::Continue {
         match action {
-            ThreadedMusicBrainzAction::MusicBrainzId((is_mpris, album_id, mb_ids)) => {
-                self.receive_id(is_mpris, album_id, mb_ids)
-            }
-            ThreadedMusicBrainzAction::ValidArtUrl((is_mpris, size, album_id, mb_ids, art_url)) => {
-                if is_mpris {
-                    if let Some(url) = art_url {
-                        send!(self.sender_mb_mpris, (album_id, url.clone()));
-                        self.url_cache.borrow_mut().insert((mb_ids.clone(), size), url);
-                    }
-                } else {
-                    if let Some(url) = art_url {
-                        send!(self.sender_mb_discord, (album_id, Some(url.clone())));
-                        self.url_cache.borrow_mut().insert((mb_ids.clone(), size), url);
-                    } else {
-                        send!(self.sender_mb_discord, (album_id, None))
-                    }
+            ThreadedMusicBrainzAction::ArtPath((album_id, result)) => {
+                let art_path = match result {
+                    Ok(art_path) => {
+                        //only completed lookups are saved, so each album is looked up once
+                        if let Err(e) = database().add_musicbrainz_cover_art(album_id, art_path.clone()) {
+                            error!("Unable to save cover art lookup of album {}: {}", album_id, e);
+                        }
+                        self.art_cache.borrow_mut().insert(album_id, art_path.clone());
+                        art_path
+                    },
+                    Err(msg) => {
+                        error!("MusicBrainz lookup of album {} failed: {}", album_id, msg);
+                        None
+                    },
+                };
+
+                let waiting = self.pending.borrow_mut().remove(&album_id).unwrap_or_default();
+                for is_mpris in waiting {
+                    self.send_art(is_mpris, album_id, art_path.clone());
                 }
-                self.thread_spawned_already.borrow_mut().remove(&album_id);
             }
         }
 
         glib::Continue(true)
     }
 
-    // Searches for a release on MusicBrainz
-    // Returns its ID if one is found.
+    // Finds the Cover Art Archive art of the album of the track,
+    // by the ids tagged in the files, or by searching for the release.
     pub fn find_release(&self, track: Rc<Track>, is_mpris: bool) {
         let album_id = track.album_id();
-        let artist = track.artist();
-        let album = track.album();
 
-        if self.id_cache.borrow().contains_key(&album_id) {
-            //debug!("{} already in cache", album_id);
-            let art_id = self.id_cache.borrow().get(&album_id).cloned();
-            if let Some(music_brainz_ids) = art_id {
-                if is_mpris {
-                    let key = &(music_brainz_ids.clone(), 500);
-
-                    if self.url_cache.borrow().contains_key(key) {
-                        //debug!("{} already in url cache", album_id);
-                        let art_url = self.url_cache.borrow().get(key).cloned();
-                        if let Some(url) = art_url {
-                            send!(self.sender_mb_mpris, (album_id, url))
-                        }
-                        return;
-                    }
-
-                    if !self.thread_spawned_already.borrow().contains(&album_id) {
-                        self.thread_spawned_already.borrow_mut().insert(album_id);
-
-                        let sender = self.tx.clone();
-
-                        thread::spawn(move || {
-                            let art_url = first_valid_art_url(music_brainz_ids.clone(), 500);
-                            send!(sender, ThreadedMusicBrainzAction::ValidArtUrl((is_mpris, 500, album_id, music_brainz_ids, art_url)));
-                        });
-
-                    }
-                    // if let Some(url) = first_valid_art_url(music_brainz_ids, 500) {
-                    //     send!(self.sender_mb_mpris, (album_id, url));
-                    // }
-                    return;
-                } else {
-                    let key = &(music_brainz_ids.clone(), 250);
-
-                    if self.url_cache.borrow().contains_key(key) {
-                        //debug!("{} already in url cache", album_id);
-                        let art_url = self.url_cache.borrow().get(key).cloned();
-                        send!(self.sender_mb_discord, (album_id, art_url));
-                        return;
-                    }
-
-                    if !self.thread_spawned_already.borrow().contains(&album_id) {
-                        self.thread_spawned_already.borrow_mut().insert(album_id);
-
-                        let sender = self.tx.clone();
-
-                        thread::spawn(move || {
-                            let art_url = first_valid_art_url(music_brainz_ids.clone(), 250);
-                            send!(sender, ThreadedMusicBrainzAction::ValidArtUrl((is_mpris, 250, album_id, music_brainz_ids, art_url)));
-                        });
-                    }
-                    return;
-                }
+        let cached = self.art_cache.borrow().get(&album_id).cloned();
+        if let Some(art_path) = cached {
+            self.send_art(is_mpris, album_id, art_path);
+            return;
+        }
+
+        let (release_id, release_group_id) = match database().query_album_musicbrainz(album_id) {
+            Ok((_, _, Some(art_path))) => {
+                debug!("cover art of album {} looked up before: {:?}", album_id, art_path);
+                self.art_cache.borrow_mut().insert(album_id, art_path.clone());
+                self.send_art(is_mpris, album_id, art_path);
+                return;
+            },
+            Ok((release_id, release_group_id, None)) => (release_id, release_group_id),
+            Err(e) => {
+                error!("Unable to query musicbrainz ids of album {}: {}", album_id, e);
+                (String::new(), String::new())
+            },
+        };
+
+        if let Some(waiting) = self.pending.borrow_mut().get_mut(&album_id) {
+            if !waiting.contains(&is_mpris) {
+                waiting.push(is_mpris);
             }
+            return;
         }
+        self.pending.borrow_mut().insert(album_id, vec![is_mpris]);
 
+        let artist = track.album_artist();
+        let album = track.album();
         let sender = self.tx.clone();
 
         thread::spawn(move || {
-            let music_brainz_id = music_brainz_id(artist, album);
-            send!(sender, ThreadedMusicBrainzAction::MusicBrainzId((is_mpris, album_id, music_brainz_id)));
+            let result = find_art_path(release_id, release_group_id, artist, album);
+            send!(sender, ThreadedMusicBrainzAction::ArtPath((album_id, result)));
         });
     }
 
-    fn receive_id(&self, is_mpris: bool, album_id: i64, music_brainz_id: Option<Vec<String>>) {
-        if let Some(id) = music_brainz_id.clone() {
-            self.id_cache.borrow_mut().insert(album_id, id);
-        }
-        let sender = self.tx.clone();
-
+    fn send_art(&self, is_mpris: bool, album_id: i64, art_path: Option<String>) {
         if is_mpris {
-            if let Some(ids) = music_brainz_id {
-                if !self.thread_spawned_already.borrow().contains(&album_id) {
-                    self.thread_spawned_already.borrow_mut().insert(album_id);
-                    thread::spawn(move || {
-                        let art_url = first_valid_art_url(ids.clone(), 500);
-                        send!(sender, ThreadedMusicBrainzAction::ValidArtUrl((is_mpris, 500, album_id, ids, art_url)));
-                    });
-                }
+            if let Some(path) = art_path {
+                send!(self.sender_mb_mpris, (album_id, get_album_art_url(&path, 500)));
             }
         } else {
-            if let Some(ids) = music_brainz_id {
-                if !self.thread_spawned_already.borrow().contains(&album_id) {
-                    self.thread_spawned_already.borrow_mut().insert(album_id);
-                    thread::spawn(move || {
-                        let art_url = first_valid_art_url(ids.clone(), 250);
-                        send!(sender, ThreadedMusicBrainzAction::ValidArtUrl((is_mpris, 250, album_id, ids, art_url)));
-                    });
-                }
-            } else {
-                send!(self.sender_mb_discord, (album_id, None))
-            }
+            send!(self.sender_mb_discord, (album_id, art_path.map(|path| get_album_art_url(&path, 250))));
         }
     }
 }
 
-fn music_brainz_id(artist: String, album: String) -> Option<Vec<String>> {
+fn client() -> Result<reqwest::blocking::Client, String> {
     //static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
     static APP_USER_AGENT: &str = concat!(
         "io.github.nate_xyz.Resonance", "/", env!("CARGO_PKG_VERSION"));
 
-    let query = format!("trackartist:{} AND release:{}", &artist, &album);
-
-    let url = format!("https://musicbrainz.org/ws/2/release/?query={}&limit=2", query);
-
-    let client = reqwest::blocking::Client::builder()
+    reqwest::blocking::Client::builder()
         .user_agent(APP_USER_AGENT)
         .build()
-        .expect("Failed to create HTTP client");
+        .map_err(|e| e.to_string())
+}
 
-    let response = client.get(&url).header("Accept", "application/json").send();
+// Tagged release & release group ids are tried first,
+// untagged albums fall back on a search by album artist and title.
+fn find_art_path(release_id: String, release_group_id: String, artist: String, album: String) -> Result<Option<String>, String> {
+    let client = client()?;
 
-    if let Ok(response) = response {
-        if response.status() != 200 {
-            error!("Status, {}", response.status());
-            return None;
+    let mut art_paths = Vec::new();
+    if !release_id.is_empty() {
+        art_paths.push(format!("release/{}", release_id));
+    }
+    if !release_group_id.is_empty() {
+        art_paths.push(format!("release-group/{}", release_group_id));
+    }
+    if art_paths.is_empty() {
+        for id in music_brainz_id(&client, &artist, &album)? {
+            art_paths.push(format!("release/{}", id));
         }
+    }
 
-        let response = response
-            .json::<SearchResult>()
-            .expect("Received response from MusicBrainz in unexpected format");
-
-        let mut all_responses = Vec::new();
-        for r in response.releases {
-            all_responses.push(r.id.clone())
+    for art_path in art_paths {
+        if has_front_art(&client, &art_path)? {
+            return Ok(Some(art_path));
         }
+    }
 
-        if !all_responses.is_empty() {
-            return Some(all_responses);
-        } else {
-            return None;
-        }
+    Ok(None)
+}
+
+fn music_brainz_id(client: &reqwest::blocking::Client, artist: &str, album: &str) -> Result<Vec<String>, String> {
+    let query = format!("trackartist:{} AND release:{}", artist, album);
+
+    let url = format!("https://musicbrainz.org/ws/2/release/?query={}&limit=2", urlencoding::encode(&query));
+
+    let response = client.get(&url)
+        .header("Accept", "application/json")
+        .send()
+        .map_err(|e| e.to_string())?;
+
+    if response.status() != 200 {
+        return Err(format!("Status, {}", response.status()));
     }
 
-    None
+    let response = response
+        .json::<SearchResult>()
+        .map_err(|e| format!("Received response from MusicBrainz in unexpected format: {}", e))?;
+
+    Ok(response.releases.into_iter().map(|r| r.id).collect())
 }
 
-fn first_valid_art_url(release_ids: Vec<String>, size: u32) -> Option<String> {
-    for release_id in release_ids {
-        let art_url = get_album_art_url(release_id, size);
-        let response = reqwest::blocking::get(art_url.clone()).unwrap();
-        if response.status() == reqwest::StatusCode::NOT_FOUND {
-            continue;
-        } else {
-            return Some(art_url);
-        }
+fn has_front_art(client: &reqwest::blocking::Client, art_path: &str) -> Result<bool, String> {
+    let response = client.head(get_album_art_url(art_path, 250))
+        .send()
+        .map_err(|e| e.to_string())?;
+
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => Ok(false),
+        status if status.is_success() => Ok(true),
+        status => Err(format!("Status, {}", status)),
     }
-    None
 }
 
-fn get_album_art_url(release_id: String, size: u32) -> String {
+fn get_album_art_url(art_path: &str, size: u32) -> String {
     format!(
-        "https://coverartarchive.org/release/{}/front-{}",
-        release_id, size
+        "https://coverartarchive.org/{}/front-{}",
+        art_path, size
     )
 }
@@ -0,0 +1,4 @@
+// This code was autogenerated with dbus-codegen-rust, see https://github.com/diwic/dbus-rs
+
+pub mod mediaplayer2;
+pub mod mediaplayer2_player;
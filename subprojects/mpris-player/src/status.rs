@@ -0,0 +1,33 @@
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+impl PlaybackStatus {
+    pub fn value(&self) -> String {
+        match *self {
+            PlaybackStatus::Playing => "Playing".to_string(),
+            PlaybackStatus::Paused => "Paused".to_string(),
+            PlaybackStatus::Stopped => "Stopped".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoopStatus {
+    None,
+    Track,
+    Playlist,
+}
+
+impl LoopStatus {
+    pub fn value(&self) -> String {
+        match *self {
+            LoopStatus::None => "None".to_string(),
+            LoopStatus::Track => "Track".to_string(),
+            LoopStatus::Playlist => "Playlist".to_string(),
+        }
+    }
+}